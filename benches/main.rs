@@ -2,8 +2,12 @@ use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use std::sync::Arc;
 use std::time::Duration;
 use swb_sys_monitor::cache::{SystemStatsCache, create_cache};
+use swb_sys_monitor::proc_file::ProcFile;
 use swb_sys_monitor::server::StatusServer;
-use swb_sys_monitor::stats::{CpuStats, CpuUsageBreakdown, SystemStats, collect_system_stats};
+use swb_sys_monitor::stats::{
+    Collector, CoreTimes, CpuStats, CpuTimes, CpuUsageBreakdown, SystemStats, collect_system_stats,
+    parse_proc_stat,
+};
 use tokio::runtime::Runtime;
 
 fn create_test_stats(hostname: &str, cpu_usage: f32) -> SystemStats {
@@ -19,6 +23,7 @@ fn create_test_stats(hostname: &str, cpu_usage: f32) -> SystemStats {
             },
             per_core: Vec::new(),
             core_count: 0,
+            core_ids: None,
         },
         memory_total: 1024 * 1024 * 1024,    // 1GB
         memory_used: 512 * 1024 * 1024,      // 512MB
@@ -26,6 +31,7 @@ fn create_test_stats(hostname: &str, cpu_usage: f32) -> SystemStats {
         memory_cached: 128 * 1024 * 1024,    // 128MB
         memory_free: 128 * 1024 * 1024,      // 128MB
         timestamp: std::time::Instant::now(),
+        ..SystemStats::default()
     }
 }
 
@@ -99,6 +105,7 @@ fn bench_html_rendering(c: &mut Criterion) {
                 },
                 per_core: Vec::new(),
                 core_count: 0,
+                core_ids: None,
             },
             memory_total: 16 * 1024 * 1024 * 1024,    // 16GB
             memory_used: 8 * 1024 * 1024 * 1024,      // 8GB
@@ -106,6 +113,7 @@ fn bench_html_rendering(c: &mut Criterion) {
             memory_cached: 2 * 1024 * 1024 * 1024,    // 2GB
             memory_free: 2 * 1024 * 1024 * 1024,      // 2GB
             timestamp: std::time::Instant::now(),
+            ..SystemStats::default()
         };
 
         b.iter(|| {
@@ -128,6 +136,38 @@ fn bench_system_stats_collection(c: &mut Criterion) {
     });
 }
 
+fn bench_proc_file_reuse(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    // 每次 open/read/close（同步读取，排除线程池调度的影响）
+    c.bench_function("proc_stat_read_reopen", |b| {
+        b.iter(|| black_box(std::fs::read_to_string("/proc/stat").unwrap()))
+    });
+
+    c.bench_function("proc_stat_read_tokio", |b| {
+        b.iter(|| {
+            rt.block_on(async { black_box(tokio::fs::read_to_string("/proc/stat").await.unwrap()) })
+        })
+    });
+
+    c.bench_function("proc_stat_read_reused_fd", |b| {
+        let file = ProcFile::new("/proc/stat");
+        b.iter(|| black_box(file.read_to_string().unwrap()))
+    });
+
+    for reuse in [false, true] {
+        let collector = Collector::default().with_reused_fds(reuse);
+        let name = if reuse {
+            "collect_reused_fds"
+        } else {
+            "collect_reopen"
+        };
+        c.bench_function(name, |b| {
+            b.iter(|| rt.block_on(async { black_box(collector.collect().await.unwrap()) }))
+        });
+    }
+}
+
 fn bench_memory_allocation(c: &mut Criterion) {
     c.bench_function("string_allocation_with_capacity", |b| {
         b.iter(|| {
@@ -159,12 +199,112 @@ fn bench_cache_ttl_precision(c: &mut Criterion) {
     });
 }
 
+/// 生成指定核心数的 /proc/stat 内容
+fn proc_stat_fixture(cores: usize) -> String {
+    let mut content = String::from("cpu  74608 2520 24433 1117073 6176 4054 0 0 0 0\n");
+    for i in 0..cores {
+        content.push_str(&format!(
+            "cpu{i} {} {} {} {} {} {} {} 0 0 0\n",
+            18000 + i * 7,
+            600 + i,
+            6100 + i * 3,
+            279000 + i * 11,
+            1500 + i,
+            1000 + i,
+            20 + i
+        ));
+    }
+    content.push_str("intr 20017863 31 9 0 0 0 0 0 0 0\nctxt 35852376\nbtime 1700000000\n");
+    content
+}
+
+/// 基于 `str::parse` 的原实现，作为快速解析的对照基准
+fn parse_proc_stat_std(content: &str) -> (CpuTimes, Vec<CoreTimes>) {
+    let mut overall = CpuTimes::default();
+    let mut per_core = Vec::new();
+    for line in content.lines().filter(|line| line.starts_with("cpu")) {
+        let mut parts = line.split_whitespace();
+        let label = parts.next().unwrap_or("");
+        let mut next = || parts.next().unwrap_or("0").parse::<u64>().unwrap_or(0);
+        let (user, nice, system, idle) = (next(), next(), next(), next());
+        let (iowait, irq, softirq) = (next(), next(), next());
+        let times = CpuTimes {
+            user,
+            nice,
+            system,
+            idle,
+            iowait,
+            irq,
+            softirq,
+            total: user + nice + system + idle + iowait + irq + softirq,
+        };
+        if label == "cpu" {
+            overall = times;
+        } else if let Some(id) = label.strip_prefix("cpu").and_then(|n| n.parse().ok()) {
+            per_core.push((id, times));
+        }
+    }
+    (overall, per_core)
+}
+
+fn bench_proc_stat_parsing(c: &mut Criterion) {
+    let content = proc_stat_fixture(256);
+
+    c.bench_function("proc_stat_parse_256_cores", |b| {
+        b.iter(|| black_box(parse_proc_stat(black_box(&content))))
+    });
+
+    c.bench_function("proc_stat_parse_256_cores_std", |b| {
+        b.iter(|| black_box(parse_proc_stat_std(black_box(&content))))
+    });
+}
+
+/// 单线程运行时（模拟单核设备）上，监听器数量对新建连接请求延迟的影响
+fn bench_multi_listener(c: &mut Criterion) {
+    for listeners in [1, 16] {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let addrs = vec!["127.0.0.1:0".parse().unwrap(); listeners];
+        let server = {
+            let _guard = rt.enter();
+            StatusServer::new_with_ttl(create_cache(10), 10)
+                .bind(&addrs)
+                .unwrap()
+        };
+        let target: hyper::Uri = format!("http://{}/health", server.local_addrs()[listeners - 1])
+            .parse()
+            .unwrap();
+        rt.spawn(server.serve());
+        // 不复用连接，每次请求都经过 accept
+        let client = hyper::Client::builder()
+            .pool_max_idle_per_host(0)
+            .build_http::<hyper::Body>();
+
+        c.bench_function(
+            &format!("new_connection_request_{listeners}_listeners"),
+            |b| {
+                b.iter(|| {
+                    rt.block_on(async {
+                        let response = client.get(target.clone()).await.unwrap();
+                        black_box(hyper::body::to_bytes(response.into_body()).await.unwrap());
+                    })
+                })
+            },
+        );
+    }
+}
+
 criterion_group!(
     benches,
+    bench_proc_stat_parsing,
     bench_cache_operations,
     bench_html_rendering,
     bench_system_stats_collection,
+    bench_proc_file_reuse,
     bench_memory_allocation,
-    bench_cache_ttl_precision
+    bench_cache_ttl_precision,
+    bench_multi_listener
 );
 criterion_main!(benches);
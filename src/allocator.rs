@@ -0,0 +1,107 @@
+//! jemalloc 分配器统计
+//!
+//! 启用 `jemalloc` feature 时二进制以 jemalloc 作为全局分配器，这里通过其 stats 接口读取
+//! 监控进程自身的分配情况，用于判断进程内存是否持续增长以及增长来自实际分配还是碎片。
+//!
+//! 统计只反映经 jemalloc 分配的内存：作为库使用时需由调用方把
+//! `tikv_jemallocator::Jemalloc` 设为 `#[global_allocator]`，否则读到的只是 jemalloc
+//! 自身近乎为空的分区。
+
+use anyhow::Result;
+use serde::Serialize;
+use tikv_jemalloc_ctl::{epoch, stats};
+
+/// 分配器统计快照（字节）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AllocatorStats {
+    /// 应用实际申请且尚未释放的字节数
+    #[serde(rename = "allocated_bytes")]
+    pub allocated: usize,
+    /// 已分配对象所在的活跃页字节数，不小于 `allocated`
+    #[serde(rename = "active_bytes")]
+    pub active: usize,
+    /// 分配器占用的物理内存字节数（含元数据与未归还的脏页）
+    #[serde(rename = "resident_bytes")]
+    pub resident: usize,
+    /// 分配器映射的虚拟内存字节数
+    #[serde(rename = "mapped_bytes")]
+    pub mapped: usize,
+    /// 已解除使用但仍保留映射、未归还操作系统的字节数
+    #[serde(rename = "retained_bytes")]
+    pub retained: usize,
+    /// 活跃页中未被实际分配使用的比例（0-1），持续升高说明碎片在增长
+    pub fragmentation_ratio: f64,
+}
+
+impl AllocatorStats {
+    /// 由各项字节数计算碎片比例
+    pub fn new(
+        allocated: usize,
+        active: usize,
+        resident: usize,
+        mapped: usize,
+        retained: usize,
+    ) -> Self {
+        let fragmentation_ratio = if active == 0 {
+            0.0
+        } else {
+            active.saturating_sub(allocated) as f64 / active as f64
+        };
+        Self {
+            allocated,
+            active,
+            resident,
+            mapped,
+            retained,
+            fragmentation_ratio,
+        }
+    }
+}
+
+/// 读取当前的分配器统计
+///
+/// jemalloc 的统计按 epoch 缓存，读取前先推进 epoch 以获得最新值。
+pub fn read() -> Result<AllocatorStats> {
+    // tikv_jemalloc_ctl::Error 未实现 std::error::Error，需手动转换
+    let ctl = |e: tikv_jemalloc_ctl::Error| anyhow::anyhow!("jemalloc mallctl 失败：{e}");
+    epoch::advance().map_err(ctl)?;
+    Ok(AllocatorStats::new(
+        stats::allocated::read().map_err(ctl)?,
+        stats::active::read().map_err(ctl)?,
+        stats::resident::read().map_err(ctl)?,
+        stats::mapped::read().map_err(ctl)?,
+        stats::retained::read().map_err(ctl)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragmentation_ratio() {
+        let stats = AllocatorStats::new(750, 1000, 2000, 4000, 0);
+        assert!((stats.fragmentation_ratio - 0.25).abs() < 1e-9);
+        assert_eq!(AllocatorStats::new(0, 0, 0, 0, 0).fragmentation_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_read() {
+        // 测试二进制以 jemalloc 为全局分配器，大块分配应反映在 allocated 中
+        let before = read().unwrap();
+        let buffer = std::hint::black_box(vec![1u8; 64 << 20]);
+        let stats = read().unwrap();
+        assert!(
+            stats.allocated >= before.allocated + (32 << 20),
+            "{before:?} -> {stats:?}"
+        );
+        drop(buffer);
+        assert!(stats.active >= stats.allocated);
+        assert!(stats.mapped >= stats.active);
+        assert!((0.0..=1.0).contains(&stats.fragmentation_ratio));
+
+        let value = serde_json::to_value(stats).unwrap();
+        assert!(value["allocated_bytes"].is_u64());
+        assert!(value["retained_bytes"].is_u64());
+    }
+}
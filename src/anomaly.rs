@@ -0,0 +1,193 @@
+//! 采集数据异常标注
+//!
+//! 对几项关键指标各保留最近若干次采集的值，计算均值与标准差。当前值偏离均值超过
+//! 设定的倍数（默认 3σ）时，在 `SystemStats::anomalies` 中标注该指标，页面与 JSON
+//! 据此给出提示，无需外部告警系统。
+//!
+//! 与 [`OutlierFilter`](crate::filter::OutlierFilter) 不同，这里只标注不修改数据。
+//! 被标注的值照常进入窗口，持续的水平变化会在窗口更新后不再被视为异常。
+//! 窗口内的值完全相同（标准差为 0）时不做判定，避免任何微小变化都被标为异常。
+
+use crate::stats::{Anomaly, SystemStats};
+use log::debug;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+/// 参与判定的指标名称，用作 `SystemStats::anomalies` 的键，数值均为百分比
+pub const METRIC_CPU_USAGE: &str = "cpu_usage_percent";
+pub const METRIC_MEMORY_USED: &str = "memory_used_percent";
+pub const METRIC_CONNTRACK_USAGE: &str = "conntrack_usage_percent";
+
+/// 进行判定前窗口内至少需要的样本数，也是窗口长度的下限
+pub const MIN_SAMPLES: usize = 5;
+
+/// 单项指标的滑动窗口
+#[derive(Debug, Default)]
+struct MetricWindow {
+    samples: VecDeque<f64>,
+}
+
+impl MetricWindow {
+    /// 窗口内样本的均值与总体标准差，样本不足时返回 `None`
+    fn mean_stddev(&self) -> Option<(f64, f64)> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let n = self.samples.len() as f64;
+        let mean = self.samples.iter().sum::<f64>() / n;
+        let variance = self
+            .samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Some((mean, variance.sqrt()))
+    }
+
+    /// 判定 `value` 相对窗口是否异常，然后将其加入窗口
+    fn check(&mut self, value: f64, window: usize, sigma: f64) -> Option<Anomaly> {
+        let anomaly = self
+            .mean_stddev()
+            .filter(|&(mean, stddev)| stddev > 0.0 && (value - mean).abs() > sigma * stddev)
+            .map(|(mean, stddev)| Anomaly {
+                value,
+                mean,
+                stddev,
+            });
+
+        self.samples.push_back(value);
+        while self.samples.len() > window {
+            self.samples.pop_front();
+        }
+
+        anomaly
+    }
+}
+
+/// 基于均值与标准差的异常检测器
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    window: usize,
+    sigma: f64,
+    windows: Mutex<BTreeMap<&'static str, MetricWindow>>,
+}
+
+impl AnomalyDetector {
+    /// 创建检测器
+    ///
+    /// `window` 为参与统计的最近采集次数，小于 [`MIN_SAMPLES`] 时按 [`MIN_SAMPLES`] 计；
+    /// `sigma` 为判定异常的标准差倍数，应为大于 0 的有限数值，调用方负责校验。
+    #[inline]
+    pub fn new(window: usize, sigma: f64) -> Self {
+        Self {
+            window: window.max(MIN_SAMPLES),
+            sigma,
+            windows: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// 对一次采集结果进行判定，在 `stats.anomalies` 中记录异常的指标
+    pub fn apply(&self, stats: &mut SystemStats) {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        stats.anomalies.clear();
+        for (name, value) in metric_values(stats) {
+            let window = windows.entry(name).or_default();
+            if let Some(anomaly) = window.check(value, self.window, self.sigma) {
+                debug!(
+                    "指标异常: {name} = {value:.1}，均值 {:.1}，标准差 {:.2}",
+                    anomaly.mean, anomaly.stddev
+                );
+                stats.anomalies.insert(name, anomaly);
+            }
+        }
+    }
+}
+
+/// 取出参与判定的指标值，本次未采集到的指标（如未加载 conntrack）不参与
+fn metric_values(stats: &SystemStats) -> Vec<(&'static str, f64)> {
+    let mut values = vec![(
+        METRIC_CPU_USAGE,
+        f64::from(stats.cpu_stats.overall.total_percent),
+    )];
+    if stats.memory_total > 0 {
+        values.push((
+            METRIC_MEMORY_USED,
+            stats.memory_used as f64 / stats.memory_total as f64 * 100.0,
+        ));
+    }
+    if let Some(conntrack) = &stats.conntrack {
+        values.push((METRIC_CONNTRACK_USAGE, f64::from(conntrack.usage_percent)));
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_with(cpu: f32, memory_used: u64) -> SystemStats {
+        let mut stats = SystemStats {
+            memory_total: 1000,
+            memory_used,
+            ..SystemStats::default()
+        };
+        stats.cpu_stats.overall.total_percent = cpu;
+        stats
+    }
+
+    #[test]
+    fn test_spike_marked_anomalous() {
+        let detector = AnomalyDetector::new(10, 3.0);
+        for cpu in [10.0, 12.0, 11.0, 9.0, 10.0, 11.0] {
+            let mut stats = stats_with(cpu, 500);
+            detector.apply(&mut stats);
+            assert!(stats.anomalies.is_empty());
+        }
+
+        let mut spike = stats_with(60.0, 500);
+        detector.apply(&mut spike);
+        let anomaly = &spike.anomalies[METRIC_CPU_USAGE];
+        assert_eq!(anomaly.value, 60.0);
+        assert!((anomaly.mean - 10.5).abs() < 1e-9);
+        // 窗口内的内存使用率恒定，不做判定
+        assert!(!spike.anomalies.contains_key(METRIC_MEMORY_USED));
+    }
+
+    #[test]
+    fn test_values_within_sigma_not_marked() {
+        let detector = AnomalyDetector::new(10, 3.0);
+        for cpu in [10.0, 20.0, 10.0, 20.0, 10.0, 20.0, 25.0] {
+            let mut stats = stats_with(cpu, 500);
+            detector.apply(&mut stats);
+            assert!(stats.anomalies.is_empty(), "{cpu}");
+        }
+    }
+
+    #[test]
+    fn test_no_judgement_before_enough_samples() {
+        let detector = AnomalyDetector::new(10, 1.0);
+        for (cpu, memory_used) in [(0.0, 100), (1.0, 900), (100.0, 0), (50.0, 500)] {
+            let mut stats = stats_with(cpu, memory_used);
+            detector.apply(&mut stats);
+            assert!(stats.anomalies.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_sustained_shift_absorbed_by_window() {
+        let detector = AnomalyDetector::new(5, 3.0);
+        for cpu in [10.0, 11.0, 10.0, 11.0, 10.0] {
+            detector.apply(&mut stats_with(cpu, 500));
+        }
+
+        let marked: Vec<bool> = (0..6)
+            .map(|_| {
+                let mut stats = stats_with(80.0, 500);
+                detector.apply(&mut stats);
+                stats.anomalies.contains_key(METRIC_CPU_USAGE)
+            })
+            .collect();
+        assert!(marked[0]);
+        assert!(!marked.last().unwrap());
+    }
+}
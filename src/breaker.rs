@@ -0,0 +1,291 @@
+//! 采集断路器
+//!
+//! /proc 持续不可读（如容器安全策略误杀）时，每个请求都去采集并失败，既刷屏日志又浪费资源。
+//! 连续失败达到阈值后断路器打开，冷却期内直接拒绝采集（调用方退回旧数据或返回错误）；
+//! 冷却期结束后进入半开状态，只放行一次试探采集：成功则关闭，失败则重新打开。
+//! 试探许可在结果记录前被丢弃（如发起采集的请求中途断开）时按失败处理，避免停留在半开状态。
+
+use log::{info, warn};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 断路器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// 正常采集
+    Closed,
+    /// 已熔断，冷却期内不再采集
+    Open,
+    /// 冷却期结束，正在进行一次试探采集
+    HalfOpen,
+}
+
+impl BreakerState {
+    /// 导出为指标时的数值
+    #[inline]
+    pub fn as_metric(self) -> u8 {
+        match self {
+            Self::Closed => 0,
+            Self::Open => 1,
+            Self::HalfOpen => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trips: u64,
+}
+
+/// 断路器状态快照
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerSnapshot {
+    pub state: BreakerState,
+    /// 当前连续失败次数
+    pub consecutive_failures: u32,
+    /// 累计熔断次数
+    pub trips: u64,
+}
+
+/// 采集失败断路器
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Mutex<BreakerInner>,
+}
+
+impl CircuitBreaker {
+    /// 创建断路器
+    ///
+    /// 连续失败 `failure_threshold` 次（至少 1 次）后熔断 `open_duration`。
+    #[inline]
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trips: 0,
+            }),
+        }
+    }
+
+    /// 请求一次采集许可，熔断期间返回剩余冷却时间
+    ///
+    /// 冷却期结束后第一个调用者获得试探许可，试探结果出来之前其余调用者仍被拒绝。
+    /// 采集结束后通过许可记录结果。
+    pub fn try_acquire(&self, now: Instant) -> Result<BreakerPermit<'_>, Duration> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let permit = || BreakerPermit {
+            breaker: self,
+            resolved: false,
+        };
+        match inner.state {
+            BreakerState::Closed => Ok(permit()),
+            BreakerState::HalfOpen => Err(Duration::ZERO),
+            BreakerState::Open => {
+                let opened_at = inner.opened_at.unwrap_or(now);
+                let elapsed = now.saturating_duration_since(opened_at);
+                if elapsed >= self.open_duration {
+                    inner.state = BreakerState::HalfOpen;
+                    info!("采集断路器冷却结束，进行一次试探采集");
+                    Ok(permit())
+                } else {
+                    Err(self.open_duration - elapsed)
+                }
+            }
+        }
+    }
+
+    /// 记录一次成功采集
+    fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state != BreakerState::Closed {
+            info!(
+                "采集已恢复，断路器关闭（此前连续失败 {} 次）",
+                inner.consecutive_failures
+            );
+        }
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    /// 记录一次失败采集，达到阈值或试探失败时打开断路器
+    fn record_failure(&self, now: Instant) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let trip = match inner.state {
+            BreakerState::HalfOpen => true,
+            BreakerState::Closed => inner.consecutive_failures >= self.failure_threshold,
+            BreakerState::Open => false,
+        };
+        if trip {
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(now);
+            inner.trips += 1;
+            warn!(
+                "采集连续失败 {} 次，断路器打开，{} 秒内不再尝试采集",
+                inner.consecutive_failures,
+                self.open_duration.as_secs_f64()
+            );
+        }
+    }
+
+    /// 读取状态快照
+    pub fn snapshot(&self) -> BreakerSnapshot {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        BreakerSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            trips: inner.trips,
+        }
+    }
+}
+
+/// 一次采集许可，由 [`CircuitBreaker::try_acquire`] 发放
+///
+/// 未记录结果就被丢弃时，若断路器处于半开状态则视为试探失败并重新打开，
+/// 下一个冷却期结束后再发放新的试探许可；关闭状态下丢弃不计入失败。
+#[must_use = "采集结束后需通过许可记录结果"]
+#[derive(Debug)]
+pub struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    resolved: bool,
+}
+
+impl BreakerPermit<'_> {
+    /// 记录采集成功
+    pub fn success(mut self) {
+        self.resolved = true;
+        self.breaker.record_success();
+    }
+
+    /// 记录采集失败
+    pub fn failure(mut self, now: Instant) {
+        self.resolved = true;
+        self.breaker.record_failure(now);
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if self.resolved {
+            return;
+        }
+        let half_open = self
+            .breaker
+            .inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .state
+            == BreakerState::HalfOpen;
+        if half_open {
+            warn!("试探采集未完成即被取消，按失败处理");
+            self.breaker.record_failure(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::AssertUnwindSafe;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        let now = Instant::now();
+        for _ in 0..2 {
+            breaker.try_acquire(now).unwrap().failure(now);
+        }
+        assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+
+        breaker.record_failure(now);
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, BreakerState::Open);
+        assert_eq!(snapshot.consecutive_failures, 3);
+        assert_eq!(snapshot.trips, 1);
+        assert_eq!(
+            breaker.try_acquire(now + Duration::from_secs(10)).err(),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+        let now = Instant::now();
+        breaker.record_failure(now);
+        breaker.record_success();
+        breaker.record_failure(now);
+        assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+        assert_eq!(breaker.snapshot().consecutive_failures, 1);
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let now = Instant::now();
+        breaker.record_failure(now);
+        assert!(breaker.try_acquire(now).is_err());
+
+        // 冷却结束：只放行一次试探，试探失败重新计时
+        let later = now + Duration::from_secs(30);
+        let probe = breaker.try_acquire(later).unwrap();
+        assert_eq!(breaker.snapshot().state, BreakerState::HalfOpen);
+        assert!(breaker.try_acquire(later).is_err());
+        probe.failure(later);
+        assert_eq!(breaker.snapshot().state, BreakerState::Open);
+        assert_eq!(breaker.snapshot().trips, 2);
+        assert!(
+            breaker
+                .try_acquire(later + Duration::from_secs(29))
+                .is_err()
+        );
+
+        // 再次试探成功后关闭
+        let recovered = later + Duration::from_secs(30);
+        breaker.try_acquire(recovered).unwrap().success();
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, BreakerState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+        assert!(breaker.try_acquire(recovered).is_ok());
+    }
+
+    #[test]
+    fn test_recovers_from_poisoned_lock() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let _ = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _inner = breaker.inner.lock().unwrap();
+            panic!("持锁期间 panic");
+        }));
+        assert!(breaker.inner.is_poisoned());
+
+        let now = Instant::now();
+        breaker.try_acquire(now).unwrap().failure(now);
+        assert_eq!(breaker.snapshot().state, BreakerState::Open);
+    }
+
+    #[test]
+    fn test_dropped_probe_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let now = Instant::now();
+        // 关闭状态下丢弃许可不计入失败
+        drop(breaker.try_acquire(now).unwrap());
+        assert_eq!(breaker.snapshot().consecutive_failures, 0);
+
+        breaker.record_failure(now);
+        let later = now + Duration::from_secs(30);
+        drop(breaker.try_acquire(later).unwrap());
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, BreakerState::Open);
+        assert_eq!(snapshot.trips, 2);
+    }
+}
@@ -0,0 +1,170 @@
+//! 采集器的 CPU 时间预算
+//!
+//! 极端负载下监控本身不应加剧问题。每次采集前读取本进程已消耗的 CPU 时间
+//! （`/proc/self/stat` 的 utime + stime），与滚动窗口起点的读数相比，
+//! 得到窗口内本进程占用单个核心的比例。超出预算时本轮跳过较重的子采集
+//! （每核心 CPU、中断分布），回落到预算以内后自动恢复。
+//!
+//! 读数包含整个进程（采集与 HTTP 请求处理），不含外部采集命令等子进程。
+
+use log::{debug, info, warn};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 本进程的 stat 文件，始终读取真实的 /proc（不受 `--proc-root` 影响）
+const SELF_STAT_PATH: &str = "/proc/self/stat";
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    /// 窗口内的 (读取时刻, 累计 CPU 时间)，首项为窗口起点
+    samples: VecDeque<(Instant, Duration)>,
+    exceeded: bool,
+}
+
+/// 滚动窗口内的 CPU 时间预算
+#[derive(Debug)]
+pub struct CpuBudget {
+    /// 允许占用单个核心的比例（0-1）
+    limit: f64,
+    window: Duration,
+    state: Mutex<BudgetState>,
+}
+
+impl CpuBudget {
+    /// 创建预算
+    ///
+    /// `percent` 为滚动窗口 `window` 内允许占用单个核心的百分比（如 2.0 表示 2%）。
+    #[inline]
+    pub fn new(percent: f64, window: Duration) -> Self {
+        Self {
+            limit: percent / 100.0,
+            window,
+            state: Mutex::new(BudgetState::default()),
+        }
+    }
+
+    /// 读取本进程的 CPU 时间并判断是否超出预算，读取失败时视为未超出
+    pub fn check(&self) -> bool {
+        match read_self_cpu_time() {
+            Ok(cpu_time) => self.record(Instant::now(), cpu_time),
+            Err(e) => {
+                debug!("读取 {SELF_STAT_PATH} 失败: {e}");
+                false
+            }
+        }
+    }
+
+    /// 记录一次读数，返回滚动窗口内的 CPU 占用是否超出预算
+    fn record(&self, now: Instant, cpu_time: Duration) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.samples.push_back((now, cpu_time));
+        // 保留不晚于窗口起点的最后一个读数作为基准
+        while state.samples.len() > 2
+            && now.saturating_duration_since(state.samples[1].0) >= self.window
+        {
+            state.samples.pop_front();
+        }
+
+        let (start, start_cpu) = state.samples[0];
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed.is_zero() {
+            return state.exceeded;
+        }
+        let ratio = cpu_time.saturating_sub(start_cpu).as_secs_f64() / elapsed.as_secs_f64();
+        let exceeded = ratio > self.limit;
+        if exceeded != state.exceeded {
+            if exceeded {
+                warn!(
+                    "本进程最近 {:.0} 秒占用 {:.1}% CPU，超出预算 {:.1}%，暂停每核心与中断采集",
+                    elapsed.as_secs_f64(),
+                    ratio * 100.0,
+                    self.limit * 100.0
+                );
+            } else {
+                info!(
+                    "本进程 CPU 占用回落到 {:.1}%，恢复每核心与中断采集",
+                    ratio * 100.0
+                );
+            }
+            state.exceeded = exceeded;
+        }
+        exceeded
+    }
+}
+
+/// 读取本进程累计消耗的 CPU 时间（用户态 + 内核态）
+fn read_self_cpu_time() -> std::io::Result<Duration> {
+    let content = std::fs::read_to_string(SELF_STAT_PATH)?;
+    let ticks = parse_self_stat_ticks(&content).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "无法解析 utime/stime")
+    })?;
+    // SAFETY: sysconf 只读取系统配置，没有内存安全方面的前置条件
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return Err(std::io::Error::other("无法获取 CLK_TCK"));
+    }
+    Ok(Duration::from_secs_f64(
+        ticks as f64 / ticks_per_second as f64,
+    ))
+}
+
+/// 解析 stat 中的 utime 与 stime（第 14、15 个字段，单位为时钟节拍）之和
+///
+/// 第 2 个字段是括号包围的进程名，可能含空格或括号，因此从最后一个 `)` 之后开始计数。
+fn parse_self_stat_ticks(content: &str) -> Option<u64> {
+    let rest = &content[content.rfind(')')? + 1..];
+    // `)` 之后从第 3 个字段（state）开始
+    let mut fields = rest.split_ascii_whitespace().skip(14 - 3);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_self_stat_ticks() {
+        let content = "1234 (swb (sys) monitor) S 1 1234 1234 0 -1 4194560 500 0 0 0 170 30 0 0 20 0 4 0 100 1000000 200 18446744073709551615";
+        assert_eq!(parse_self_stat_ticks(content), Some(200));
+        assert_eq!(parse_self_stat_ticks("1234 (truncated) S 1"), None);
+    }
+
+    #[test]
+    fn test_read_self_cpu_time() {
+        assert!(read_self_cpu_time().is_ok());
+    }
+
+    #[test]
+    fn test_budget_exceeded_and_recovered() {
+        let budget = CpuBudget::new(2.0, Duration::from_secs(10));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(!budget.record(at(0), Duration::ZERO));
+        // 5 秒内用掉 50 毫秒，占 1%
+        assert!(!budget.record(at(5), Duration::from_millis(50)));
+        // 10 秒内用掉 500 毫秒，占 5%
+        assert!(budget.record(at(10), Duration::from_millis(500)));
+        // 突发仍在窗口内
+        assert!(budget.record(at(15), Duration::from_millis(550)));
+        // 窗口滑过突发后回落：基准为 10 秒时刻的读数，10 秒内只用掉 100 毫秒
+        assert!(!budget.record(at(20), Duration::from_millis(600)));
+    }
+
+    #[test]
+    fn test_window_keeps_baseline_at_window_start() {
+        let budget = CpuBudget::new(2.0, Duration::from_secs(10));
+        let start = Instant::now();
+        for secs in 0..=30 {
+            budget.record(start + Duration::from_secs(secs), Duration::ZERO);
+        }
+        let state = budget.state.lock().unwrap();
+        assert_eq!(
+            state.samples.front().unwrap().0,
+            start + Duration::from_secs(20)
+        );
+    }
+}
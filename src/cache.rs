@@ -1,13 +1,168 @@
-use crate::stats::{Result, SystemStats, collect_system_stats};
+use crate::anomaly::AnomalyDetector;
+use crate::breaker::CircuitBreaker;
+#[cfg(feature = "custom")]
+use crate::custom::CustomCollector;
+use crate::filter::OutlierFilter;
+#[cfg(feature = "sqlite")]
+use crate::history::HistoryStore;
+use crate::sampler::CpuSampler;
+use crate::stats::{
+    Collector, CompactCpuUsage, Result, SUBSYSTEM_CUSTOM, StatsError, SubsystemStatus, SystemStats,
+};
+use log::{debug, warn};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-/// 无锁系统统计数据缓存
+/// 缓存数据的刷新状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshState {
+    /// 尚未采集过数据
+    Empty,
+    /// 最近一次刷新成功
+    Fresh,
+    /// 正在刷新
+    Refreshing,
+    /// 最近一次刷新失败，缓存中如有旧数据仍可用
+    Failed,
+}
+
+impl RefreshState {
+    #[inline]
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Fresh,
+            2 => Self::Refreshing,
+            3 => Self::Failed,
+            _ => Self::Empty,
+        }
+    }
+
+    /// 状态的简短描述
+    #[inline]
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Empty => "尚无数据",
+            Self::Fresh => "新鲜",
+            Self::Refreshing => "正在刷新",
+            Self::Failed => "刷新失败（显示上次成功的数据）",
+        }
+    }
+}
+
+/// 后台采集间隔诊断：记录实际相邻采集间隔相对名义 TTL 的抖动
+#[derive(Debug, Default)]
+pub struct CollectionTiming {
+    samples: AtomicU64,
+    last_interval_ms: AtomicU64,
+    last_jitter_ms: AtomicU64,
+    max_jitter_ms: AtomicU64,
+}
+
+/// 采集间隔诊断快照
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingSnapshot {
+    /// 已记录的间隔数
+    pub samples: u64,
+    /// 最近一次实际采集间隔
+    pub last_interval: Duration,
+    /// 最近一次间隔与名义 TTL 的偏差（绝对值）
+    pub last_jitter: Duration,
+    /// 观察到的最大偏差
+    pub max_jitter: Duration,
+}
+
+impl CollectionTiming {
+    /// 记录一次实际采集间隔
+    fn record(&self, interval: Duration, nominal: Duration) {
+        let interval_ms = interval.as_millis() as u64;
+        let jitter_ms = interval_ms.abs_diff(nominal.as_millis() as u64);
+
+        self.last_interval_ms.store(interval_ms, Ordering::Relaxed);
+        self.last_jitter_ms.store(jitter_ms, Ordering::Relaxed);
+        self.max_jitter_ms.fetch_max(jitter_ms, Ordering::Relaxed);
+        self.samples.fetch_add(1, Ordering::Release);
+    }
+
+    /// 读取诊断快照，尚无记录时返回 `None`
+    pub fn snapshot(&self) -> Option<TimingSnapshot> {
+        let samples = self.samples.load(Ordering::Acquire);
+        if samples == 0 {
+            return None;
+        }
+        Some(TimingSnapshot {
+            samples,
+            last_interval: Duration::from_millis(self.last_interval_ms.load(Ordering::Relaxed)),
+            last_jitter: Duration::from_millis(self.last_jitter_ms.load(Ordering::Relaxed)),
+            max_jitter: Duration::from_millis(self.max_jitter_ms.load(Ordering::Relaxed)),
+        })
+    }
+}
+
+/// 缓存中保存的采集结果；启用紧凑表示时每核心数据以定点数单独保存，读取时还原
+struct CachedStats {
+    stats: SystemStats,
+    compact_cores: Option<Box<[CompactCpuUsage]>>,
+}
+
+impl CachedStats {
+    fn new(mut stats: SystemStats, compact: bool) -> Self {
+        let compact_cores = (compact && !stats.cpu_stats.per_core.is_empty()).then(|| {
+            let per_core = std::mem::take(&mut stats.cpu_stats.per_core);
+            per_core
+                .iter()
+                .map(CompactCpuUsage::from_breakdown)
+                .collect()
+        });
+        Self {
+            stats,
+            compact_cores,
+        }
+    }
+
+    fn to_stats(&self) -> SystemStats {
+        let mut stats = self.stats.clone();
+        if let Some(cores) = &self.compact_cores {
+            stats.cpu_stats.per_core = cores.iter().map(|core| core.to_breakdown()).collect();
+        }
+        stats
+    }
+}
+
+/// 系统统计数据缓存
 pub struct SystemStatsCache {
-    current_stats: AtomicPtr<SystemStats>,
+    /// 当前数据：读取方只在克隆 `Arc` 时短暂持有读锁，替换后旧数据随最后一个读取方释放
+    current_stats: RwLock<Arc<CachedStats>>,
+    /// 最近一次更新的单调时刻（相对 `created_at` 的毫秒数加 1，0 表示从未更新）
+    ///
+    /// 年龄与过期判断不受墙上时钟回拨或跳变影响。
     last_update: AtomicU64,
+    created_at: Instant,
+    refresh_state: AtomicU8,
+    /// 最近一次分配的采集序列号
+    sequence: AtomicU64,
+    /// 串行化采集：并发请求同时发现数据过期时只采集一次，其余请求等待后直接取用结果
+    refresh_lock: tokio::sync::Mutex<()>,
+    timing: CollectionTiming,
     ttl: Duration,
+    /// 后台刷新是否对齐到墙上时钟的整 TTL 边界
+    align_refresh: bool,
+    collector: Collector,
+    outlier_filter: Option<OutlierFilter>,
+    anomaly_detector: Option<AnomalyDetector>,
+    cpu_sampler: Option<Arc<CpuSampler>>,
+    /// 只保留这些核心的每核心数据（`None` 表示全部）
+    cpu_cores: Option<Vec<usize>>,
+    /// 是否已就不存在的核心编号告警过
+    cpu_cores_warned: AtomicBool,
+    /// 每核心数据是否以定点数紧凑保存
+    compact_per_core: bool,
+    breaker: Option<CircuitBreaker>,
+    #[cfg(feature = "custom")]
+    custom_collector: Option<CustomCollector>,
+    #[cfg(feature = "sqlite")]
+    history: Option<HistoryStore>,
 }
 
 impl SystemStatsCache {
@@ -15,13 +170,168 @@ impl SystemStatsCache {
     #[inline]
     pub fn new(ttl: Duration) -> Self {
         Self {
-            current_stats: AtomicPtr::new(Box::into_raw(Box::new(SystemStats::default()))),
+            current_stats: RwLock::new(Arc::new(CachedStats::new(SystemStats::default(), false))),
             last_update: AtomicU64::new(0),
+            created_at: Instant::now(),
+            refresh_state: AtomicU8::new(RefreshState::Empty as u8),
+            sequence: AtomicU64::new(0),
+            refresh_lock: tokio::sync::Mutex::new(()),
+            timing: CollectionTiming::default(),
             ttl,
+            align_refresh: false,
+            collector: Collector::default(),
+            outlier_filter: None,
+            anomaly_detector: None,
+            cpu_sampler: None,
+            cpu_cores: None,
+            cpu_cores_warned: AtomicBool::new(false),
+            compact_per_core: false,
+            breaker: None,
+            #[cfg(feature = "custom")]
+            custom_collector: None,
+            #[cfg(feature = "sqlite")]
+            history: None,
+        }
+    }
+
+    /// 使用指定的采集器（默认读取 /proc）
+    #[inline]
+    pub fn with_collector(mut self, collector: Collector) -> Self {
+        self.collector = collector;
+        self
+    }
+
+    /// 后台刷新的采样时刻对齐到墙上时钟的整 TTL 边界（默认从启动时刻起算）
+    ///
+    /// 如 TTL 为 10 秒时在每分钟的第 0、10、20… 秒采集，多台设备的采样时刻因此一致。
+    #[inline]
+    pub fn with_aligned_refresh(mut self, align: bool) -> Self {
+        self.align_refresh = align;
+        self
+    }
+
+    /// 启用采集异常值剔除（默认关闭）
+    #[inline]
+    pub fn with_outlier_filter(mut self, filter: OutlierFilter) -> Self {
+        self.outlier_filter = Some(filter);
+        self
+    }
+
+    /// 启用异常标注：指标偏离最近窗口均值过多时记入 `SystemStats::anomalies`（默认关闭）
+    #[inline]
+    pub fn with_anomaly_detector(mut self, detector: AnomalyDetector) -> Self {
+        self.anomaly_detector = Some(detector);
+        self
+    }
+
+    /// 使用独立 CPU 采样子循环的平滑结果作为 CPU 数据（采样循环需另行启动）
+    #[inline]
+    pub fn with_cpu_sampler(mut self, sampler: Arc<CpuSampler>) -> Self {
+        self.cpu_sampler = Some(sampler);
+        self
+    }
+
+    /// 每核心数据只保留指定的核心（如 NUMA 节点或隔离出来的核心），总体使用率仍统计全部核心
+    #[inline]
+    pub fn with_cpu_cores(mut self, cores: Vec<usize>) -> Self {
+        self.cpu_cores = Some(cores);
+        self
+    }
+
+    /// 缓存中的每核心使用率以 `u16` 定点数保存（精度 0.01 个百分点），占用减半（默认关闭）
+    #[inline]
+    pub fn with_compact_per_core(mut self, compact: bool) -> Self {
+        self.compact_per_core = compact;
+        self
+    }
+
+    /// 启用采集断路器：连续失败后暂停采集一段时间（默认关闭）
+    #[inline]
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// 采集断路器（未启用时为 `None`）
+    #[inline]
+    pub fn breaker(&self) -> Option<&CircuitBreaker> {
+        self.breaker.as_ref()
+    }
+
+    /// 启用外部命令采集，结果随每次采集合并进 `SystemStats::custom`
+    #[cfg(feature = "custom")]
+    #[inline]
+    pub fn with_custom_collector(mut self, collector: CustomCollector) -> Self {
+        self.custom_collector = Some(collector);
+        self
+    }
+
+    /// 启用采集历史持久化，每次采集后写入（失败不影响缓存更新）
+    ///
+    /// 序列号接着库中已有的最大序列号计数，进程重启后写入的记录不与之前的重复。
+    #[cfg(feature = "sqlite")]
+    pub fn with_history(mut self, history: HistoryStore) -> Self {
+        match history.last_sequence() {
+            Ok(last) => *self.sequence.get_mut() = last,
+            Err(e) => warn!("读取采集历史的序列号失败，序列号从 1 开始：{e}"),
         }
+        self.history = Some(history);
+        self
+    }
+
+    /// 采集历史存储（未启用时为 `None`）
+    #[cfg(feature = "sqlite")]
+    #[inline]
+    pub fn history(&self) -> Option<&HistoryStore> {
+        self.history.as_ref()
+    }
+
+    /// 当前的刷新状态
+    #[inline]
+    pub fn refresh_state(&self) -> RefreshState {
+        RefreshState::from_u8(self.refresh_state.load(Ordering::Acquire))
     }
 
-    /// 无锁读取缓存数据
+    #[inline]
+    fn set_refresh_state(&self, state: RefreshState) {
+        self.refresh_state.store(state as u8, Ordering::Release);
+    }
+
+    /// 后台采集间隔诊断
+    #[inline]
+    pub fn timing(&self) -> &CollectionTiming {
+        &self.timing
+    }
+
+    /// 将刷新状态标记为失败（供测试模拟采集失败）
+    #[cfg(test)]
+    pub(crate) fn mark_refresh_failed(&self) {
+        self.set_refresh_state(RefreshState::Failed);
+    }
+
+    /// 当前单调时刻：相对 `created_at` 的毫秒数加 1，保证不为 0
+    #[inline]
+    fn monotonic_millis(&self) -> u64 {
+        self.created_at.elapsed().as_millis() as u64 + 1
+    }
+
+    /// 读取缓存数据及其年龄，不检查是否过期；从未采集过时返回 `None`
+    pub fn get_with_age(&self) -> Option<(SystemStats, Duration)> {
+        let last_update = self.last_update.load(Ordering::Acquire);
+        if last_update == 0 {
+            return None;
+        }
+
+        let now = self.monotonic_millis();
+
+        let cached = self.load_cached();
+        Some((
+            cached.to_stats(),
+            Duration::from_millis(now.saturating_sub(last_update)),
+        ))
+    }
+
+    /// 读取缓存数据，过期或从未采集过时返回 `None`
     pub fn get(&self) -> Option<SystemStats> {
         // 先加载时间戳，避免 ABA 问题
         let last_update = self.last_update.load(Ordering::Acquire);
@@ -29,49 +339,130 @@ impl SystemStatsCache {
             return None; // 未初始化
         }
 
-        // 获取当前时间戳（使用毫秒精度）
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        // 获取当前单调时刻（使用毫秒精度）
+        let now = self.monotonic_millis();
 
         // 检查数据是否过期（使用毫秒精度）
-        if now - last_update > self.ttl.as_millis() as u64 {
+        if now.saturating_sub(last_update) > self.ttl.as_millis() as u64 {
             return None; // 数据过期
         }
 
-        // 加载数据指针
-        let ptr = self.current_stats.load(Ordering::Acquire);
-        if ptr.is_null() {
-            return None;
-        }
+        Some(self.load_cached().to_stats())
+    }
 
-        // 安全读取数据
-        let stats = unsafe { &*ptr };
-        Some(stats.clone())
+    /// 取得当前数据的引用计数句柄，释放读锁后再使用
+    #[inline]
+    fn load_cached(&self) -> Arc<CachedStats> {
+        Arc::clone(&self.current_stats.read().unwrap_or_else(|e| e.into_inner()))
     }
 
     /// 原子更新缓存数据
     pub fn update(&self, new_stats: SystemStats) {
-        // 先获取当前时间戳（使用毫秒精度）
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        // 先获取当前单调时刻（使用毫秒精度）
+        let now = self.monotonic_millis();
+
+        // 在锁外创建新数据，锁内只替换句柄；仍被读取方持有的旧数据由其释放
+        let cached = Arc::new(CachedStats::new(new_stats, self.compact_per_core));
+        let old = std::mem::replace(
+            &mut *self
+                .current_stats
+                .write()
+                .unwrap_or_else(|e| e.into_inner()),
+            cached,
+        );
+        drop(old);
+
+        // 最后更新时间戳，确保数据先于时间戳可见
+        self.last_update.store(now, Ordering::Release);
+        self.set_refresh_state(RefreshState::Fresh);
+    }
+
+    /// 立即采集一次并更新缓存，同时维护刷新状态
+    pub async fn refresh(&self) -> Result<SystemStats> {
+        let _guard = self.refresh_lock.lock().await;
+        self.refresh_locked().await
+    }
 
-        // 创建新数据
-        let boxed_stats = Box::into_raw(Box::new(new_stats));
+    /// 持有 `refresh_lock` 时执行的采集
+    ///
+    /// 两次采集紧挨着进行时，后一次的 CPU 增量只覆盖极短的间隔，结果失真，
+    /// 还会多占一个序列号，因此采集必须串行。
+    async fn refresh_locked(&self) -> Result<SystemStats> {
+        // 每次采集尝试都占用一个序列号，失败或熔断的采集在序列中留下缺口
+        let sequence = self.sequence.fetch_add(1, Ordering::AcqRel) + 1;
+        let permit = match self.breaker.as_ref().map(|b| b.try_acquire(Instant::now())) {
+            Some(Err(retry_after)) => {
+                self.set_refresh_state(RefreshState::Failed);
+                return Err(StatsError::CircuitOpen { retry_after });
+            }
+            Some(Ok(permit)) => Some(permit),
+            None => None,
+        };
+        self.set_refresh_state(RefreshState::Refreshing);
+        let _refreshing = RefreshingGuard(self);
 
-        // 原子替换数据指针
-        let old_ptr = self.current_stats.swap(boxed_stats, Ordering::Release);
+        // 上一次成功采集的时刻，用于计算实际采集间隔
+        let previous = self.get_with_age().map(|(stats, _)| stats.timestamp);
 
-        // 安全释放旧数据
-        if !old_ptr.is_null() {
-            let _ = unsafe { Box::from_raw(old_ptr) };
+        let mut new_stats = match self.collector.collect().await {
+            Ok(stats) => stats,
+            Err(e) => {
+                if let Some(permit) = permit {
+                    permit.failure(Instant::now());
+                }
+                self.set_refresh_state(RefreshState::Failed);
+                return Err(e);
+            }
+        };
+        if let Some(permit) = permit {
+            permit.success();
+        }
+        if let Some(sampler) = &self.cpu_sampler {
+            sampler.apply(&mut new_stats);
+        }
+        if let Some(cores) = &self.cpu_cores {
+            let invalid = new_stats.cpu_stats.retain_cores(cores);
+            // 核心数在运行期间基本不变，只告警一次
+            if !invalid.is_empty() && !self.cpu_cores_warned.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "忽略不存在的核心编号 {invalid:?}（共 {} 个核心）",
+                    new_stats.cpu_stats.core_count
+                );
+            }
+        }
+        if let Some(filter) = &self.outlier_filter {
+            filter.apply(&mut new_stats);
         }
+        if let Some(detector) = &self.anomaly_detector {
+            detector.apply(&mut new_stats);
+        }
+        #[cfg(feature = "custom")]
+        let custom_status = match &self.custom_collector {
+            Some(collector) => {
+                let (custom, failed) = collector.collect().await;
+                new_stats.custom = custom;
+                if failed == 0 {
+                    SubsystemStatus::Ok
+                } else {
+                    SubsystemStatus::Failed
+                }
+            }
+            None => SubsystemStatus::Disabled,
+        };
+        #[cfg(not(feature = "custom"))]
+        let custom_status = SubsystemStatus::Disabled;
+        new_stats.subsystems.insert(SUBSYSTEM_CUSTOM, custom_status);
+        new_stats.collection_interval =
+            previous.map(|previous| new_stats.timestamp.saturating_duration_since(previous));
+        new_stats.sequence = sequence;
 
-        // 最后更新时间戳，确保数据先于时间戳可见
-        self.last_update.store(now, Ordering::Release);
+        // 更新缓存
+        self.update(new_stats.clone());
+        #[cfg(feature = "sqlite")]
+        if let Some(history) = &self.history {
+            history.record(&new_stats);
+        }
+        Ok(new_stats)
     }
 
     /// 按需更新策略：只有在数据过期且有请求时才更新
@@ -81,21 +472,101 @@ impl SystemStatsCache {
             return Ok(stats);
         }
 
-        // 数据过期或不存在，重新获取
-        let new_stats = collect_system_stats().await?;
+        // 数据过期或不存在，重新获取；等锁期间其他请求可能已经刷新完毕
+        let _guard = self.refresh_lock.lock().await;
+        if let Some(stats) = self.get() {
+            return Ok(stats);
+        }
+        self.refresh_locked().await
+    }
 
-        // 更新缓存
-        self.update(new_stats.clone());
-        Ok(new_stats)
+    /// 启动后台刷新任务：每个 TTL 周期主动采集一次，不依赖用户请求
+    ///
+    /// 启用对齐时首次采集推迟到下一个整 TTL 边界，错过的周期直接跳过以保持相位；
+    /// 周期由单调时钟计时，墙上时钟被大幅调整后需重启才能重新对齐。
+    pub fn spawn_background_refresh(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = if cache.align_refresh {
+                let since_epoch = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let delay = align_delay(since_epoch, cache.ttl);
+                debug!(
+                    "后台刷新对齐到整 TTL 边界，{} 毫秒后首次采集",
+                    delay.as_millis()
+                );
+                let mut interval =
+                    tokio::time::interval_at(tokio::time::Instant::now() + delay, cache.ttl);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                interval
+            } else {
+                let mut interval = tokio::time::interval(cache.ttl);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                interval
+            };
+            let mut last_tick: Option<Instant> = None;
+            loop {
+                interval.tick().await;
+                // 系统卡住等原因错过的周期也计入序列号，使消费方能发现缺口
+                let now = Instant::now();
+                if let Some(last_tick) = last_tick.replace(now) {
+                    let missed = missed_periods(now.duration_since(last_tick), cache.ttl);
+                    if missed > 0 {
+                        warn!("后台刷新延误，错过了 {missed} 个采集周期");
+                        cache.sequence.fetch_add(missed, Ordering::AcqRel);
+                    }
+                }
+                match cache.refresh().await {
+                    Ok(stats) => {
+                        // 任务被延迟调度时实际间隔会偏离 TTL，记录下来用于诊断
+                        if let Some(interval) = stats.collection_interval {
+                            cache.timing.record(interval, cache.ttl);
+                        }
+                        debug!("后台刷新完成");
+                    }
+                    // 熔断期间跳过的采集不逐次告警，断路器打开时已记录
+                    Err(e @ StatsError::CircuitOpen { .. }) => debug!("后台刷新跳过: {e}"),
+                    Err(e) => warn!("后台刷新失败: {e}"),
+                }
+            }
+        })
+    }
+}
+
+/// 从 `since_epoch`（Unix 纪元起的时长）到下一个 `period` 整数倍时刻的等待时长，恰在边界上时为零
+fn align_delay(since_epoch: Duration, period: Duration) -> Duration {
+    let period_ns = period.as_nanos();
+    if period_ns == 0 {
+        return Duration::ZERO;
+    }
+    match since_epoch.as_nanos() % period_ns {
+        0 => Duration::ZERO,
+        rem => Duration::from_nanos((period_ns - rem) as u64),
     }
 }
 
-impl Drop for SystemStatsCache {
+/// 相邻两次后台刷新之间错过的周期数：实际间隔按 TTL 四舍五入后减去本次
+fn missed_periods(elapsed: Duration, ttl: Duration) -> u64 {
+    let ttl_ms = ttl.as_millis() as u64;
+    if ttl_ms == 0 {
+        return 0;
+    }
+    let periods = (elapsed.as_millis() as u64).saturating_add(ttl_ms / 2) / ttl_ms;
+    periods.saturating_sub(1)
+}
+
+/// 采集中途被取消（如发起请求的连接断开）时把刷新状态从 Refreshing 改为 Failed
+struct RefreshingGuard<'a>(&'a SystemStatsCache);
+
+impl Drop for RefreshingGuard<'_> {
     fn drop(&mut self) {
-        let ptr = self.current_stats.load(Ordering::Acquire);
-        if !ptr.is_null() {
-            let _ = unsafe { Box::from_raw(ptr) };
-        }
+        let _ = self.0.refresh_state.compare_exchange(
+            RefreshState::Refreshing as u8,
+            RefreshState::Failed as u8,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
     }
 }
 
@@ -104,6 +575,7 @@ pub type CacheRef = Arc<SystemStatsCache>;
 
 /// 创建缓存实例的便捷函数
 #[inline]
+#[allow(dead_code)] // 库 API，二进制通过 SystemStatsCache::new 组装可选处理
 pub fn create_cache(ttl_seconds: u64) -> CacheRef {
     Arc::new(SystemStatsCache::new(Duration::from_secs(ttl_seconds)))
 }
@@ -127,6 +599,7 @@ mod tests {
                 },
                 per_core: Vec::new(),
                 core_count: 0,
+                core_ids: None,
             },
             memory_total: 1024 * 1024 * 1024,    // 1GB
             memory_used: 512 * 1024 * 1024,      // 512MB
@@ -134,6 +607,7 @@ mod tests {
             memory_cached: 128 * 1024 * 1024,    // 128MB
             memory_free: 128 * 1024 * 1024,      // 128MB
             timestamp: std::time::Instant::now(),
+            ..SystemStats::default()
         }
     }
 
@@ -289,6 +763,314 @@ mod tests {
         assert!(cache.get().is_none());
     }
 
+    #[tokio::test]
+    async fn test_cache_refresh_state() {
+        let cache = SystemStatsCache::new(Duration::from_secs(10));
+        assert_eq!(cache.refresh_state(), RefreshState::Empty);
+        assert!(cache.get_with_age().is_none());
+
+        cache.set_refresh_state(RefreshState::Refreshing);
+        assert_eq!(cache.refresh_state(), RefreshState::Refreshing);
+
+        cache.update(create_test_stats("state", 0.1));
+        assert_eq!(cache.refresh_state(), RefreshState::Fresh);
+
+        // 刷新失败后仍可读取旧数据
+        cache.set_refresh_state(RefreshState::Failed);
+        let (stats, age) = cache.get_with_age().unwrap();
+        assert_eq!(stats.hostname, "state");
+        assert!(age < Duration::from_secs(10));
+        assert_eq!(cache.refresh_state(), RefreshState::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_cache_circuit_breaker() {
+        use crate::breaker::BreakerState;
+        let cache = SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new("/nonexistent/proc"))
+            .with_circuit_breaker(CircuitBreaker::new(2, Duration::from_secs(60)));
+
+        for _ in 0..2 {
+            assert!(matches!(cache.refresh().await, Err(StatsError::IoError(_))));
+        }
+        // 断路器打开后不再尝试读取 /proc
+        let error = cache.get_or_update().await.unwrap_err();
+        assert!(
+            matches!(error, StatsError::CircuitOpen { retry_after } if retry_after > Duration::from_secs(59))
+        );
+        assert_eq!(cache.refresh_state(), RefreshState::Failed);
+
+        let snapshot = cache.breaker().unwrap().snapshot();
+        assert_eq!(snapshot.state, BreakerState::Open);
+        assert_eq!(snapshot.consecutive_failures, 2);
+        assert_eq!(snapshot.trips, 1);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_sequence() {
+        let proc_root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc");
+        let cache = SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new(proc_root));
+        assert_eq!(cache.refresh().await.unwrap().sequence, 1);
+        assert_eq!(cache.refresh().await.unwrap().sequence, 2);
+
+        // 失败的采集同样占用序列号
+        let failing = SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new("/nonexistent/proc"));
+        assert!(failing.refresh().await.is_err());
+        assert_eq!(failing.sequence.load(Ordering::Acquire), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sequence_continues_from_history() {
+        let proc_root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc");
+        let history = HistoryStore::open_in_memory(Duration::from_secs(3600)).unwrap();
+        // 上一次运行写入的记录
+        SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new(proc_root))
+            .with_history(history.clone())
+            .refresh()
+            .await
+            .unwrap();
+        for _ in 0..100 {
+            if history.last_sequence().unwrap() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(history.last_sequence().unwrap(), 1);
+
+        let cache = SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new(proc_root))
+            .with_history(history);
+        assert_eq!(cache.refresh().await.unwrap().sequence, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_get_or_update_collects_once() {
+        let proc_root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc");
+        let cache = Arc::new(
+            SystemStatsCache::new(Duration::from_secs(10))
+                .with_collector(Collector::new(proc_root)),
+        );
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.get_or_update().await.unwrap().sequence })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), 1);
+        }
+        assert_eq!(cache.sequence.load(Ordering::Acquire), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_update_and_reads() {
+        const UPDATES: u32 = 2_000;
+        let cache = Arc::new(SystemStatsCache::new(Duration::from_secs(60)));
+        cache.update(create_test_stats("host-0", 0.0));
+
+        let writer = {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                for i in 1..=UPDATES {
+                    cache.update(create_test_stats(&format!("host-{i}"), i as f32));
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    let mut reads = 0;
+                    loop {
+                        // 读到的每一份数据都必须是某一次完整写入的结果
+                        let stats = cache.get().unwrap();
+                        assert_eq!(stats.hostname, format!("host-{}", stats.cpu_usage));
+                        let (stats, _) = cache.get_with_age().unwrap();
+                        assert_eq!(stats.hostname, format!("host-{}", stats.cpu_usage));
+                        reads += 1;
+                        if stats.cpu_usage == UPDATES as f32 {
+                            break reads;
+                        }
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+
+        writer.await.unwrap();
+        for reader in readers {
+            assert!(reader.await.unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_missed_periods() {
+        let ttl = Duration::from_secs(10);
+        assert_eq!(missed_periods(Duration::from_millis(10_200), ttl), 0);
+        assert_eq!(missed_periods(Duration::from_millis(14_900), ttl), 0);
+        assert_eq!(missed_periods(Duration::from_secs(20), ttl), 1);
+        assert_eq!(missed_periods(Duration::from_secs(61), ttl), 5);
+        assert_eq!(missed_periods(Duration::from_secs(5), Duration::ZERO), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_get_with_age_ignores_ttl() {
+        let cache = SystemStatsCache::new(Duration::from_millis(10));
+        cache.update(create_test_stats("aged", 0.2));
+
+        sleep(Duration::from_millis(30)).await;
+
+        assert!(cache.get().is_none());
+        let (stats, age) = cache.get_with_age().unwrap();
+        assert_eq!(stats.hostname, "aged");
+        assert!(age >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_align_delay() {
+        let ttl = Duration::from_secs(10);
+        assert_eq!(
+            align_delay(Duration::from_secs(1_700_000_000), ttl),
+            Duration::ZERO
+        );
+        assert_eq!(
+            align_delay(Duration::from_millis(1_700_000_003_250), ttl),
+            Duration::from_millis(6_750)
+        );
+        assert_eq!(
+            align_delay(Duration::from_secs(59), Duration::from_secs(60)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            align_delay(Duration::from_secs(5), Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_background_refresh() {
+        let cache = Arc::new(SystemStatsCache::new(Duration::from_millis(20)));
+        let handle = cache.spawn_background_refresh();
+
+        sleep(Duration::from_millis(100)).await;
+        handle.abort();
+
+        // 在能读取 /proc 的环境中，后台任务无需请求即可填充缓存
+        if cache.refresh_state() != RefreshState::Failed {
+            assert!(cache.get_with_age().is_some());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[cfg(target_os = "linux")]
+    async fn test_aligned_background_refresh() {
+        let ttl = Duration::from_secs(10);
+        let step = Duration::from_millis(10);
+        let cache = Arc::new(
+            SystemStatsCache::new(ttl)
+                .with_collector(Collector::new(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/proc"
+                )))
+                .with_aligned_refresh(true),
+        );
+        let wall_start = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let start = tokio::time::Instant::now();
+        let handle = cache.spawn_background_refresh();
+
+        // 以虚拟时间逐步推进，直到后台任务开始首次采集
+        while cache.refresh_state() == RefreshState::Empty {
+            assert!(start.elapsed() <= ttl, "一个 TTL 内未发生采集");
+            tokio::time::advance(step).await;
+            tokio::task::yield_now().await;
+        }
+        let fired_at = wall_start + start.elapsed();
+        // 采集在 fixture 上进行，等待其完成
+        while cache.refresh_state() == RefreshState::Refreshing {
+            tokio::task::yield_now().await;
+        }
+        handle.abort();
+
+        assert_eq!(cache.refresh_state(), RefreshState::Fresh);
+        assert_eq!(cache.get_with_age().unwrap().0.hostname, "fixture-host");
+        // 首次采集落在整 TTL 边界上（误差不超过推进步长与调度开销）
+        let phase = align_delay(fired_at, ttl).min(ttl - align_delay(fired_at, ttl));
+        assert!(phase < step * 5, "采集时刻距整 TTL 边界 {phase:?}");
+    }
+
+    #[test]
+    fn test_collection_timing() {
+        let timing = CollectionTiming::default();
+        assert!(timing.snapshot().is_none());
+
+        let nominal = Duration::from_secs(10);
+        timing.record(Duration::from_millis(10_300), nominal);
+        timing.record(Duration::from_millis(9_900), nominal);
+
+        let snapshot = timing.snapshot().unwrap();
+        assert_eq!(snapshot.samples, 2);
+        assert_eq!(snapshot.last_interval, Duration::from_millis(9_900));
+        assert_eq!(snapshot.last_jitter, Duration::from_millis(100));
+        assert_eq!(snapshot.max_jitter, Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_refresh_records_actual_interval() {
+        let cache = SystemStatsCache::new(Duration::from_secs(10));
+        let Ok(first) = cache.refresh().await else {
+            return; // 无法读取 /proc 的环境
+        };
+        assert!(first.collection_interval.is_none());
+
+        sleep(Duration::from_millis(20)).await;
+        let second = cache.refresh().await.unwrap();
+        assert!(second.collection_interval.unwrap() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_compact_per_core() {
+        let cache = SystemStatsCache::new(Duration::from_secs(60)).with_compact_per_core(true);
+        let mut stats = create_test_stats("compact", 0.5);
+        stats.cpu_stats.per_core = (0..1024)
+            .map(|i| crate::stats::CpuUsageBreakdown {
+                user_percent: i as f32 / 20.48,
+                total_percent: i as f32 / 10.24,
+                ..Default::default()
+            })
+            .collect();
+        cache.update(stats.clone());
+
+        // 缓存中只保留定点数：1024 个核心占 8 KiB，浮点表示为 16 KiB
+        let cached = cache.load_cached();
+        assert_eq!(cached.stats.cpu_stats.per_core.capacity(), 0);
+        let compact = cached.compact_cores.as_deref().unwrap();
+        assert_eq!(std::mem::size_of_val(compact), 8 * 1024);
+        assert_eq!(
+            std::mem::size_of_val(stats.cpu_stats.per_core.as_slice()),
+            16 * 1024
+        );
+
+        let restored = cache.get().unwrap();
+        assert_eq!(restored.cpu_stats.per_core.len(), 1024);
+        for (original, restored) in stats
+            .cpu_stats
+            .per_core
+            .iter()
+            .zip(&restored.cpu_stats.per_core)
+        {
+            assert!((original.total_percent - restored.total_percent).abs() <= 0.0051);
+            assert!((original.user_percent - restored.user_percent).abs() <= 0.0051);
+        }
+        assert_eq!(restored.hostname, "compact");
+    }
+
     #[tokio::test]
     async fn test_cache_large_ttl() {
         let cache = SystemStatsCache::new(Duration::from_secs(3600)); // 1 小时
@@ -0,0 +1,290 @@
+//! TOML 配置文件
+//!
+//! 配置文件的键与命令行长参数一一对应（`-` 换成 `_`），模板与加载都直接由 clap 的参数元数据
+//! 生成，新增命令行参数无需另外维护配置文件格式。加载时把文件内容展开为命令行参数插在
+//! 实际参数之前，因此命令行上显式给出的参数优先于配置文件。
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgAction, Command};
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::path::Path;
+
+/// 指定配置文件的命令行参数
+pub const CONFIG_ARG: &str = "config";
+
+/// 不属于配置、只在命令行上使用的参数（运行模式与一次性操作）
+const CLI_ONLY_ARGS: &[&str] = &[
+    "help",
+    "version",
+    CONFIG_ARG,
+    "print-default-config",
+    "healthcheck",
+    "stdio",
+];
+
+/// 参与配置文件的参数
+fn config_args(command: &Command) -> impl Iterator<Item = (&Arg, &str)> {
+    command.get_arguments().filter_map(|arg| {
+        arg.get_long()
+            .filter(|long| !CLI_ONLY_ARGS.contains(long))
+            .map(|long| (arg, long))
+    })
+}
+
+/// 生成带注释的完整配置模板：每个参数的说明、默认值，没有默认值的参数以注释形式给出
+pub fn render_template(command: &Command) -> String {
+    let mut out = format!(
+        "# {} 配置文件模板\n# 通过 --{CONFIG_ARG} <FILE> 加载，命令行参数优先于配置文件\n",
+        command.get_name()
+    );
+    for (arg, long) in config_args(command) {
+        let key = long.replace('-', "_");
+        out.push('\n');
+        if let Some(help) = arg.get_help() {
+            for line in help.to_string().lines() {
+                let _ = writeln!(out, "# {line}");
+            }
+        }
+
+        let defaults: Vec<String> = arg
+            .get_default_values()
+            .iter()
+            .map(|value| value.to_string_lossy().into_owned())
+            .collect();
+        match arg.get_action() {
+            ArgAction::SetTrue => {
+                let _ = writeln!(out, "{key} = false");
+            }
+            ArgAction::Append if defaults.is_empty() => {
+                let _ = writeln!(out, "# {key} = []");
+            }
+            ArgAction::Append => {
+                let values: Vec<String> =
+                    defaults.iter().map(|value| toml_literal(value)).collect();
+                let _ = writeln!(out, "{key} = [{}]", values.join(", "));
+            }
+            _ if defaults.is_empty() => {
+                let _ = writeln!(out, "# {key} =");
+            }
+            _ => {
+                let _ = writeln!(out, "{key} = {}", toml_literal(&defaults[0]));
+            }
+        }
+    }
+    out
+}
+
+/// 默认值在 TOML 中的写法：数字保持裸值，其余写成字符串
+fn toml_literal(value: &str) -> String {
+    if value.parse::<i64>().is_ok() || value.parse::<f64>().is_ok_and(f64::is_finite) {
+        value.to_string()
+    } else {
+        toml::Value::String(value.to_string()).to_string()
+    }
+}
+
+/// 把配置文件展开为命令行参数，跳过 `skip` 返回 true 的参数
+fn load_args_except(
+    path: &Path,
+    command: &Command,
+    skip: impl Fn(&Arg) -> bool,
+) -> Result<Vec<OsString>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("无法读取配置文件 {}", path.display()))?;
+    let table: toml::Table = content
+        .parse()
+        .with_context(|| format!("配置文件 {} 格式错误", path.display()))?;
+
+    let mut args = Vec::new();
+    for (key, value) in &table {
+        let long = key.replace('_', "-");
+        let Some((arg, _)) = config_args(command).find(|(_, name)| *name == long) else {
+            bail!("配置文件 {} 中有未知的配置项 `{key}`", path.display());
+        };
+        if skip(arg) {
+            continue;
+        }
+        let flag = format!("--{long}");
+        match (arg.get_action(), value) {
+            (ArgAction::SetTrue, toml::Value::Boolean(enabled)) => {
+                if *enabled {
+                    args.push(flag.into());
+                }
+            }
+            (ArgAction::SetTrue, _) => bail!("配置项 `{key}` 应为 true 或 false"),
+            (ArgAction::Append, toml::Value::Array(values)) => {
+                for value in values {
+                    args.push(flag.clone().into());
+                    args.push(scalar(key, value)?.into());
+                }
+            }
+            (_, value) => {
+                args.push(flag.into());
+                args.push(scalar(key, value)?.into());
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// 标量配置值转为命令行参数值
+fn scalar(key: &str, value: &toml::Value) -> Result<String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        _ => bail!("配置项 `{key}` 应为字符串、数字或布尔值"),
+    }
+}
+
+/// 命令行上是否给出了该参数
+fn given_on_cli(args: &[OsString], arg: &Arg) -> bool {
+    let long = arg.get_long().map(|long| format!("--{long}"));
+    let short = arg.get_short().map(|short| format!("-{short}"));
+    args.iter()
+        .skip(1)
+        .filter_map(|token| token.to_str())
+        .any(|token| {
+            long.as_deref().is_some_and(|long| {
+                token == long
+                    || token
+                        .strip_prefix(long)
+                        .is_some_and(|rest| rest.starts_with('='))
+            }) || short
+                .as_deref()
+                .is_some_and(|short| token.starts_with(short))
+        })
+}
+
+/// 若命令行指定了 `--config`，把配置文件展开后插在其余参数之前
+///
+/// 可重复的参数在命令行上给出时整体取代配置文件中的列表，而不是与之合并。
+pub fn expand_args(args: Vec<OsString>, command: &Command) -> Result<Vec<OsString>> {
+    let flag = format!("--{CONFIG_ARG}");
+    let prefix = format!("--{CONFIG_ARG}=");
+    let path = args.iter().enumerate().skip(1).find_map(|(i, arg)| {
+        let arg = arg.to_str()?;
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(&prefix).map(OsString::from)
+        }
+    });
+    let Some(path) = path else {
+        return Ok(args);
+    };
+
+    let mut expanded = Vec::with_capacity(args.len() + 16);
+    expanded.extend(args.first().cloned());
+    expanded.extend(load_args_except(Path::new(&path), command, |arg| {
+        matches!(arg.get_action(), ArgAction::Append) && given_on_cli(&args, arg)
+    })?);
+    expanded.extend(args.into_iter().skip(1));
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Parser, Debug)]
+    #[command(name = "demo", args_override_self = true)]
+    struct Demo {
+        /// 端口
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        /// 地址
+        #[arg(long, default_value = "::")]
+        address: String,
+        /// 可选阈值
+        #[arg(long)]
+        outlier_threshold: Option<f32>,
+        /// 开关
+        #[arg(long)]
+        background_refresh: bool,
+        /// 可重复
+        #[arg(long = "custom-command")]
+        custom_commands: Vec<String>,
+        /// 可重复且有默认值
+        #[arg(short = 'l', long, default_value = "::")]
+        listen: Vec<String>,
+        /// 配置文件
+        #[arg(long)]
+        config: Option<std::path::PathBuf>,
+    }
+
+    fn write_config(name: &str, content: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("swb-config-{name}-{}.toml", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn parse(args: &[&str]) -> Result<Demo> {
+        let args = args.iter().map(OsString::from).collect();
+        let args = expand_args(args, &Demo::command())?;
+        Ok(Demo::try_parse_from(args)?)
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = render_template(&Demo::command());
+        assert!(template.contains("# 端口\nport = 8080\n"));
+        assert!(template.contains("address = \"::\"\n"));
+        assert!(template.contains("# outlier_threshold =\n"));
+        assert!(template.contains("background_refresh = false\n"));
+        assert!(template.contains("# custom_command = []\n"));
+        assert!(template.contains("listen = [\"::\"]\n"));
+        assert!(!template.contains("config ="));
+
+        // 模板原样加载后等同于默认参数
+        let path = write_config("template", &template);
+        let demo = parse(&["demo", "--config", path.to_str().unwrap()]).unwrap();
+        assert_eq!(demo.port, 8080);
+        assert_eq!(demo.address, "::");
+        assert_eq!(demo.listen, ["::"]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_load_config() {
+        let path = write_config(
+            "load",
+            "port = 9090\noutlier_threshold = 5.5\nbackground_refresh = true\ncustom_command = [\"a\", \"b\"]\nlisten = [\"a\", \"b\"]\n",
+        );
+        let config = format!("--config={}", path.display());
+        let demo = parse(&["demo", &config]).unwrap();
+        assert_eq!(demo.port, 9090);
+        assert_eq!(demo.outlier_threshold, Some(5.5));
+        assert!(demo.background_refresh);
+        assert_eq!(demo.custom_commands, ["a", "b"]);
+
+        assert_eq!(demo.listen, ["a", "b"]);
+
+        // 命令行参数优先，可重复的参数整体取代配置文件中的列表
+        let demo = parse(&["demo", &config, "--port", "1234", "-l", "c"]).unwrap();
+        assert_eq!(demo.port, 1234);
+        assert_eq!(demo.listen, ["c"]);
+        assert_eq!(demo.custom_commands, ["a", "b"]);
+        let demo = parse(&["demo", &config, "--listen=d"]).unwrap();
+        assert_eq!(demo.listen, ["d"]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_load_config_errors() {
+        let path = write_config("unknown", "no_such_option = 1\n");
+        let error = parse(&["demo", "--config", path.to_str().unwrap()]).unwrap_err();
+        assert!(error.to_string().contains("no_such_option"));
+        let _ = std::fs::remove_file(path);
+
+        let path = write_config("flag", "background_refresh = 1\n");
+        assert!(parse(&["demo", "--config", path.to_str().unwrap()]).is_err());
+        let _ = std::fs::remove_file(path);
+
+        assert!(parse(&["demo", "--config", "/nonexistent/swb.toml"]).is_err());
+    }
+}
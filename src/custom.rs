@@ -0,0 +1,207 @@
+//! 自定义采集插件（外部命令）
+//!
+//! 每个外部命令通过 `sh -c` 执行，向 stdout 输出若干 `key=value` 行，
+//! 结果合并进 `SystemStats::custom`。命令执行受超时和输出大小限制，
+//! 任一命令失败只会被记录日志，不影响主采集。
+
+use log::{debug, warn};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+
+/// 外部命令采集器
+#[derive(Debug, Clone)]
+pub struct CustomCollector {
+    commands: Vec<String>,
+    timeout: Duration,
+    max_output_bytes: usize,
+}
+
+impl CustomCollector {
+    /// 创建外部命令采集器
+    #[inline]
+    pub fn new(commands: Vec<String>, timeout: Duration, max_output_bytes: usize) -> Self {
+        Self {
+            commands,
+            timeout,
+            max_output_bytes,
+        }
+    }
+
+    /// 并发执行所有命令并合并结果，后执行完成的命令覆盖同名指标
+    ///
+    /// 同时返回失败的命令数。
+    pub async fn collect(&self) -> (BTreeMap<String, f64>, usize) {
+        let mut tasks = JoinSet::new();
+        for command in &self.commands {
+            let command = command.clone();
+            let timeout = self.timeout;
+            let max_output_bytes = self.max_output_bytes;
+            tasks.spawn(async move {
+                let result = run_command(&command, timeout, max_output_bytes).await;
+                (command, result)
+            });
+        }
+
+        let mut metrics = BTreeMap::new();
+        let mut failed = 0;
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((_, Ok(output))) => metrics.extend(parse_output(&output)),
+                Ok((command, Err(e))) => {
+                    failed += 1;
+                    warn!("自定义采集命令 `{command}` 失败: {e}");
+                }
+                Err(e) => {
+                    failed += 1;
+                    warn!("自定义采集任务异常: {e}");
+                }
+            }
+        }
+        (metrics, failed)
+    }
+}
+
+/// 执行单个命令，返回其 stdout 内容
+async fn run_command(
+    command: &str,
+    timeout: Duration,
+    max_output_bytes: usize,
+) -> std::result::Result<String, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("无法启动: {e}"))?;
+
+    let mut stdout = child.stdout.take().ok_or("无法获取 stdout")?;
+
+    let run = async {
+        // 多读一个字节用于判断输出是否超限
+        let mut output = Vec::with_capacity(max_output_bytes.min(4096));
+        (&mut stdout)
+            .take(max_output_bytes as u64 + 1)
+            .read_to_end(&mut output)
+            .await
+            .map_err(|e| format!("读取输出失败: {e}"))?;
+        if output.len() > max_output_bytes {
+            return Err(format!("输出超过 {max_output_bytes} 字节限制"));
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| format!("等待退出失败: {e}"))?;
+        if !status.success() {
+            return Err(format!("退出状态 {status}"));
+        }
+
+        String::from_utf8(output).map_err(|_| "输出不是有效的 UTF-8".to_string())
+    };
+
+    // 超时或出错时 child 被 drop，kill_on_drop 会终止子进程
+    tokio::time::timeout(timeout, run)
+        .await
+        .map_err(|_| format!("执行超过 {} 毫秒超时", timeout.as_millis()))?
+}
+
+/// 判断指标名是否合法（与 Prometheus 指标名字符集一致，不含冒号）
+#[inline]
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 解析 `key=value` 行，忽略空行、`#` 注释和无效行
+fn parse_output(output: &str) -> BTreeMap<String, f64> {
+    let mut metrics = BTreeMap::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            debug!("忽略无效的自定义指标行: {line}");
+            continue;
+        };
+        let key = key.trim();
+        match value.trim().parse::<f64>() {
+            Ok(value) if is_valid_key(key) && value.is_finite() => {
+                metrics.insert(key.to_string(), value);
+            }
+            _ => debug!("忽略无效的自定义指标行: {line}"),
+        }
+    }
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        let output = "queue_length=42\n\
+                      # 注释\n\
+                      \n\
+                      latency_ms = 1.5\n\
+                      bad-key=1\n\
+                      no_value=\n\
+                      nan_value=NaN\n\
+                      garbage";
+        let metrics = parse_output(output);
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics["queue_length"], 42.0);
+        assert_eq!(metrics["latency_ms"], 1.5);
+    }
+
+    #[test]
+    fn test_is_valid_key() {
+        assert!(is_valid_key("orders_total"));
+        assert!(is_valid_key("_private"));
+        assert!(!is_valid_key(""));
+        assert!(!is_valid_key("1abc"));
+        assert!(!is_valid_key("a.b"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_merges_commands() {
+        let collector = CustomCollector::new(
+            vec![
+                "echo a=1; echo b=2".to_string(),
+                "echo c=3.5".to_string(),
+                "exit 1".to_string(),
+            ],
+            Duration::from_secs(5),
+            1024,
+        );
+        let (metrics, failed) = collector.collect().await;
+        assert_eq!(failed, 1);
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics["a"], 1.0);
+        assert_eq!(metrics["c"], 3.5);
+    }
+
+    #[tokio::test]
+    async fn test_command_timeout() {
+        let result = run_command("sleep 5", Duration::from_millis(50), 1024).await;
+        assert!(result.unwrap_err().contains("超时"));
+    }
+
+    #[tokio::test]
+    async fn test_command_output_limit() {
+        let result = run_command("echo 0123456789", Duration::from_secs(5), 4).await;
+        assert!(result.unwrap_err().contains("字节限制"));
+    }
+}
@@ -0,0 +1,210 @@
+//! 采集异常值剔除
+//!
+//! 偶发的 /proc 读取可能给出明显异常的 CPU 使用率（瞬间 100% 后恢复），
+//! 在趋势上形成尖刺。启用后，若某项使用率相对最近若干样本的中位数
+//! 偏离超过阈值（百分点），则用该项上一个被接受的值替代。
+//!
+//! 原始值仍会进入滑动窗口，持续的真实突发会在约半个窗口后被接受，不会被长期掩盖。
+
+use crate::stats::{CpuUsageBreakdown, SystemStats};
+use log::debug;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// 进行判定前窗口内至少需要的样本数
+const MIN_SAMPLES: usize = 3;
+
+/// 单项指标的滑动窗口
+#[derive(Debug, Default)]
+struct MetricWindow {
+    samples: VecDeque<f32>,
+    last_accepted: Option<CpuUsageBreakdown>,
+}
+
+impl MetricWindow {
+    /// 计算窗口内样本的中位数
+    fn median(&self) -> Option<f32> {
+        if self.samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<f32> = self.samples.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        let mid = sorted.len() / 2;
+        Some(if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        })
+    }
+
+    /// 判定并在必要时替换当前值，返回是否发生了替换
+    fn filter(
+        &mut self,
+        value: &mut CpuUsageBreakdown,
+        window: usize,
+        threshold: f32,
+        core: Option<usize>,
+    ) -> bool {
+        let raw = value.total_percent;
+        let mut replaced = false;
+
+        if let (Some(median), Some(last)) = (self.median(), &self.last_accepted)
+            && (raw - median).abs() > threshold
+        {
+            let name = match core {
+                Some(i) => format!("核心 {i}"),
+                None => "总体 CPU".to_string(),
+            };
+            debug!(
+                "剔除异常值: {name} = {raw:.1}%，中位数 {median:.1}%，沿用上一个值 {:.1}%",
+                last.total_percent
+            );
+            *value = last.clone();
+            replaced = true;
+        }
+
+        if !replaced {
+            self.last_accepted = Some(value.clone());
+        }
+
+        self.samples.push_back(raw);
+        while self.samples.len() > window {
+            self.samples.pop_front();
+        }
+
+        replaced
+    }
+}
+
+#[derive(Debug, Default)]
+struct FilterState {
+    overall: MetricWindow,
+    per_core: Vec<MetricWindow>,
+}
+
+/// CPU 使用率离群值过滤器
+#[derive(Debug)]
+pub struct OutlierFilter {
+    window: usize,
+    threshold: f32,
+    state: Mutex<FilterState>,
+}
+
+impl OutlierFilter {
+    /// 创建过滤器
+    ///
+    /// `window` 为参与中位数计算的最近样本数，`threshold` 为允许的最大偏离（百分点）。
+    #[inline]
+    pub fn new(window: usize, threshold: f32) -> Self {
+        Self {
+            window: window.max(MIN_SAMPLES),
+            threshold,
+            state: Mutex::new(FilterState::default()),
+        }
+    }
+
+    /// 对一次采集结果执行离群值剔除
+    pub fn apply(&self, stats: &mut SystemStats) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let FilterState { overall, per_core } = &mut *state;
+
+        if overall.filter(
+            &mut stats.cpu_stats.overall,
+            self.window,
+            self.threshold,
+            None,
+        ) {
+            stats.cpu_usage = stats.cpu_stats.overall.total_percent / 100.0;
+        }
+
+        // 核心数量变化（如 CPU 热插拔）时重建窗口
+        if per_core.len() != stats.cpu_stats.per_core.len() {
+            per_core.clear();
+            per_core.resize_with(stats.cpu_stats.per_core.len(), MetricWindow::default);
+        }
+        for (i, (window, core)) in per_core
+            .iter_mut()
+            .zip(stats.cpu_stats.per_core.iter_mut())
+            .enumerate()
+        {
+            window.filter(core, self.window, self.threshold, Some(i));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_with(overall: f32, cores: &[f32]) -> SystemStats {
+        let breakdown = |total: f32| CpuUsageBreakdown {
+            user_percent: total / 2.0,
+            nice_percent: 0.0,
+            system_percent: total / 2.0,
+            total_percent: total,
+        };
+        let mut stats = SystemStats {
+            cpu_usage: overall / 100.0,
+            ..SystemStats::default()
+        };
+        stats.cpu_stats.overall = breakdown(overall);
+        stats.cpu_stats.per_core = cores.iter().copied().map(breakdown).collect();
+        stats.cpu_stats.core_count = cores.len();
+        stats
+    }
+
+    #[test]
+    fn test_outlier_replaced_with_last_value() {
+        let filter = OutlierFilter::new(5, 30.0);
+        for value in [10.0, 12.0, 11.0] {
+            let mut stats = stats_with(value, &[value]);
+            filter.apply(&mut stats);
+            assert_eq!(stats.cpu_stats.overall.total_percent, value);
+        }
+
+        let mut spike = stats_with(100.0, &[100.0]);
+        filter.apply(&mut spike);
+        assert_eq!(spike.cpu_stats.overall.total_percent, 11.0);
+        assert!((spike.cpu_usage - 0.11).abs() < f32::EPSILON);
+        assert_eq!(spike.cpu_stats.per_core[0].total_percent, 11.0);
+    }
+
+    #[test]
+    fn test_values_within_threshold_kept() {
+        let filter = OutlierFilter::new(5, 30.0);
+        for value in [10.0, 12.0, 11.0, 35.0] {
+            let mut stats = stats_with(value, &[]);
+            filter.apply(&mut stats);
+            assert_eq!(stats.cpu_stats.overall.total_percent, value);
+        }
+    }
+
+    #[test]
+    fn test_sustained_burst_accepted() {
+        let filter = OutlierFilter::new(5, 30.0);
+        for value in [10.0, 10.0, 10.0] {
+            filter.apply(&mut stats_with(value, &[]));
+        }
+
+        // 持续的高负载在中位数跟上后被接受
+        let results: Vec<f32> = (0..4)
+            .map(|_| {
+                let mut stats = stats_with(95.0, &[]);
+                filter.apply(&mut stats);
+                stats.cpu_stats.overall.total_percent
+            })
+            .collect();
+        assert_eq!(results[0], 10.0);
+        assert_eq!(*results.last().unwrap(), 95.0);
+    }
+
+    #[test]
+    fn test_no_filtering_before_enough_samples() {
+        let filter = OutlierFilter::new(5, 1.0);
+        for value in [0.0, 100.0] {
+            let mut stats = stats_with(value, &[]);
+            filter.apply(&mut stats);
+            assert_eq!(stats.cpu_stats.overall.total_percent, value);
+        }
+    }
+}
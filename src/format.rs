@@ -0,0 +1,190 @@
+//! 页面展示用的数字格式化
+//!
+//! 只用于服务端渲染的 HTML 页面：按区域习惯插入千位分隔符、替换小数点，
+//! 并把字节数换算为 KiB/MiB/GiB 等二进制单位。JSON 与指标端点始终输出裸数字。
+
+use std::str::FromStr;
+
+/// 二进制单位，依次相差 1024 倍
+const BYTE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// 数字格式区域
+///
+/// 按区域标签（如 `zh-CN`、`de-DE`）的语言部分归类为几种分隔符习惯。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// 不分组，小数点为 `.`（`C`、`POSIX`）
+    Plain,
+    /// `,` 分组，小数点为 `.`：1,024.5（zh、en、ja、ko 等）
+    #[default]
+    Comma,
+    /// `.` 分组，小数点为 `,`：1.024,5（de、es、it、nl、pt 等）
+    Dot,
+    /// 不换行空格分组，小数点为 `,`：1 024,5（fr、ru、pl、sv 等）
+    Space,
+    /// `'` 分组，小数点为 `.`：1'024.5（瑞士，如 de-CH）
+    Apostrophe,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        // 兼容 zh_CN.UTF-8 这类 POSIX 写法
+        let tag = tag.split('.').next().unwrap_or_default().replace('_', "-");
+        let mut parts = tag.split('-');
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+
+        if region == "CH" || region == "LI" {
+            return Ok(Self::Apostrophe);
+        }
+        match language.as_str() {
+            "c" | "posix" => Ok(Self::Plain),
+            "zh" | "en" | "ja" | "ko" | "th" | "he" | "hi" => Ok(Self::Comma),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+            | "sr" | "vi" => Ok(Self::Dot),
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu" | "bg"
+            | "lt" | "lv" | "et" => Ok(Self::Space),
+            _ => Err(format!("不支持的区域：{tag}")),
+        }
+    }
+}
+
+impl Locale {
+    /// 千位分隔符，`None` 表示不分组
+    #[inline]
+    fn grouping(self) -> Option<char> {
+        match self {
+            Self::Plain => None,
+            Self::Comma => Some(','),
+            Self::Dot => Some('.'),
+            Self::Space => Some('\u{a0}'),
+            Self::Apostrophe => Some('\''),
+        }
+    }
+
+    /// 小数点
+    #[inline]
+    fn decimal(self) -> char {
+        match self {
+            Self::Dot | Self::Space => ',',
+            Self::Plain | Self::Comma | Self::Apostrophe => '.',
+        }
+    }
+
+    /// 格式化整数
+    #[inline]
+    pub fn format_integer(self, value: u64) -> String {
+        self.localize(&value.to_string())
+    }
+
+    /// 格式化浮点数，保留 `value` 的 `Display` 精度
+    #[inline]
+    pub fn format_number(self, value: f64) -> String {
+        if value.is_finite() {
+            self.localize(&value.to_string())
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// 将字节数换算为合适的二进制单位，保留至多一位小数
+    pub fn format_bytes(self, bytes: u64) -> String {
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while unit + 1 < BYTE_UNITS.len() {
+            // 按舍入后的值判断，避免出现 "1024 MiB"
+            if (value * 10.0).round() / 10.0 < 1024.0 {
+                break;
+            }
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        let number = if unit == 0 {
+            bytes.to_string()
+        } else {
+            let rounded = format!("{value:.1}");
+            rounded.strip_suffix(".0").unwrap_or(&rounded).to_string()
+        };
+        format!("{} {}", self.localize(&number), BYTE_UNITS[unit])
+    }
+
+    /// 将 `-1234.5` 形式的数字字符串改写为本区域格式
+    fn localize(self, plain: &str) -> String {
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+
+        let mut result = String::with_capacity(plain.len() + integer.len() / 3 * 2);
+        result.push_str(sign);
+        for (i, digit) in integer.chars().enumerate() {
+            if let Some(separator) = self.grouping()
+                && i > 0
+                && (integer.len() - i) % 3 == 0
+            {
+                result.push(separator);
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push(self.decimal());
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_str() {
+        assert_eq!("zh-CN".parse(), Ok(Locale::Comma));
+        assert_eq!("en_US.UTF-8".parse(), Ok(Locale::Comma));
+        assert_eq!("de-DE".parse(), Ok(Locale::Dot));
+        assert_eq!("fr".parse(), Ok(Locale::Space));
+        assert_eq!("de-CH".parse(), Ok(Locale::Apostrophe));
+        assert_eq!("C".parse(), Ok(Locale::Plain));
+        assert!("xx-YY".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_format_integer() {
+        assert_eq!(Locale::Comma.format_integer(1024), "1,024");
+        assert_eq!(Locale::Dot.format_integer(1024), "1.024");
+        assert_eq!(Locale::Space.format_integer(1024), "1\u{a0}024");
+        assert_eq!(Locale::Apostrophe.format_integer(1_234_567), "1'234'567");
+        assert_eq!(Locale::Plain.format_integer(1_234_567), "1234567");
+        assert_eq!(Locale::Comma.format_integer(999), "999");
+        assert_eq!(Locale::Comma.format_integer(0), "0");
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(Locale::Comma.format_number(-1234.5), "-1,234.5");
+        assert_eq!(Locale::Dot.format_number(1234.5), "1.234,5");
+        assert_eq!(Locale::Space.format_number(0.25), "0,25");
+        assert_eq!(Locale::Comma.format_number(42.0), "42");
+        assert_eq!(Locale::Comma.format_number(f64::NAN), "NaN");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(Locale::Comma.format_bytes(512), "512 B");
+        assert_eq!(Locale::Comma.format_bytes(1536), "1.5 KiB");
+        assert_eq!(Locale::Comma.format_bytes(2 * 1024 * 1024 * 1024), "2 GiB");
+        assert_eq!(Locale::Dot.format_bytes(1536 * 1024 * 1024), "1,5 GiB");
+        // 舍入后满 1024 时进位到下一个单位
+        assert_eq!(Locale::Comma.format_bytes(1024 * 1024 - 1), "1 MiB");
+        // TiB 之后不再进位，仍按千位分组
+        assert_eq!(Locale::Comma.format_bytes(2048 << 40), "2,048 TiB");
+    }
+}
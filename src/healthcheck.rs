@@ -0,0 +1,140 @@
+//! CLI 探针模式
+//!
+//! 以 `--healthcheck` 启动时不提供服务，而是请求本机运行中实例的 `/health` 端点，
+//! 健康时退出码为 0，否则为 1，供容器 `HEALTHCHECK CMD` 或 `exec` 探针直接使用。
+
+use anyhow::{Result, bail};
+use hyper::{Body, Request, StatusCode};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// 单次探测的超时
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 探测目标地址：监听在通配地址上的实例通过回环地址访问
+#[inline]
+pub fn probe_address(bind: SocketAddr) -> SocketAddr {
+    let mut addr = bind;
+    match &mut addr {
+        SocketAddr::V4(v4) if v4.ip().is_unspecified() => v4.set_ip(Ipv4Addr::LOCALHOST),
+        SocketAddr::V6(v6) if v6.ip().is_unspecified() => v6.set_ip(Ipv6Addr::LOCALHOST),
+        _ => {}
+    }
+    addr
+}
+
+/// 请求 `addr` 上实例的 `/health`，返回 200 时给出响应内容，否则返回错误
+///
+/// 直接连接套接字地址再在连接上发送请求，不把地址拼进 URI：带 scope id 的
+/// 链路本地 IPv6 地址（如 `[fe80::1%2]:8080`）写进 URI 后要交给解析器重新解析，
+/// 是否可用取决于平台的 `getaddrinfo`。
+pub async fn probe(addr: SocketAddr, timeout: Duration) -> Result<String> {
+    let request = async {
+        let stream = TcpStream::connect(addr).await?;
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+        tokio::spawn(connection);
+        let request = Request::get("/health")
+            .header(hyper::header::HOST, "localhost")
+            .body(Body::empty())?;
+        anyhow::Ok(sender.send_request(request).await?)
+    };
+    let response = match tokio::time::timeout(timeout, request).await {
+        Ok(response) => response.map_err(|e| anyhow::anyhow!("无法连接到 {addr}：{e}"))?,
+        Err(_) => bail!("请求 {addr} 超时（{} 秒）", timeout.as_secs()),
+    };
+    let status = response.status();
+    let body =
+        match tokio::time::timeout(timeout, hyper::body::to_bytes(response.into_body())).await {
+            Ok(body) => String::from_utf8_lossy(&body?).trim().to_string(),
+            Err(_) => bail!("读取 {addr} 的响应超时"),
+        };
+
+    if status == StatusCode::OK {
+        Ok(body)
+    } else {
+        bail!("实例不健康（HTTP {}）：{body}", status.as_u16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+    use std::convert::Infallible;
+
+    /// 启动一个对所有请求返回固定状态码的本地服务，返回其地址
+    fn spawn_server(status: StatusCode) -> SocketAddr {
+        let make_svc = make_service_fn(move |_conn| async move {
+            Ok::<_, Infallible>(service_fn(move |_req| async move {
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(status)
+                        .body(Body::from("OK"))
+                        .unwrap(),
+                )
+            }))
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    #[test]
+    fn test_probe_address() {
+        let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+        assert_eq!(probe_address(addr("0.0.0.0:8080")), addr("127.0.0.1:8080"));
+        assert_eq!(probe_address(addr("[::]:8080")), addr("[::1]:8080"));
+        assert_eq!(
+            probe_address(addr("192.168.1.2:80")),
+            addr("192.168.1.2:80")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_healthy() {
+        let addr = spawn_server(StatusCode::OK);
+        assert_eq!(probe(addr, PROBE_TIMEOUT).await.unwrap(), "OK");
+    }
+
+    #[tokio::test]
+    async fn test_probe_unhealthy() {
+        let addr = spawn_server(StatusCode::SERVICE_UNAVAILABLE);
+        let error = probe(addr, PROBE_TIMEOUT).await.unwrap_err();
+        assert!(error.to_string().contains("HTTP 503"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_scoped_ipv6() {
+        // 测试环境未必有链路本地地址，用带 scope id 的 ::1 代替
+        let Ok(listener) = std::net::TcpListener::bind("[::1]:0") else {
+            return; // 未启用 IPv6 的环境
+        };
+        let SocketAddr::V6(mut addr) = listener.local_addr().unwrap() else {
+            unreachable!()
+        };
+        drop(listener);
+        let server = Server::bind(&SocketAddr::V6(addr)).serve(make_service_fn(|_conn| async {
+            Ok::<_, Infallible>(service_fn(|_req| async {
+                Ok::<_, Infallible>(Response::new(Body::from("OK")))
+            }))
+        }));
+        tokio::spawn(server);
+
+        addr.set_scope_id(1);
+        let scoped = SocketAddr::V6(addr);
+        assert_eq!(probe(scoped, PROBE_TIMEOUT).await.unwrap(), "OK");
+    }
+
+    #[tokio::test]
+    async fn test_probe_refused() {
+        // 绑定后立即释放，得到一个大概率无人监听的端口
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        assert!(probe(addr, PROBE_TIMEOUT).await.is_err());
+    }
+}
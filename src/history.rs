@@ -0,0 +1,825 @@
+//! 采集历史持久化（本地 SQLite）
+//!
+//! 每次采集的关键指标写入 `samples` 表，按时间范围查询，并按保留期定期清理。
+//! 所有数据库操作在阻塞线程池中执行，写入失败只记录日志，不影响主服务。
+//!
+//! 连续样本的大部分字段变化缓慢，因此按"基线 + 增量"编码存储：
+//! 每 `KEYFRAME_INTERVAL` 条写一条基线（全部字段的绝对值），其余只存与上一条相比
+//! 发生变化的字段的差值。每行带校验和，查询时从范围起点之前最近的基线开始重建；
+//! 某一行损坏时丢弃到下一条基线为止的记录，之后的数据不受影响。
+
+use crate::stats::{SystemStats, unix_millis};
+use anyhow::Result;
+use log::{debug, info, warn};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 单次查询最多返回的记录数
+pub const HISTORY_QUERY_LIMIT: usize = 10_000;
+
+/// 过期数据清理周期
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// 每多少条记录写一条基线
+pub const KEYFRAME_INTERVAL: usize = 60;
+
+/// 每条记录编码的字段数
+const FIELD_COUNT: usize = 8;
+
+/// `payload` 为基线时是各字段的 varint，为增量时是一个变化字段位图加各变化字段差值的 zigzag varint；
+/// `checksum` 覆盖时间戳、基线标记、`payload` 与 `sequence`（非空时）。
+/// `sequence` 为采集序列号，加入该列之前写入的行为 NULL
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS samples (
+    timestamp_ms INTEGER NOT NULL,
+    keyframe     INTEGER NOT NULL,
+    payload      BLOB    NOT NULL,
+    checksum     INTEGER NOT NULL,
+    sequence     INTEGER
+);
+CREATE INDEX IF NOT EXISTS samples_timestamp ON samples (timestamp_ms);
+";
+
+/// 旧版逐列全量存储的表，打开时迁移到 `samples`
+const LEGACY_TABLE: &str = "stats";
+
+/// 一条历史记录，字段名与 `/api/stats` 保持一致
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryRecord {
+    pub timestamp_ms: u64,
+    /// 采集序列号，相邻记录不连续说明中间有采集失败或被跳过（旧版本写入的记录为 `None`）
+    pub sequence: Option<u64>,
+    pub cpu_usage_ratio: f32,
+    pub memory_total_bytes: u64,
+    pub memory_used_bytes: u64,
+    pub memory_available_bytes: u64,
+    pub memory_cached_bytes: u64,
+    pub memory_free_bytes: u64,
+    pub conntrack_entries: Option<u64>,
+    pub conntrack_entries_limit: Option<u64>,
+}
+
+impl HistoryRecord {
+    /// 从采集数据提取关键指标
+    fn from_stats(stats: &SystemStats, timestamp_ms: u64) -> Self {
+        Self {
+            timestamp_ms,
+            sequence: Some(stats.sequence),
+            cpu_usage_ratio: stats.cpu_usage,
+            memory_total_bytes: stats.memory_total,
+            memory_used_bytes: stats.memory_used,
+            memory_available_bytes: stats.memory_available,
+            memory_cached_bytes: stats.memory_cached,
+            memory_free_bytes: stats.memory_free,
+            conntrack_entries: stats.conntrack.as_ref().map(|c| c.count),
+            conntrack_entries_limit: stats.conntrack.as_ref().map(|c| c.max),
+        }
+    }
+
+    /// 转为参与编码的字段，`None` 记为 0、其余值加 1
+    fn to_fields(&self) -> [u64; FIELD_COUNT] {
+        let optional = |value: Option<u64>| value.map_or(0, |v| v.wrapping_add(1));
+        [
+            u64::from(self.cpu_usage_ratio.to_bits()),
+            self.memory_total_bytes,
+            self.memory_used_bytes,
+            self.memory_available_bytes,
+            self.memory_cached_bytes,
+            self.memory_free_bytes,
+            optional(self.conntrack_entries),
+            optional(self.conntrack_entries_limit),
+        ]
+    }
+
+    /// 由编码字段重建记录
+    fn from_fields(timestamp_ms: u64, sequence: Option<u64>, fields: &[u64; FIELD_COUNT]) -> Self {
+        let optional = |value: u64| value.checked_sub(1);
+        Self {
+            timestamp_ms,
+            sequence,
+            cpu_usage_ratio: f32::from_bits(fields[0] as u32),
+            memory_total_bytes: fields[1],
+            memory_used_bytes: fields[2],
+            memory_available_bytes: fields[3],
+            memory_cached_bytes: fields[4],
+            memory_free_bytes: fields[5],
+            conntrack_entries: optional(fields[6]),
+            conntrack_entries_limit: optional(fields[7]),
+        }
+    }
+}
+
+/// 写入端的编码状态：上一条成功写入的记录
+#[derive(Debug, Default)]
+struct Encoder {
+    prev: Option<(u64, [u64; FIELD_COUNT])>,
+    since_keyframe: usize,
+}
+
+impl Encoder {
+    /// 编码一条记录，返回是否为基线与编码内容
+    ///
+    /// 尚无上一条记录（刚启动或上次写入失败）、距上一条基线已满间隔、或时间戳回退时写基线，
+    /// 保证同一条增量链内时间戳单调不减。
+    fn encode(&self, record: &HistoryRecord) -> (bool, Vec<u8>) {
+        let fields = record.to_fields();
+        match &self.prev {
+            Some((prev_timestamp, prev))
+                if self.since_keyframe + 1 < KEYFRAME_INTERVAL
+                    && record.timestamp_ms >= *prev_timestamp =>
+            {
+                (false, encode_delta(prev, &fields))
+            }
+            _ => (true, encode_keyframe(&fields)),
+        }
+    }
+
+    /// 记录已成功写入
+    fn advance(&mut self, record: &HistoryRecord, keyframe: bool) {
+        self.since_keyframe = if keyframe { 0 } else { self.since_keyframe + 1 };
+        self.prev = Some((record.timestamp_ms, record.to_fields()));
+    }
+}
+
+fn encode_keyframe(fields: &[u64; FIELD_COUNT]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(FIELD_COUNT * 5);
+    for &field in fields {
+        write_varint(&mut payload, field);
+    }
+    payload
+}
+
+fn encode_delta(prev: &[u64; FIELD_COUNT], fields: &[u64; FIELD_COUNT]) -> Vec<u8> {
+    let mut payload = vec![0u8];
+    for (i, (&prev, &field)) in prev.iter().zip(fields).enumerate() {
+        if field != prev {
+            payload[0] |= 1 << i;
+            write_varint(&mut payload, zigzag(field.wrapping_sub(prev) as i64));
+        }
+    }
+    payload
+}
+
+/// 解码一行，`prev` 为 `None` 时按基线解码；内容不完整或有多余字节时返回 `None`
+fn decode(prev: Option<&[u64; FIELD_COUNT]>, payload: &[u8]) -> Option<[u64; FIELD_COUNT]> {
+    let mut cursor = payload;
+    let fields = match prev {
+        None => {
+            let mut fields = [0; FIELD_COUNT];
+            for field in &mut fields {
+                *field = read_varint(&mut cursor)?;
+            }
+            fields
+        }
+        Some(prev) => {
+            let (&mask, rest) = cursor.split_first()?;
+            cursor = rest;
+            let mut fields = *prev;
+            for (i, field) in fields.iter_mut().enumerate() {
+                if mask & (1 << i) != 0 {
+                    *field = field.wrapping_add(unzigzag(read_varint(&mut cursor)?) as u64);
+                }
+            }
+            fields
+        }
+    };
+    cursor.is_empty().then_some(fields)
+}
+
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[inline]
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(cursor: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = cursor.split_first()?;
+        *cursor = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// 一行的 FNV-1a 校验和
+///
+/// `sequence` 为空时与加入序列号之前的算法一致，旧数据无需重算。
+fn checksum(timestamp_ms: u64, keyframe: bool, payload: &[u8], sequence: Option<u64>) -> u32 {
+    let sequence = sequence.map(u64::to_le_bytes);
+    timestamp_ms
+        .to_le_bytes()
+        .iter()
+        .chain([&u8::from(keyframe)])
+        .chain(payload)
+        .chain(sequence.iter().flatten())
+        .fold(0x811c_9dc5u32, |hash, &byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        })
+}
+
+/// 在已持锁的连接上编码并写入一条记录，失败时编码状态不前进
+fn insert_encoded(
+    conn: &Connection,
+    encoder: &mut Encoder,
+    record: &HistoryRecord,
+) -> rusqlite::Result<()> {
+    let (keyframe, payload) = encoder.encode(record);
+    let sum = checksum(record.timestamp_ms, keyframe, &payload, record.sequence);
+    conn.prepare_cached(
+        "INSERT INTO samples (timestamp_ms, keyframe, payload, checksum, sequence) \
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?
+    .execute(params![
+        record.timestamp_ms as i64,
+        keyframe,
+        payload,
+        sum,
+        record.sequence.map(|sequence| sequence as i64)
+    ])?;
+    encoder.advance(record, keyframe);
+    Ok(())
+}
+
+/// 删除时间戳早于 `cutoff_ms` 的记录
+///
+/// 截止时间之前最近一条基线之前的行可以整体删除；该基线所在的增量链中过期的前缀也删除，
+/// 并把第一条保留的行改写为基线，使其后的增量仍能重建。链的最后一行总是保留，
+/// 因为写入端的下一条增量以它为参照。
+fn purge_before(conn: &Connection, cutoff_ms: u64) -> rusqlite::Result<usize> {
+    let cutoff = cutoff_ms.min(i64::MAX as u64) as i64;
+    let tx = conn.unchecked_transaction()?;
+    let Some(chain_start) = tx.query_row(
+        "SELECT MAX(rowid) FROM samples WHERE keyframe = 1 AND timestamp_ms <= ?1",
+        params![cutoff],
+        |row| row.get::<_, Option<i64>>(0),
+    )?
+    else {
+        return Ok(0);
+    };
+    let mut deleted = tx.execute("DELETE FROM samples WHERE rowid < ?1", params![chain_start])?;
+
+    // 读出该基线所在的整条增量链
+    let chain = {
+        let mut stmt = tx.prepare(
+            "SELECT rowid, timestamp_ms, keyframe, payload, checksum, sequence FROM samples \
+             WHERE rowid >= ?1 ORDER BY rowid",
+        )?;
+        let mut rows = stmt.query(params![chain_start])?;
+        let mut chain = Vec::new();
+        while let Some(row) = rows.next()? {
+            let rowid: i64 = row.get(0)?;
+            let keyframe: bool = row.get(2)?;
+            if keyframe && rowid != chain_start {
+                break;
+            }
+            let timestamp_ms = row.get::<_, i64>(1)? as u64;
+            let payload: Vec<u8> = row.get(3)?;
+            let sequence = row
+                .get::<_, Option<i64>>(5)?
+                .map(|sequence| sequence as u64);
+            let valid =
+                row.get::<_, u32>(4)? == checksum(timestamp_ms, keyframe, &payload, sequence);
+            chain.push((rowid, timestamp_ms, keyframe, payload, sequence, valid));
+        }
+        chain
+    };
+
+    let mut prev = None;
+    let mut survivor = None;
+    for (i, (rowid, timestamp_ms, keyframe, payload, sequence, valid)) in chain.iter().enumerate() {
+        let fields = match (*valid, *keyframe) {
+            (true, true) => decode(None, payload),
+            (true, false) => prev.as_ref().and_then(|prev| decode(Some(prev), payload)),
+            (false, _) => None,
+        };
+        // 链已损坏时无法改写基线，只删除基线之前的行
+        let Some(fields) = fields else {
+            tx.commit()?;
+            return Ok(deleted);
+        };
+        if *timestamp_ms >= cutoff_ms || i + 1 == chain.len() {
+            survivor = Some((*rowid, *timestamp_ms, *sequence, fields));
+            break;
+        }
+        prev = Some(fields);
+    }
+
+    if let Some((rowid, timestamp_ms, sequence, fields)) = survivor
+        && rowid != chain_start
+    {
+        deleted += tx.execute("DELETE FROM samples WHERE rowid < ?1", params![rowid])?;
+        let payload = encode_keyframe(&fields);
+        tx.execute(
+            "UPDATE samples SET keyframe = 1, payload = ?1, checksum = ?2 WHERE rowid = ?3",
+            params![
+                payload,
+                checksum(timestamp_ms, true, &payload, sequence),
+                rowid
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// 为加入序列号之前创建的 `samples` 表补上 `sequence` 列，已有的行保持 NULL
+fn add_sequence_column(conn: &Connection) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('samples') WHERE name = 'sequence'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch("ALTER TABLE samples ADD COLUMN sequence INTEGER")?;
+    }
+    Ok(())
+}
+
+/// 把旧版 `stats` 表中的记录按时间顺序编码写入 `samples` 后删除旧表
+fn migrate_legacy(conn: &mut Connection) -> rusqlite::Result<usize> {
+    let exists: Option<String> = conn
+        .query_row(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![LEGACY_TABLE],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if exists.is_none() {
+        return Ok(0);
+    }
+
+    let tx = conn.transaction()?;
+    let records = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT * FROM {LEGACY_TABLE} ORDER BY timestamp_ms"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(HistoryRecord {
+                timestamp_ms: row.get::<_, i64>(0)? as u64,
+                sequence: None,
+                cpu_usage_ratio: row.get(1)?,
+                memory_total_bytes: row.get::<_, i64>(2)? as u64,
+                memory_used_bytes: row.get::<_, i64>(3)? as u64,
+                memory_available_bytes: row.get::<_, i64>(4)? as u64,
+                memory_cached_bytes: row.get::<_, i64>(5)? as u64,
+                memory_free_bytes: row.get::<_, i64>(6)? as u64,
+                conntrack_entries: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                conntrack_entries_limit: row.get::<_, Option<i64>>(8)?.map(|v| v as u64),
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    let mut encoder = Encoder::default();
+    for record in &records {
+        insert_encoded(&tx, &mut encoder, record)?;
+    }
+    tx.execute_batch(&format!("DROP TABLE {LEGACY_TABLE}"))?;
+    tx.commit()?;
+    Ok(records.len())
+}
+
+/// SQLite 历史存储
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    conn: Arc<Mutex<Connection>>,
+    /// 只在持有 `conn` 锁时访问，保证编码顺序与写入顺序一致
+    encoder: Arc<Mutex<Encoder>>,
+    retention: Duration,
+}
+
+impl HistoryStore {
+    /// 打开（必要时创建）数据库文件
+    pub fn open(path: &Path, retention: Duration) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?, retention)
+    }
+
+    /// 使用内存数据库（供测试使用）
+    #[cfg(test)]
+    pub(crate) fn open_in_memory(retention: Duration) -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, retention)
+    }
+
+    fn with_connection(mut conn: Connection, retention: Duration) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        add_sequence_column(&conn)?;
+        let migrated = migrate_legacy(&mut conn)?;
+        if migrated > 0 {
+            info!("已将 {migrated} 条旧格式采集历史迁移为增量编码");
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            encoder: Arc::new(Mutex::new(Encoder::default())),
+            retention,
+        })
+    }
+
+    /// 在阻塞线程池中执行数据库操作
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&conn).map_err(Into::into)
+        })
+        .await?
+    }
+
+    async fn insert_record(&self, record: HistoryRecord) -> Result<()> {
+        let encoder = Arc::clone(&self.encoder);
+        self.with_conn(move |conn| {
+            let mut encoder = encoder.lock().unwrap_or_else(|e| e.into_inner());
+            let result = insert_encoded(conn, &mut encoder, &record);
+            if result.is_err() {
+                // 写入失败后下一条从基线重新开始
+                *encoder = Encoder::default();
+            }
+            result
+        })
+        .await
+    }
+
+    /// 库中最大的采集序列号，没有带序列号的记录时为 0
+    pub fn last_sequence(&self) -> Result<u64> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let last: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(sequence), 0) FROM samples",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(last as u64)
+    }
+
+    /// 后台写入一次采集结果，失败只记录日志
+    pub fn record(&self, stats: &SystemStats) {
+        let store = self.clone();
+        // 以采集时刻的墙上时间入库；未经采集器构造的数据没有该时间，取写入时刻
+        let timestamp_ms = match stats.collected_at_unix_ms {
+            0 => unix_millis(),
+            collected_at => collected_at,
+        };
+        let record = HistoryRecord::from_stats(stats, timestamp_ms);
+        tokio::spawn(async move {
+            if let Err(e) = store.insert_record(record).await {
+                warn!("写入采集历史失败: {e}");
+            }
+        });
+    }
+
+    /// 查询 `[from_ms, to_ms]` 范围内的记录，按时间升序，最多返回 `HISTORY_QUERY_LIMIT` 条
+    ///
+    /// 从 `from_ms` 之前最近的基线开始按写入顺序重建，校验失败或无法解码的行
+    /// 连同其后到下一条基线之间的记录都会被跳过。
+    pub async fn query(&self, from_ms: u64, to_ms: u64) -> Result<Vec<HistoryRecord>> {
+        let from = from_ms.min(i64::MAX as u64) as i64;
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT timestamp_ms, keyframe, payload, checksum, sequence FROM samples \
+                 WHERE rowid >= (SELECT COALESCE(MAX(rowid), 0) FROM samples \
+                                 WHERE keyframe = 1 AND timestamp_ms <= ?1) \
+                 ORDER BY rowid",
+            )?;
+            let mut rows = stmt.query(params![from])?;
+
+            let mut records = Vec::new();
+            let mut prev: Option<[u64; FIELD_COUNT]> = None;
+            let mut corrupted = 0usize;
+            while let Some(row) = rows.next()? {
+                let timestamp_ms = row.get::<_, i64>(0)? as u64;
+                if timestamp_ms > to_ms || records.len() >= HISTORY_QUERY_LIMIT {
+                    break;
+                }
+                let keyframe: bool = row.get(1)?;
+                let payload: Vec<u8> = row.get(2)?;
+                let sequence = row
+                    .get::<_, Option<i64>>(4)?
+                    .map(|sequence| sequence as u64);
+                let valid =
+                    row.get::<_, u32>(3)? == checksum(timestamp_ms, keyframe, &payload, sequence);
+
+                let fields = match (valid, keyframe, &prev) {
+                    (false, _, _) => None,
+                    (true, true, _) => decode(None, &payload),
+                    (true, false, Some(prev)) => decode(Some(prev), &payload),
+                    // 增量链已断，等待下一条基线
+                    (true, false, None) => None,
+                };
+                match fields {
+                    Some(fields) => {
+                        if timestamp_ms >= from_ms {
+                            records.push(HistoryRecord::from_fields(
+                                timestamp_ms,
+                                sequence,
+                                &fields,
+                            ));
+                        }
+                        prev = Some(fields);
+                    }
+                    None => {
+                        corrupted += 1;
+                        prev = None;
+                    }
+                }
+            }
+            if corrupted > 0 {
+                warn!("采集历史中有 {corrupted} 行已损坏或无法重建，已从下一条基线恢复");
+            }
+            Ok(records)
+        })
+        .await
+    }
+
+    /// 删除超过保留期的记录，返回删除的条数
+    pub async fn purge_expired(&self) -> Result<usize> {
+        let cutoff = unix_millis().saturating_sub(self.retention.as_millis() as u64);
+        self.with_conn(move |conn| purge_before(conn, cutoff)).await
+    }
+
+    /// 启动定期清理过期数据的后台任务
+    pub fn spawn_cleanup(&self) -> tokio::task::JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                match store.purge_expired().await {
+                    Ok(deleted) => debug!("已清理 {deleted} 条过期采集历史"),
+                    Err(e) => warn!("清理采集历史失败: {e}"),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::ConntrackStats;
+
+    fn record_at(timestamp_ms: u64) -> HistoryRecord {
+        let stats = SystemStats {
+            cpu_usage: 0.5,
+            memory_total: 1024,
+            conntrack: Some(ConntrackStats {
+                count: 1,
+                max: 10,
+                usage_percent: 10.0,
+            }),
+            sequence: timestamp_ms / 1_000,
+            ..SystemStats::default()
+        };
+        HistoryRecord::from_stats(&stats, timestamp_ms)
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_query_range() {
+        let store = HistoryStore::open_in_memory(Duration::from_secs(3600)).unwrap();
+        for timestamp_ms in [1_000, 2_000, 3_000] {
+            store.insert_record(record_at(timestamp_ms)).await.unwrap();
+        }
+
+        let records = store.query(1_500, 3_000).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], record_at(2_000));
+        assert_eq!(records[1].timestamp_ms, 3_000);
+        assert_eq!(records[1].conntrack_entries_limit, Some(10));
+
+        assert!(store.query(4_000, u64::MAX).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_record_uses_collection_time() {
+        let store = HistoryStore::open_in_memory(Duration::from_secs(3600)).unwrap();
+        store.record(&SystemStats {
+            collected_at_unix_ms: 5_000,
+            ..SystemStats::default()
+        });
+
+        // 写入在后台任务中完成
+        let mut records = Vec::new();
+        for _ in 0..100 {
+            records = store.query(0, u64::MAX).await.unwrap();
+            if !records.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].timestamp_ms, 5_000);
+    }
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let base = record_at(1_000);
+        let mut changed = base.clone();
+        changed.cpu_usage_ratio = 0.25;
+        changed.memory_used_bytes = u64::MAX;
+        changed.conntrack_entries = None;
+
+        let keyframe = encode_keyframe(&base.to_fields());
+        assert_eq!(decode(None, &keyframe), Some(base.to_fields()));
+
+        let delta = encode_delta(&base.to_fields(), &changed.to_fields());
+        let fields = decode(Some(&base.to_fields()), &delta).unwrap();
+        assert_eq!(
+            HistoryRecord::from_fields(1_000, base.sequence, &fields),
+            changed
+        );
+
+        // 未变化的记录只占一个位图字节
+        assert_eq!(encode_delta(&base.to_fields(), &base.to_fields()), vec![0]);
+        // 截断或多余字节都视为损坏
+        assert_eq!(
+            decode(Some(&base.to_fields()), &delta[..delta.len() - 1]),
+            None
+        );
+        assert_eq!(decode(None, &[keyframe.as_slice(), &[0]].concat()), None);
+    }
+
+    #[tokio::test]
+    async fn test_keyframes_and_corruption_recovery() {
+        let store = HistoryStore::open_in_memory(Duration::from_secs(3600)).unwrap();
+        let records: Vec<_> = (0..KEYFRAME_INTERVAL as u64 * 2 + 10)
+            .map(|i| {
+                let mut record = record_at(i * 1_000);
+                record.memory_used_bytes = i * 4096;
+                record
+            })
+            .collect();
+        for record in &records {
+            store.insert_record(record.clone()).await.unwrap();
+        }
+        assert_eq!(store.query(0, u64::MAX).await.unwrap(), records);
+        // 从中间开始的查询也能从之前的基线重建
+        let from = KEYFRAME_INTERVAL as u64 * 1_000 + 5_000;
+        let tail = store.query(from, from + 2_000).await.unwrap();
+        assert_eq!(tail, records[KEYFRAME_INTERVAL + 5..=KEYFRAME_INTERVAL + 7]);
+
+        // 损坏第一条增量链中的一行：该行到下一条基线之间的记录丢失，之后完整恢复
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE samples SET payload = x'ff' WHERE rowid = 6", [])
+            .unwrap();
+        let recovered = store.query(0, u64::MAX).await.unwrap();
+        assert_eq!(recovered[..5], records[..5]);
+        assert_eq!(recovered[5..], records[KEYFRAME_INTERVAL..]);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_table() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE stats (timestamp_ms INTEGER, cpu_usage_ratio REAL, \
+             memory_total_bytes INTEGER, memory_used_bytes INTEGER, memory_available_bytes INTEGER, \
+             memory_cached_bytes INTEGER, memory_free_bytes INTEGER, conntrack_entries INTEGER, \
+             conntrack_entries_limit INTEGER);
+             INSERT INTO stats VALUES (2000, 0.5, 1024, 0, 0, 0, 0, 1, 10);
+             INSERT INTO stats VALUES (1000, 0.5, 1024, 0, 0, 0, 0, 1, 10);",
+        )
+        .unwrap();
+
+        let store = HistoryStore::with_connection(conn, Duration::from_secs(3600)).unwrap();
+        let records = store.query(0, u64::MAX).await.unwrap();
+        let legacy = |timestamp_ms| HistoryRecord {
+            sequence: None,
+            ..record_at(timestamp_ms)
+        };
+        assert_eq!(records, vec![legacy(1_000), legacy(2_000)]);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_adds_sequence_column() {
+        // 加入序列号之前的表结构与一行旧数据
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE samples (timestamp_ms INTEGER NOT NULL, keyframe INTEGER NOT NULL, \
+             payload BLOB NOT NULL, checksum INTEGER NOT NULL);",
+        )
+        .unwrap();
+        let old = HistoryRecord {
+            sequence: None,
+            ..record_at(1_000)
+        };
+        let payload = encode_keyframe(&old.to_fields());
+        conn.execute(
+            "INSERT INTO samples VALUES (?1, 1, ?2, ?3)",
+            params![1_000, payload, checksum(1_000, true, &payload, None)],
+        )
+        .unwrap();
+
+        let store = HistoryStore::with_connection(conn, Duration::from_secs(3600)).unwrap();
+        store.insert_record(record_at(2_000)).await.unwrap();
+        store.insert_record(record_at(4_000)).await.unwrap();
+        let records = store.query(0, u64::MAX).await.unwrap();
+        assert_eq!(records, vec![old, record_at(2_000), record_at(4_000)]);
+        // 序列号 2 与 4 之间的缺口说明漏了一次采集
+        assert_eq!(records[1].sequence, Some(2));
+        assert_eq!(records[2].sequence, Some(4));
+        assert_eq!(store.last_sequence().unwrap(), 4);
+
+        // 序列号参与校验
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE samples SET sequence = 3 WHERE rowid = 3", [])
+            .unwrap();
+        assert_eq!(store.query(0, u64::MAX).await.unwrap().len(), 2);
+    }
+
+    /// 用真实采集序列验证压缩率：fixture 为一台 1 核虚拟机上每秒采集一次、共 241 条的
+    /// `/api/stats` 记录（期间有数段满载），格式与 `stats` 旧表的列顺序一致
+    #[test]
+    fn test_compression_ratio_real_samples() {
+        let samples = include_str!("../tests/fixtures/history_samples.csv");
+        let optional = |s: &str| (!s.is_empty()).then(|| s.parse().unwrap());
+        let records: Vec<HistoryRecord> = samples
+            .lines()
+            .map(|line| {
+                let f: Vec<&str> = line.split(',').collect();
+                HistoryRecord {
+                    timestamp_ms: f[0].parse().unwrap(),
+                    sequence: None,
+                    cpu_usage_ratio: f[1].parse().unwrap(),
+                    memory_total_bytes: f[2].parse().unwrap(),
+                    memory_used_bytes: f[3].parse().unwrap(),
+                    memory_available_bytes: f[4].parse().unwrap(),
+                    memory_cached_bytes: f[5].parse().unwrap(),
+                    memory_free_bytes: f[6].parse().unwrap(),
+                    conntrack_entries: optional(f[7]),
+                    conntrack_entries_limit: optional(f[8]),
+                }
+            })
+            .collect();
+
+        let mut encoder = Encoder::default();
+        let (mut delta_bytes, mut keyframe_bytes) = (0, 0);
+        let mut prev = None;
+        for record in &records {
+            let (keyframe, payload) = encoder.encode(record);
+            // 重建结果与原记录一致
+            let fields = decode(if keyframe { None } else { prev.as_ref() }, &payload).unwrap();
+            assert_eq!(
+                &HistoryRecord::from_fields(record.timestamp_ms, None, &fields),
+                record
+            );
+            prev = Some(fields);
+
+            delta_bytes += payload.len();
+            keyframe_bytes += encode_keyframe(&record.to_fields()).len();
+            encoder.advance(record, keyframe);
+        }
+
+        // 旧表每条记录 9 列按 8 字节计
+        let legacy_bytes = records.len() * 9 * 8;
+        let ratio = delta_bytes as f64 / keyframe_bytes as f64;
+        println!(
+            "{} 条记录：增量编码 {delta_bytes} 字节，全部基线 {keyframe_bytes} 字节，旧表约 {legacy_bytes} 字节",
+            records.len()
+        );
+        assert!(ratio < 0.5, "压缩率 {ratio:.2}");
+    }
+
+    #[tokio::test]
+    async fn test_purge_expired() {
+        let store = HistoryStore::open_in_memory(Duration::from_secs(60)).unwrap();
+        let now = unix_millis();
+        store.insert_record(record_at(now - 120_000)).await.unwrap();
+        store.insert_record(record_at(now - 90_000)).await.unwrap();
+        store
+            .insert_record(HistoryRecord::from_stats(&SystemStats::default(), now))
+            .await
+            .unwrap();
+
+        // 保留的增量改写为基线，之后写入的增量仍能重建
+        assert_eq!(store.purge_expired().await.unwrap(), 2);
+        store.insert_record(record_at(now + 1_000)).await.unwrap();
+        let records = store.query(0, u64::MAX).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].conntrack_entries, None);
+        assert_eq!(records[1], record_at(now + 1_000));
+    }
+}
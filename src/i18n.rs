@@ -0,0 +1,307 @@
+//! 页面语言
+//!
+//! 页面文字有中文与英文两套，模板各一份，服务端生成的区块文字见 [`Messages`]。
+//! 每个请求的语言依次取自查询参数 `lang`、请求头 `Accept-Language`（按 q 值），
+//! 都无法匹配时使用配置的默认语言。数字格式由 [`crate::format::Locale`] 单独决定。
+
+use crate::anomaly::{METRIC_CONNTRACK_USAGE, METRIC_CPU_USAGE, METRIC_MEMORY_USED};
+use crate::cache::RefreshState;
+use crate::stats::{
+    SUBSYSTEM_CONNTRACK, SUBSYSTEM_CUSTOM, SUBSYSTEM_INTERRUPTS, SUBSYSTEM_PER_CORE,
+    SubsystemStatus,
+};
+use std::str::FromStr;
+
+/// 页面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    /// 简体中文
+    #[default]
+    Zh,
+    /// 英文
+    En,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    /// 按语言标签的主语言部分匹配，如 `zh-CN`、`zh_TW.UTF-8`、`en-US`
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let primary = tag.split(['-', '_', '.']).next().unwrap_or_default();
+        match primary.to_ascii_lowercase().as_str() {
+            "zh" => Ok(Self::Zh),
+            "en" => Ok(Self::En),
+            _ => Err(format!("不支持的页面语言：{tag}（支持 zh、en）")),
+        }
+    }
+}
+
+/// 服务端生成的页面区块文字
+#[derive(Debug)]
+pub struct Messages {
+    pub cores_legend: &'static str,
+    pub core_title: &'static str,
+    pub conntrack_legend: &'static str,
+    pub conntrack_used: &'static str,
+    pub conntrack_warning: &'static str,
+    pub custom_legend: &'static str,
+    pub clock_warning: &'static str,
+    pub subsystems_legend: &'static str,
+    pub anomalies_legend: &'static str,
+    pub refresh_state_label: &'static str,
+    /// 标签与值之间的分隔符
+    pub separator: &'static str,
+}
+
+const ZH: Messages = Messages {
+    cores_legend: "处理器 - 各核心使用率",
+    core_title: "核心 ",
+    conntrack_legend: "连接跟踪",
+    conntrack_used: "已用：",
+    conntrack_warning: "警告：连接跟踪表即将用尽，新连接可能被丢弃",
+    custom_legend: "自定义指标",
+    clock_warning: "警告：系统时钟未同步，采集间隔、历史记录时间戳等基于时间的统计可能不准确",
+    subsystems_legend: "采集子系统",
+    anomalies_legend: "异常指标（明显偏离近期水平）",
+    refresh_state_label: "数据状态：",
+    separator: "：",
+};
+
+const EN: Messages = Messages {
+    cores_legend: "CPU - per-core usage",
+    core_title: "Core ",
+    conntrack_legend: "Connection tracking",
+    conntrack_used: "Used: ",
+    conntrack_warning: "Warning: the connection tracking table is almost full, new connections may be dropped",
+    custom_legend: "Custom metrics",
+    clock_warning: "Warning: the system clock is not synchronized, time-based statistics such as collection intervals and history timestamps may be inaccurate",
+    subsystems_legend: "Collection subsystems",
+    anomalies_legend: "Anomalies (well outside the recent range)",
+    refresh_state_label: "Data status: ",
+    separator: ": ",
+};
+
+impl Language {
+    /// 全部支持的语言
+    #[allow(dead_code)] // 库 API，二进制中未使用
+    pub const ALL: [Self; 2] = [Self::Zh, Self::En];
+
+    /// 响应头 `Content-Language` 使用的语言标签
+    #[inline]
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::Zh => "zh-CN",
+            Self::En => "en",
+        }
+    }
+
+    /// 页面模板（编译进二进制文件）
+    #[inline]
+    pub fn template(self) -> &'static str {
+        match self {
+            Self::Zh => include_str!("../templates/index.html"),
+            Self::En => include_str!("../templates/index.en.html"),
+        }
+    }
+
+    /// 区块文字
+    #[inline]
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Self::Zh => &ZH,
+            Self::En => &EN,
+        }
+    }
+
+    /// 缓存刷新状态的描述
+    pub fn refresh_state(self, state: RefreshState) -> &'static str {
+        match self {
+            Self::Zh => state.description(),
+            Self::En => match state {
+                RefreshState::Empty => "no data yet",
+                RefreshState::Fresh => "fresh",
+                RefreshState::Refreshing => "refreshing",
+                RefreshState::Failed => "refresh failed (showing the last successful data)",
+            },
+        }
+    }
+
+    /// 采集子系统状态的描述
+    pub fn subsystem_status(self, status: SubsystemStatus) -> &'static str {
+        match self {
+            Self::Zh => status.description(),
+            Self::En => match status {
+                SubsystemStatus::Ok => "OK",
+                SubsystemStatus::Failed => "enabled, collection failed",
+                SubsystemStatus::Disabled => "not enabled",
+                SubsystemStatus::Throttled => "skipped, over CPU budget",
+            },
+        }
+    }
+
+    /// 采集子系统的名称，未知的子系统原样显示
+    pub fn subsystem_label(self, name: &str) -> &str {
+        match (self, name) {
+            (Self::Zh, SUBSYSTEM_PER_CORE) => "每核心 CPU",
+            (Self::Zh, SUBSYSTEM_CONNTRACK) => "连接跟踪",
+            (Self::Zh, SUBSYSTEM_INTERRUPTS) => "中断分布",
+            (Self::Zh, SUBSYSTEM_CUSTOM) => "自定义采集",
+            (Self::En, SUBSYSTEM_PER_CORE) => "Per-core CPU",
+            (Self::En, SUBSYSTEM_CONNTRACK) => "Connection tracking",
+            (Self::En, SUBSYSTEM_INTERRUPTS) => "Interrupt distribution",
+            (Self::En, SUBSYSTEM_CUSTOM) => "Custom collection",
+            _ => name,
+        }
+    }
+
+    /// 异常标注指标的名称，未知的指标原样显示
+    pub fn anomaly_label(self, name: &str) -> &str {
+        match (self, name) {
+            (Self::Zh, METRIC_CPU_USAGE) => "CPU 使用率",
+            (Self::Zh, METRIC_MEMORY_USED) => "内存使用率",
+            (Self::Zh, METRIC_CONNTRACK_USAGE) => "连接跟踪表使用率",
+            (Self::En, METRIC_CPU_USAGE) => "CPU usage",
+            (Self::En, METRIC_MEMORY_USED) => "Memory usage",
+            (Self::En, METRIC_CONNTRACK_USAGE) => "Connection tracking table usage",
+            _ => name,
+        }
+    }
+
+    /// 异常值与近期水平的对比说明，参数为已格式化的百分比
+    pub fn anomaly_detail(self, value: &str, mean: &str, stddev: &str) -> String {
+        match self {
+            Self::Zh => format!("{value}%（近期均值 {mean}%，标准差 {stddev}）"),
+            Self::En => format!("{value}% (recent mean {mean}%, std dev {stddev})"),
+        }
+    }
+}
+
+/// 解析 `Accept-Language`，按 q 值从高到低返回语言标签，q 值相同时保持原顺序
+///
+/// 格式错误的项与 `q=0`（明确拒绝）的项被忽略。
+pub fn parse_accept_language(header: &str) -> Vec<(&str, f32)> {
+    let mut ranges: Vec<(&str, f32)> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim();
+            if tag.is_empty() {
+                return None;
+            }
+            let mut quality = 1.0;
+            for param in parts {
+                let (key, value) = param.split_once('=')?;
+                if key.trim().eq_ignore_ascii_case("q") {
+                    quality = value.trim().parse::<f32>().ok()?;
+                }
+            }
+            ((0.0..=1.0).contains(&quality) && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges
+}
+
+/// 协商页面语言：查询参数 `lang` 优先，其次 `Accept-Language` 中权重最高的支持语言，否则为 `default`
+pub fn negotiate(
+    query: Option<&str>,
+    accept_language: Option<&str>,
+    default: Language,
+) -> Language {
+    let forced = query.and_then(|query| {
+        query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key == "lang").then(|| value.parse().ok()).flatten()
+        })
+    });
+    if let Some(language) = forced {
+        return language;
+    }
+
+    accept_language
+        .map(parse_accept_language)
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|(tag, _)| match tag {
+            "*" => Some(default),
+            tag => tag.parse().ok(),
+        })
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_str() {
+        assert_eq!("zh-CN".parse(), Ok(Language::Zh));
+        assert_eq!("zh_TW.UTF-8".parse(), Ok(Language::Zh));
+        assert_eq!("EN-us".parse(), Ok(Language::En));
+        assert!("fr".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+            vec![
+                ("fr-CH", 1.0),
+                ("fr", 0.9),
+                ("en", 0.8),
+                ("de", 0.7),
+                ("*", 0.5)
+            ]
+        );
+        // 乱序权重按 q 值排序，相同权重保持原顺序
+        assert_eq!(
+            parse_accept_language("en;q=0.5,zh-CN;q=0.9,ja,ko"),
+            vec![("ja", 1.0), ("ko", 1.0), ("zh-CN", 0.9), ("en", 0.5)]
+        );
+        // q=0 表示拒绝，格式错误的项被忽略
+        assert_eq!(
+            parse_accept_language("zh;q=0, en;q=abc, de;q=2, ja ; q = 0.3"),
+            vec![("ja", 0.3)]
+        );
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let zh = Language::Zh;
+        assert_eq!(negotiate(None, None, zh), Language::Zh);
+        assert_eq!(negotiate(None, Some("en-US,en;q=0.9"), zh), Language::En);
+        // 跳过不支持的语言，取下一个支持的
+        assert_eq!(
+            negotiate(None, Some("fr;q=1, en;q=0.8, zh;q=0.5"), zh),
+            Language::En
+        );
+        assert_eq!(
+            negotiate(None, Some("en;q=0.4, zh-TW;q=0.6"), Language::En),
+            Language::Zh
+        );
+        // 全部不支持或通配时回退到默认语言
+        assert_eq!(negotiate(None, Some("fr, de"), Language::En), Language::En);
+        assert_eq!(negotiate(None, Some("*, en;q=0.5"), zh), Language::Zh);
+        // 查询参数强制覆盖，无效值被忽略
+        assert_eq!(negotiate(Some("lang=en"), Some("zh-CN"), zh), Language::En);
+        assert_eq!(
+            negotiate(Some("x=1&lang=zh"), Some("en"), Language::En),
+            Language::Zh
+        );
+        assert_eq!(negotiate(Some("lang=fr"), Some("en"), zh), Language::En);
+    }
+
+    #[test]
+    fn test_messages_cover_all_languages() {
+        for language in Language::ALL {
+            assert!(language.template().contains("{cpu_cores_section}"));
+            assert!(language.template().contains("{subsystems_section}"));
+            assert!(language.template().contains("{anomalies_section}"));
+            assert_ne!(language.anomaly_label(METRIC_CPU_USAGE), METRIC_CPU_USAGE);
+            assert_ne!(language.subsystem_label(SUBSYSTEM_CUSTOM), SUBSYSTEM_CUSTOM);
+            assert!(!language.messages().clock_warning.is_empty());
+        }
+        assert_eq!(Language::En.subsystem_label("unknown"), "unknown");
+    }
+}
@@ -0,0 +1,244 @@
+//! 按 CPU 的中断分布（/proc/interrupts）
+//!
+//! `/proc/interrupts` 首行列出在线 CPU（`CPU0 CPU1 ...`，CPU 下线后编号不连续），
+//! 其后每行是一个中断源：`标签: 各 CPU 计数... 中断控制器与设备描述`。
+//! 计数为开机以来的累计值，相邻两次采集相减得到采样间隔内的速率。
+//! 解析与差分的开销随 CPU 数 × 中断源数增长，因此通过 `interrupts` feature 按需启用。
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 中断速率超过其余 CPU 平均值的该倍数时视为热点 CPU
+pub const HOTSPOT_FACTOR: f64 = 2.0;
+
+/// 热点 CPU 的最低中断速率（次/秒），避免空闲系统上的小数值被误判
+pub const HOTSPOT_MIN_PER_SECOND: f64 = 1000.0;
+
+/// 结果中保留的中断源数（按总速率从高到低）
+pub const TOP_SOURCES: usize = 10;
+
+/// 一次读取的累计中断计数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterruptCounts {
+    /// 各计数列对应的 CPU 编号
+    pub cpus: Vec<usize>,
+    /// 各中断源：标签、描述与各 CPU 的累计计数
+    pub sources: Vec<(String, String, Vec<u64>)>,
+}
+
+/// 单个中断源在采样间隔内的速率
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InterruptSourceRate {
+    pub irq: String,                  // 中断标签（如 `24`、`LOC`）
+    pub description: String,          // 中断控制器与设备描述
+    pub per_cpu_per_second: Vec<f64>, // 各 CPU 的速率，与 `cpus` 对应
+    pub total_per_second: f64,        // 所有 CPU 的速率之和
+}
+
+/// 采样间隔内按 CPU 的中断分布
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InterruptStats {
+    pub cpus: Vec<usize>,                      // CPU 编号
+    pub per_cpu_per_second: Vec<f64>,          // 各 CPU 的中断总速率
+    pub top_sources: Vec<InterruptSourceRate>, // 速率最高的中断源
+    pub hotspot_cpus: Vec<usize>,              // 热点 CPU 编号
+}
+
+/// 解析 /proc/interrupts，首行没有 CPU 列时返回 `None`
+///
+/// 计数列少于 CPU 数的行（如 `ERR`、`MIS` 只有一个总数）不是按 CPU 统计的，跳过。
+pub fn parse_interrupts(content: &str) -> Option<InterruptCounts> {
+    let mut lines = content.lines();
+    let cpus: Vec<usize> = lines
+        .next()?
+        .split_whitespace()
+        .filter_map(|column| column.strip_prefix("CPU")?.parse().ok())
+        .collect();
+    if cpus.is_empty() {
+        return None;
+    }
+
+    let mut sources = Vec::new();
+    for line in lines {
+        let Some((label, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let mut tokens = rest.split_whitespace().peekable();
+        let mut counts = Vec::with_capacity(cpus.len());
+        while counts.len() < cpus.len() {
+            match tokens.peek().and_then(|token| token.parse::<u64>().ok()) {
+                Some(count) => {
+                    counts.push(count);
+                    tokens.next();
+                }
+                None => break,
+            }
+        }
+        if counts.len() < cpus.len() {
+            continue;
+        }
+        let description = tokens.collect::<Vec<_>>().join(" ");
+        sources.push((label.trim().to_string(), description, counts));
+    }
+    Some(InterruptCounts { cpus, sources })
+}
+
+/// 由相邻两次计数计算速率，CPU 列变化（热插拔）时返回 `None`
+pub fn compute_rates(
+    prev: &InterruptCounts,
+    current: &InterruptCounts,
+    elapsed: Duration,
+) -> Option<InterruptStats> {
+    if prev.cpus != current.cpus || elapsed.is_zero() {
+        return None;
+    }
+    let seconds = elapsed.as_secs_f64();
+
+    let mut per_cpu_per_second = vec![0.0; current.cpus.len()];
+    let mut sources = Vec::with_capacity(current.sources.len());
+    for (irq, description, counts) in &current.sources {
+        // 新出现的中断源没有上一次计数，下个周期再计入
+        let Some((_, _, prev_counts)) = prev.sources.iter().find(|(label, ..)| label == irq) else {
+            continue;
+        };
+        let rates: Vec<f64> = counts
+            .iter()
+            .zip(prev_counts)
+            .map(|(count, prev)| count.saturating_sub(*prev) as f64 / seconds)
+            .collect();
+        for (total, rate) in per_cpu_per_second.iter_mut().zip(&rates) {
+            *total += rate;
+        }
+        sources.push(InterruptSourceRate {
+            irq: irq.clone(),
+            description: description.clone(),
+            total_per_second: rates.iter().sum(),
+            per_cpu_per_second: rates,
+        });
+    }
+
+    sources.sort_by(|a, b| b.total_per_second.total_cmp(&a.total_per_second));
+    sources.truncate(TOP_SOURCES);
+
+    let hotspot_cpus = find_hotspots(&current.cpus, &per_cpu_per_second);
+    Some(InterruptStats {
+        cpus: current.cpus.clone(),
+        per_cpu_per_second,
+        top_sources: sources,
+        hotspot_cpus,
+    })
+}
+
+/// 速率明显高于其余 CPU 平均值的 CPU；单 CPU 时没有可比较的对象
+fn find_hotspots(cpus: &[usize], per_cpu_per_second: &[f64]) -> Vec<usize> {
+    if cpus.len() < 2 {
+        return Vec::new();
+    }
+    let total: f64 = per_cpu_per_second.iter().sum();
+    let others = (cpus.len() - 1) as f64;
+    cpus.iter()
+        .zip(per_cpu_per_second)
+        .filter(|&(_, &rate)| {
+            rate >= HOTSPOT_MIN_PER_SECOND && rate > (total - rate) / others * HOTSPOT_FACTOR
+        })
+        .map(|(&cpu, _)| cpu)
+        .collect()
+}
+
+/// 保存上一次计数并计算速率
+#[derive(Debug, Default)]
+pub struct InterruptSampler {
+    prev: Mutex<Option<(Instant, InterruptCounts)>>,
+}
+
+impl InterruptSampler {
+    /// 处理一次 /proc/interrupts 内容，第一次采样或无法比较时返回 `None`
+    pub fn sample(&self, content: &str, now: Instant) -> Option<InterruptStats> {
+        let current = parse_interrupts(content)?;
+        let prev = self
+            .prev
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace((now, current.clone()));
+        let (prev_at, prev) = prev?;
+        compute_rates(&prev, &current, now.saturating_duration_since(prev_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "           CPU0       CPU1       CPU3
+  0:         44          0          0   IO-APIC   2-edge      timer
+ 24:       1000         10         10   PCI-MSI 524288-edge      eth0
+NMI:          0          0          0   Non-maskable interrupts
+LOC:    5000000    5000000    5000000   Local timer interrupts
+ERR:          0
+MIS:          0
+";
+
+    #[test]
+    fn test_parse_interrupts() {
+        let counts = parse_interrupts(SAMPLE).unwrap();
+        assert_eq!(counts.cpus, vec![0, 1, 3]);
+        assert_eq!(counts.sources.len(), 4);
+        assert_eq!(
+            counts.sources[1],
+            (
+                "24".to_string(),
+                "PCI-MSI 524288-edge eth0".to_string(),
+                vec![1000, 10, 10]
+            )
+        );
+        assert_eq!(counts.sources[3].1, "Local timer interrupts");
+        assert!(parse_interrupts("").is_none());
+        assert!(parse_interrupts("garbage\n 0: 1 2\n").is_none());
+    }
+
+    #[test]
+    fn test_compute_rates_and_hotspots() {
+        let prev = parse_interrupts(SAMPLE).unwrap();
+        let current = parse_interrupts(
+            &SAMPLE
+                .replace(
+                    "       1000         10         10",
+                    "      21000         20         10",
+                )
+                .replace(
+                    "5000000    5000000    5000000",
+                    "5000100    5000100    5000100",
+                ),
+        )
+        .unwrap();
+
+        let stats = compute_rates(&prev, &current, Duration::from_secs(2)).unwrap();
+        assert_eq!(stats.cpus, vec![0, 1, 3]);
+        assert_eq!(stats.per_cpu_per_second, vec![10_050.0, 55.0, 50.0]);
+        assert_eq!(stats.top_sources[0].irq, "24");
+        assert_eq!(stats.top_sources[0].total_per_second, 10_005.0);
+        assert_eq!(
+            stats.top_sources[0].per_cpu_per_second,
+            vec![10_000.0, 5.0, 0.0]
+        );
+        assert_eq!(stats.hotspot_cpus, vec![0]);
+
+        // CPU 列变化时重新开始
+        let mut hotplug = current.clone();
+        hotplug.cpus = vec![0, 1, 2];
+        assert!(compute_rates(&prev, &hotplug, Duration::from_secs(2)).is_none());
+    }
+
+    #[test]
+    fn test_interrupt_sampler() {
+        let sampler = InterruptSampler::default();
+        let start = Instant::now();
+        assert!(sampler.sample(SAMPLE, start).is_none());
+        let stats = sampler
+            .sample(SAMPLE, start + Duration::from_secs(1))
+            .unwrap();
+        assert!(stats.per_cpu_per_second.iter().all(|&rate| rate == 0.0));
+        assert!(stats.hotspot_cpus.is_empty());
+    }
+}
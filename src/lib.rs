@@ -2,9 +2,37 @@
 //!
 //! 这个库提供了一个极简的资源占用显示系统，专为嵌入式设备监控场景设计。
 
+#[cfg(feature = "jemalloc")]
+pub mod allocator;
+pub mod anomaly;
+pub mod breaker;
+pub mod budget;
 pub mod cache;
+pub mod config_file;
+#[cfg(feature = "custom")]
+pub mod custom;
+pub mod filter;
+pub mod format;
+pub mod healthcheck;
+#[cfg(feature = "sqlite")]
+pub mod history;
+pub mod i18n;
+#[cfg(feature = "interrupts")]
+pub mod interrupts;
+pub mod listener;
+pub mod metrics;
+pub mod proc_file;
+#[cfg(feature = "remote-write")]
+pub mod remote_write;
+pub mod sampler;
 pub mod server;
 pub mod stats;
+pub mod stdio;
+
+/// 单元测试与二进制使用同一个全局分配器，`allocator` 的统计在测试中才反映实际分配
+#[cfg(all(test, feature = "jemalloc"))]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 // 重新导出主要的公共类型
 pub use cache::{SystemStatsCache, create_cache};
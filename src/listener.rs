@@ -0,0 +1,253 @@
+//! 多地址监听
+//!
+//! 所有监听套接字合并为一个 hyper `Accept`，由同一个服务器、同一个 accept 循环轮流接受连接：
+//! 监听器数量增加时只多占监听套接字本身，不额外创建服务器实例或任务，
+//! 请求处理状态（端点指标、scrape 间隔等）也只有一份。
+//!
+//! 监听地址也可以写成 `all-v4`/`all-v6`/`all`，启动时枚举本机网卡地址展开为具体地址，
+//! 网卡增删需重启才生效。
+
+use crate::server::BindError;
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// 已接受连接的 TCP keep-alive 探测间隔
+pub const TCP_KEEPALIVE: Duration = Duration::from_secs(10);
+
+/// 合并多个监听套接字的连接来源
+#[derive(Debug)]
+pub struct MultiIncoming {
+    incomings: Vec<AddrIncoming>,
+    /// 下一次优先检查的监听器，轮转以免排在前面的监听器独占 accept
+    next: usize,
+}
+
+impl MultiIncoming {
+    /// 绑定全部地址，任一地址失败即返回该地址的绑定错误
+    ///
+    /// 需要在 tokio 运行时内调用。
+    pub fn bind(addrs: &[SocketAddr]) -> anyhow::Result<Self> {
+        let mut incomings = Vec::with_capacity(addrs.len());
+        for &addr in addrs {
+            // 先自行绑定监听套接字，以便对常见绑定错误给出针对性提示
+            let listener = std::net::TcpListener::bind(addr)
+                .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+                .and_then(tokio::net::TcpListener::from_std)
+                .map_err(|source| BindError { addr, source })?;
+            let mut incoming = AddrIncoming::from_listener(listener)?;
+            incoming.set_nodelay(true);
+            incoming.set_keepalive(Some(TCP_KEEPALIVE));
+            incomings.push(incoming);
+        }
+        Ok(Self { incomings, next: 0 })
+    }
+
+    /// 实际监听的地址（绑定端口 0 时为系统分配的端口）
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.incomings
+            .iter()
+            .map(AddrIncoming::local_addr)
+            .collect()
+    }
+}
+
+/// 网卡地址通配关键字对应的地址族
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceFamily {
+    /// `all-v4`：所有非回环的 IPv4 地址
+    V4,
+    /// `all-v6`：所有非回环的 IPv6 地址
+    V6,
+    /// `all`：以上两者
+    Any,
+}
+
+impl InterfaceFamily {
+    /// 识别通配关键字，不是关键字时返回 `None`
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "all-v4" => Some(Self::V4),
+            "all-v6" => Some(Self::V6),
+            "all" => Some(Self::Any),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn contains(self, ip: IpAddr) -> bool {
+        matches!(
+            (self, ip),
+            (Self::Any, _) | (Self::V4, IpAddr::V4(_)) | (Self::V6, IpAddr::V6(_))
+        )
+    }
+}
+
+/// 枚举本机已启用网卡上的地址（端口为 0），IPv6 地址带接口的 scope id
+pub fn interface_addresses() -> std::io::Result<Vec<SocketAddr>> {
+    let mut ifaddrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs 成功时写入链表头，之后由 freeifaddrs 释放
+    if unsafe { libc::getifaddrs(&mut ifaddrs) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut addrs = Vec::new();
+    let mut cursor = ifaddrs;
+    while !cursor.is_null() {
+        // SAFETY: cursor 指向 getifaddrs 返回的链表节点，释放前一直有效
+        let entry = unsafe { &*cursor };
+        cursor = entry.ifa_next;
+        // 未启用的网卡上的地址无法接受连接
+        if entry.ifa_addr.is_null() || entry.ifa_flags & libc::IFF_UP as libc::c_uint == 0 {
+            continue;
+        }
+        // SAFETY: ifa_addr 非空，按 sa_family 转换为对应的地址结构
+        match i32::from(unsafe { (*entry.ifa_addr).sa_family }) {
+            libc::AF_INET => {
+                let sin = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in) };
+                let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+                addrs.push(SocketAddr::V4(SocketAddrV4::new(ip, 0)));
+            }
+            libc::AF_INET6 => {
+                let sin6 = unsafe { &*(entry.ifa_addr as *const libc::sockaddr_in6) };
+                let ip = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+                addrs.push(SocketAddr::V6(SocketAddrV6::new(
+                    ip,
+                    0,
+                    0,
+                    sin6.sin6_scope_id,
+                )));
+            }
+            _ => {}
+        }
+    }
+    // SAFETY: ifaddrs 来自成功的 getifaddrs 调用，且之后不再访问
+    unsafe { libc::freeifaddrs(ifaddrs) };
+    Ok(addrs)
+}
+
+/// 从网卡地址中选出属于 `family` 的非回环地址并设置端口，保持枚举顺序并去重
+pub fn select_interface_addresses(
+    candidates: &[SocketAddr],
+    family: InterfaceFamily,
+    port: u16,
+) -> Vec<SocketAddr> {
+    let mut selected: Vec<SocketAddr> = Vec::new();
+    for candidate in candidates {
+        if candidate.ip().is_loopback() || !family.contains(candidate.ip()) {
+            continue;
+        }
+        let mut addr = *candidate;
+        addr.set_port(port);
+        if !selected.contains(&addr) {
+            selected.push(addr);
+        }
+    }
+    selected
+}
+
+impl Accept for MultiIncoming {
+    type Conn = AddrStream;
+    type Error = std::io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let this = self.get_mut();
+        let count = this.incomings.len();
+        if count == 0 {
+            return Poll::Ready(None);
+        }
+        // 每个监听器都会被轮询到，未就绪的各自登记 waker，任一监听器有新连接都会唤醒本任务
+        for offset in 0..count {
+            let i = (this.next + offset) % count;
+            if let Poll::Ready(conn) = Pin::new(&mut this.incomings[i]).poll_accept(cx) {
+                this.next = (i + 1) % count;
+                return Poll::Ready(conn);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::poll_fn;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn test_bind_multiple_addresses() {
+        let addrs: Vec<SocketAddr> = vec!["127.0.0.1:0".parse().unwrap(); 3];
+        let mut incoming = MultiIncoming::bind(&addrs).unwrap();
+        let local_addrs = incoming.local_addrs();
+        assert_eq!(local_addrs.len(), 3);
+
+        // 每个监听地址上的连接都能被同一个 accept 循环接受
+        for addr in &local_addrs {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"x").await.unwrap();
+            let conn = poll_fn(|cx| Pin::new(&mut incoming).poll_accept(cx))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(conn.local_addr(), *addr);
+        }
+    }
+
+    #[test]
+    fn test_select_interface_addresses() {
+        let candidates: Vec<SocketAddr> = [
+            "127.0.0.1:0",
+            "192.168.1.10:0",
+            "[::1]:0",
+            "[fe80::1%2]:0",
+            "10.0.0.2:0",
+            "192.168.1.10:0",
+            "[2001:db8::1]:0",
+        ]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+        let select = |family| -> Vec<String> {
+            select_interface_addresses(&candidates, family, 8080)
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        };
+        assert_eq!(
+            select(InterfaceFamily::V4),
+            ["192.168.1.10:8080", "10.0.0.2:8080"]
+        );
+        assert_eq!(
+            select(InterfaceFamily::V6),
+            ["[fe80::1%2]:8080", "[2001:db8::1]:8080"]
+        );
+        assert_eq!(select(InterfaceFamily::Any).len(), 4);
+    }
+
+    #[test]
+    fn test_interface_addresses() {
+        let addrs = interface_addresses().unwrap();
+        // 回环网卡总是存在且已启用
+        assert!(addrs.iter().any(|addr| addr.ip().is_loopback()));
+        assert!(addrs.iter().all(|addr| addr.port() == 0));
+    }
+
+    #[tokio::test]
+    async fn test_bind_reports_failed_address() {
+        let occupied = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addrs = [
+            "127.0.0.1:0".parse().unwrap(),
+            occupied.local_addr().unwrap(),
+        ];
+        let error = MultiIncoming::bind(&addrs).unwrap_err();
+        let bind_error = error.downcast_ref::<BindError>().unwrap();
+        assert_eq!(bind_error.addr, addrs[1]);
+        assert_eq!(bind_error.source.kind(), std::io::ErrorKind::AddrInUse);
+    }
+}
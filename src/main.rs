@@ -1,20 +1,54 @@
+#[cfg(feature = "jemalloc")]
+mod allocator;
+mod anomaly;
+mod breaker;
+mod budget;
 mod cache;
+mod config_file;
+#[cfg(feature = "custom")]
+mod custom;
+mod filter;
+mod format;
+mod healthcheck;
+#[cfg(feature = "sqlite")]
+mod history;
+mod i18n;
+#[cfg(feature = "interrupts")]
+mod interrupts;
+mod listener;
+mod metrics;
+mod proc_file;
+#[cfg(feature = "remote-write")]
+mod remote_write;
+mod sampler;
 mod server;
 mod stats;
+mod stdio;
 
+use anomaly::{AnomalyDetector, MIN_SAMPLES};
 use anyhow::Result;
-use cache::create_cache;
-use clap::Parser;
-use log::info;
-use server::{Config, StatusServer};
+use cache::SystemStatsCache;
+use clap::{CommandFactory, Parser};
+use filter::OutlierFilter;
+use log::{error, info};
+use server::{BindError, Config, StatusServer};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 启用 `jemalloc` feature 时以 jemalloc 作为全局分配器，`/debug/allocator` 读取其统计
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 /// 资源占用显示系统
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_override_self = true)]
 struct Args {
-    /// 服务器绑定地址 (默认: ::，支持 IPv4 和 IPv6)
+    /// 服务器绑定地址 (默认: ::，支持 IPv4 和 IPv6)，可重复指定以同时监听多个地址；
+    /// 可带端口（如 127.0.0.1:9090、[::1]:9090），未带端口时使用 --port；
+    /// all-v4、all-v6、all 在启动时展开为本机网卡上的非回环地址
     #[arg(short, long, default_value = "::")]
-    address: String,
+    address: Vec<String>,
 
     /// 服务器端口 (默认: 8080)
     #[arg(short, long, default_value_t = 8080)]
@@ -27,48 +61,404 @@ struct Args {
     /// 日志级别 (trace, debug, info, warn, error)
     #[arg(short, long, default_value = "info")]
     log_level: String,
+
+    /// 异常值剔除阈值（百分点）：CPU 使用率偏离最近样本中位数超过该值时沿用上一个值（默认不启用）
+    #[arg(long)]
+    outlier_threshold: Option<f32>,
+
+    /// 异常值剔除参考的最近样本数
+    #[arg(long, default_value_t = 5)]
+    outlier_window: usize,
+
+    /// 异常标注参考的最近采集次数（至少 5）：指标偏离窗口均值超过 --anomaly-sigma 倍标准差时标为异常（默认不启用）
+    #[arg(long, value_name = "SAMPLES")]
+    anomaly_window: Option<usize>,
+
+    /// 异常标注的标准差倍数（须大于 0）
+    #[arg(long, default_value_t = 3.0)]
+    anomaly_sigma: f64,
+
+    /// 采集连续失败多少次后打开断路器，冷却期内不再尝试采集（0 表示不启用）
+    #[arg(long, default_value_t = 5)]
+    breaker_threshold: u32,
+
+    /// 断路器打开后的冷却秒数，之后进行一次试探采集
+    #[arg(long, default_value_t = 30)]
+    breaker_cooldown: u64,
+
+    /// 启用后台刷新：每个 TTL 周期主动采集一次，而非仅在请求时按需采集
+    #[arg(long)]
+    background_refresh: bool,
+
+    /// 后台刷新的采样时刻对齐到墙上时钟的整 TTL 边界（如每分钟第 0、10、20 秒），便于多台设备横向对比
+    #[arg(long)]
+    align_refresh: bool,
+
+    /// proc 文件系统根目录（容器中可指向挂载进来的宿主机 /proc）
+    #[arg(long, default_value = stats::DEFAULT_PROC_ROOT, value_name = "PATH")]
+    proc_root: std::path::PathBuf,
+
+    /// 复用 /proc/stat 与 /proc/meminfo 的文件句柄，每次用 pread 从头重读，减少高频采集时的系统调用（读取在异步工作线程上进行，--proc-root 位于较慢的文件系统时不要启用）
+    #[arg(long)]
+    reuse_proc_fds: bool,
+
+    /// 本进程的 CPU 时间预算（占单个核心的百分比，如 2）：滚动窗口内超出时跳过 /proc/interrupts 的读取与每核心 CPU 的解析，回落后恢复（默认不限制）
+    #[arg(long, value_name = "PERCENT")]
+    cpu_budget_percent: Option<f64>,
+
+    /// CPU 时间预算的滚动窗口秒数
+    #[arg(long, default_value_t = 60)]
+    cpu_budget_window: u64,
+
+    /// 独立 CPU 采样间隔毫秒数：以该间隔采样并平滑 CPU 使用率，与整机采集周期解耦（默认不启用）
+    #[arg(long, value_name = "MS")]
+    cpu_sample_interval_ms: Option<u64>,
+
+    /// 独立 CPU 采样的 EWMA 平滑系数（新样本权重，0-1）
+    #[arg(long, default_value_t = 0.3)]
+    cpu_ewma_alpha: f32,
+
+    /// 只采集并显示这些核心的每核心使用率（逗号分隔的核心编号，如 0,2,3），总体使用率仍统计全部核心（默认全部）
+    #[arg(long, value_delimiter = ',', value_name = "CORES")]
+    cpu_cores: Option<Vec<usize>>,
+
+    /// 缓存中的每核心使用率以 16 位定点数保存（精度 0.01 个百分点），占用减半，适合核心数多的内存敏感设备
+    #[arg(long)]
+    compact_cores: bool,
+
+    /// 页面数字格式的区域（如 zh-CN、en-US、de-DE、fr-FR），决定千位分隔符与小数点
+    #[arg(long, default_value = "zh-CN")]
+    locale: format::Locale,
+
+    /// 页面默认语言（zh、en），请求可通过 `Accept-Language` 或 `?lang=` 另行指定
+    #[arg(long, default_value = "zh")]
+    language: i18n::Language,
+
+    /// 允许以 iframe 嵌入页面的来源（可重复指定），CSP frame-ancestors 写法，如 https://dashboard.example.com；
+    /// 'none' 禁止任何嵌入（默认只允许同源嵌入）
+    #[arg(long = "frame-ancestor", value_name = "SOURCE")]
+    frame_ancestors: Vec<String>,
+
+    /// 外部采集命令（可重复指定），每个命令向 stdout 输出 `key=value` 行
+    #[cfg(feature = "custom")]
+    #[arg(long = "custom-command", value_name = "COMMAND")]
+    custom_commands: Vec<String>,
+
+    /// 单个外部采集命令的超时毫秒数
+    #[cfg(feature = "custom")]
+    #[arg(long, default_value_t = 2000)]
+    custom_timeout_ms: u64,
+
+    /// 单个外部采集命令的最大输出字节数
+    #[cfg(feature = "custom")]
+    #[arg(long, default_value_t = 4096)]
+    custom_max_output_bytes: usize,
+
+    /// 采集历史 SQLite 数据库路径，指定后每次采集的关键指标写入该数据库
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "PATH")]
+    history_db: Option<std::path::PathBuf>,
+
+    /// 采集历史保留小时数，过期数据定期清理
+    #[cfg(feature = "sqlite")]
+    #[arg(long, default_value_t = 168)]
+    history_retention_hours: u64,
+
+    /// Prometheus remote_write 接收端地址（如 http://prometheus:9090/api/v1/write），指定后周期推送指标
+    #[cfg(feature = "remote-write")]
+    #[arg(long, value_name = "URL")]
+    remote_write_url: Option<String>,
+
+    /// remote_write 推送间隔秒数
+    #[cfg(feature = "remote-write")]
+    #[arg(long, default_value_t = 30)]
+    remote_write_interval: u64,
+
+    /// remote_write 请求附加的请求头（可重复指定），格式为 `名称: 值`，如 `Authorization: Bearer TOKEN`
+    #[cfg(feature = "remote-write")]
+    #[arg(long = "remote-write-header", value_name = "HEADER")]
+    remote_write_headers: Vec<String>,
+
+    /// 探针模式：请求本机运行中实例的 /health，健康时退出码为 0，否则为 1，不启动服务
+    #[arg(long)]
+    healthcheck: bool,
+
+    /// 从 TOML 配置文件读取参数，键为长参数名（`-` 换成 `_`），命令行参数优先
+    #[arg(long, value_name = "FILE")]
+    config: Option<std::path::PathBuf>,
+
+    /// 打印带注释的完整配置文件模板（所有配置项、默认值与说明）后退出
+    #[arg(long)]
+    print_default_config: bool,
+
+    /// 以 stdio 模式运行：从 stdin 读取请求行，向 stdout 输出 JSON Lines 响应，不启动 HTTP 服务
+    #[arg(long)]
+    stdio: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let args = Args::parse_from(config_file::expand_args(
+        std::env::args_os().collect(),
+        &Args::command(),
+    )?);
+
+    if args.print_default_config {
+        print!("{}", config_file::render_template(&Args::command()));
+        return Ok(());
+    }
 
     // 初始化日志系统
     init_logger(&args.log_level);
 
+    if args.healthcheck {
+        run_healthcheck(&args).await;
+    }
+
     info!("资源占用显示系统启动中...");
 
     // 从命令行参数创建配置
     let config = Config {
-        bind_address: args.address.clone(),
+        bind_addresses: args.address.clone(),
         port: args.port,
         cache_ttl_seconds: args.ttl,
+        outlier_threshold: args.outlier_threshold,
+        outlier_window: args.outlier_window,
+        anomaly_window: args.anomaly_window,
+        anomaly_sigma: args.anomaly_sigma,
+        breaker_threshold: args.breaker_threshold,
+        breaker_cooldown_seconds: args.breaker_cooldown,
+        background_refresh: args.background_refresh,
+        align_refresh: args.align_refresh,
+        proc_root: args.proc_root.clone(),
+        reuse_proc_fds: args.reuse_proc_fds,
+        cpu_budget_percent: args.cpu_budget_percent,
+        cpu_budget_window_seconds: args.cpu_budget_window,
+        cpu_sample_interval_ms: args.cpu_sample_interval_ms,
+        cpu_ewma_alpha: args.cpu_ewma_alpha,
+        cpu_cores: args.cpu_cores.clone(),
+        compact_cores: args.compact_cores,
+        locale: args.locale,
+        language: args.language,
+        frame_ancestors: args.frame_ancestors.clone(),
+        #[cfg(feature = "custom")]
+        custom_commands: args.custom_commands.clone(),
+        #[cfg(feature = "custom")]
+        custom_timeout_ms: args.custom_timeout_ms,
+        #[cfg(feature = "custom")]
+        custom_max_output_bytes: args.custom_max_output_bytes,
+        #[cfg(feature = "sqlite")]
+        history_db: args.history_db.clone(),
+        #[cfg(feature = "sqlite")]
+        history_retention_hours: args.history_retention_hours,
+        #[cfg(feature = "remote-write")]
+        remote_write_url: args.remote_write_url.clone(),
+        #[cfg(feature = "remote-write")]
+        remote_write_interval_seconds: args.remote_write_interval,
+        #[cfg(feature = "remote-write")]
+        remote_write_headers: args.remote_write_headers.clone(),
     };
 
     info!(
         "配置信息 - 地址: {}, 端口: {}, 缓存 TTL: {} 秒",
-        config.bind_address, config.port, config.cache_ttl_seconds
+        config.bind_addresses.join(", "),
+        config.port,
+        config.cache_ttl_seconds
     );
 
     // 创建缓存
-    let cache = create_cache(config.cache_ttl_seconds);
+    let cache = Arc::new(build_cache(&config)?);
     info!("缓存系统初始化完成");
 
+    if config.background_refresh {
+        cache.spawn_background_refresh();
+        info!("已启用后台刷新，每 {} 秒采集一次", config.cache_ttl_seconds);
+        if config.align_refresh {
+            info!("后台刷新的采样时刻对齐到整 TTL 边界");
+        }
+    }
+
+    #[cfg(feature = "remote-write")]
+    if let Some(url) = &config.remote_write_url {
+        let remote_write = remote_write::RemoteWriteConfig::new(
+            url,
+            Duration::from_secs(config.remote_write_interval_seconds.max(1)),
+            &config.remote_write_headers,
+        )?;
+        info!(
+            "已启用 remote_write 推送 - 地址: {url}, 每 {} 秒一次",
+            remote_write.interval.as_secs()
+        );
+        remote_write::spawn(cache.clone(), remote_write);
+    }
+
+    if args.stdio {
+        return stdio::run(cache).await;
+    }
+
     // 创建服务器
-    let server = StatusServer::new_with_ttl(cache, config.cache_ttl_seconds);
+    let server = StatusServer::new_with_ttl(cache, config.cache_ttl_seconds)
+        .with_locale(config.locale)
+        .with_language(config.language)
+        .with_frame_policy(server::FramePolicy::new(&config.frame_ancestors)?);
     info!("服务器实例创建完成");
 
     // 启动服务器
-    let addr = config.address();
-    info!("服务器将在 {addr} 启动");
+    let addrs = config.addresses()?;
+    info!("服务器将在 {addrs:?} 启动");
 
-    server.run(addr).await?;
+    if let Err(e) = server.run(&addrs).await {
+        // 绑定失败给出针对性提示，而非打印完整错误链
+        if let Some(bind_error) = e.downcast_ref::<BindError>() {
+            error!("{bind_error}");
+            std::process::exit(1);
+        }
+        return Err(e);
+    }
 
     info!("服务器正常关闭");
 
     Ok(())
 }
 
+/// 根据配置创建缓存并启用可选的采集处理，配置取值无效时返回错误
+fn build_cache(config: &Config) -> Result<SystemStatsCache> {
+    let mut collector =
+        stats::Collector::new(&config.proc_root).with_reused_fds(config.reuse_proc_fds);
+    if let Some(percent) = config.cpu_budget_percent {
+        if !(percent.is_finite() && percent > 0.0) {
+            anyhow::bail!("--cpu-budget-percent 应为大于 0 的有限数值，当前为 {percent}");
+        }
+        info!(
+            "已启用 CPU 时间预算 - {percent}% / {} 秒窗口",
+            config.cpu_budget_window_seconds
+        );
+        collector = collector.with_cpu_budget(budget::CpuBudget::new(
+            percent,
+            Duration::from_secs(config.cpu_budget_window_seconds.max(1)),
+        ));
+    }
+
+    let mut cache = SystemStatsCache::new(Duration::from_secs(config.cache_ttl_seconds))
+        .with_collector(collector)
+        .with_aligned_refresh(config.align_refresh)
+        .with_compact_per_core(config.compact_cores);
+
+    if let Some(threshold) = config.outlier_threshold {
+        info!(
+            "已启用异常值剔除 - 阈值: {threshold} 个百分点, 窗口: {} 个样本",
+            config.outlier_window
+        );
+        cache = cache.with_outlier_filter(OutlierFilter::new(config.outlier_window, threshold));
+    }
+
+    if let Some(window) = config.anomaly_window {
+        if window < MIN_SAMPLES {
+            anyhow::bail!("--anomaly-window 至少为 {MIN_SAMPLES}，当前为 {window}");
+        }
+        let sigma = config.anomaly_sigma;
+        if !(sigma.is_finite() && sigma > 0.0) {
+            anyhow::bail!("--anomaly-sigma 应为大于 0 的有限数值，当前为 {sigma}");
+        }
+        info!(
+            "已启用异常标注 - 窗口: {window} 次采集, 阈值: {} 倍标准差",
+            config.anomaly_sigma
+        );
+        cache = cache.with_anomaly_detector(AnomalyDetector::new(window, config.anomaly_sigma));
+    }
+
+    if config.breaker_threshold > 0 {
+        info!(
+            "已启用采集断路器 - 连续失败 {} 次后冷却 {} 秒",
+            config.breaker_threshold, config.breaker_cooldown_seconds
+        );
+        cache = cache.with_circuit_breaker(breaker::CircuitBreaker::new(
+            config.breaker_threshold,
+            Duration::from_secs(config.breaker_cooldown_seconds),
+        ));
+    }
+
+    if let Some(cores) = &config.cpu_cores {
+        info!("每核心数据只保留核心 {cores:?}");
+        cache = cache.with_cpu_cores(cores.clone());
+    }
+
+    if let Some(interval_ms) = config.cpu_sample_interval_ms {
+        info!(
+            "已启用独立 CPU 采样 - 间隔: {interval_ms} 毫秒, EWMA 系数: {}",
+            config.cpu_ewma_alpha
+        );
+        let sampler = Arc::new(
+            sampler::CpuSampler::new(
+                Duration::from_millis(interval_ms.max(1)),
+                config.cpu_ewma_alpha,
+            )
+            .with_proc_root(&config.proc_root)
+            .with_reused_fds(config.reuse_proc_fds),
+        );
+        sampler.spawn();
+        cache = cache.with_cpu_sampler(sampler);
+    }
+
+    #[cfg(feature = "custom")]
+    if !config.custom_commands.is_empty() {
+        info!("已启用 {} 个外部采集命令", config.custom_commands.len());
+        cache = cache.with_custom_collector(custom::CustomCollector::new(
+            config.custom_commands.clone(),
+            Duration::from_millis(config.custom_timeout_ms),
+            config.custom_max_output_bytes,
+        ));
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &config.history_db {
+        let retention = Duration::from_secs(config.history_retention_hours * 3600);
+        match history::HistoryStore::open(path, retention) {
+            Ok(store) => {
+                info!(
+                    "已启用采集历史 - 数据库: {}, 保留 {} 小时",
+                    path.display(),
+                    config.history_retention_hours
+                );
+                store.spawn_cleanup();
+                cache = cache.with_history(store);
+            }
+            // 历史记录是辅助功能，打不开数据库时继续提供实时数据
+            Err(e) => error!("无法打开采集历史数据库 {}: {e}，不记录历史", path.display()),
+        }
+    }
+
+    Ok(cache)
+}
+
+/// 探针模式：探测本机运行中的实例并以退出码报告结果
+async fn run_healthcheck(args: &Args) -> ! {
+    let config = Config {
+        bind_addresses: args.address.clone(),
+        port: args.port,
+        ..Config::default()
+    };
+    // 多个监听地址共用同一服务器，探测第一个即可
+    let result = match config.addresses() {
+        Ok(addrs) => {
+            let addr = healthcheck::probe_address(addrs[0]);
+            healthcheck::probe(addr, healthcheck::PROBE_TIMEOUT).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(body) => {
+            println!("{body}");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("健康检查失败: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
 /// 初始化日志系统
 fn init_logger(level: &str) {
     use std::env;
@@ -0,0 +1,783 @@
+//! Prometheus 文本格式指标导出
+
+use crate::breaker::BreakerSnapshot;
+use crate::cache::TimingSnapshot;
+use crate::stats::SystemStats;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 所有指标名的公共前缀
+pub const METRIC_PREFIX: &str = "swb_";
+
+/// 自定义采集指标的前缀
+pub const CUSTOM_METRIC_PREFIX: &str = "swb_custom_";
+
+/// 写入一条无标签的 gauge 指标
+#[inline]
+fn write_gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {METRIC_PREFIX}{name} {help}");
+    let _ = writeln!(out, "# TYPE {METRIC_PREFIX}{name} gauge");
+    let _ = writeln!(out, "{METRIC_PREFIX}{name} {value}");
+}
+
+/// 写入一条无标签的 counter 指标
+#[inline]
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {METRIC_PREFIX}{name} {help}");
+    let _ = writeln!(out, "# TYPE {METRIC_PREFIX}{name} counter");
+    let _ = writeln!(out, "{METRIC_PREFIX}{name} {value}");
+}
+
+/// 将系统统计数据渲染为 Prometheus 文本格式
+pub fn render_metrics(stats: &SystemStats) -> String {
+    let mut out = String::with_capacity(2048 + stats.cpu_stats.per_core.len() * 64);
+
+    write_gauge(
+        &mut out,
+        "cpu_usage_ratio",
+        "Overall CPU usage (0.0-1.0)",
+        stats.cpu_usage,
+    );
+
+    if !stats.cpu_stats.per_core.is_empty() {
+        let _ = writeln!(
+            out,
+            "# HELP {METRIC_PREFIX}cpu_core_usage_percent Per-core CPU usage percent"
+        );
+        let _ = writeln!(out, "# TYPE {METRIC_PREFIX}cpu_core_usage_percent gauge");
+        for (i, core) in stats.cpu_stats.per_core.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "{METRIC_PREFIX}cpu_core_usage_percent{{core=\"{}\"}} {}",
+                stats.cpu_stats.core_id(i),
+                core.total_percent
+            );
+        }
+    }
+
+    write_gauge(
+        &mut out,
+        "memory_total_bytes",
+        "Total memory in bytes",
+        stats.memory_total,
+    );
+    write_gauge(
+        &mut out,
+        "memory_used_bytes",
+        "Used memory in bytes",
+        stats.memory_used,
+    );
+    write_gauge(
+        &mut out,
+        "memory_available_bytes",
+        "Available memory in bytes",
+        stats.memory_available,
+    );
+    write_gauge(
+        &mut out,
+        "memory_cached_bytes",
+        "Cached memory in bytes",
+        stats.memory_cached,
+    );
+    write_gauge(
+        &mut out,
+        "memory_free_bytes",
+        "Free memory in bytes",
+        stats.memory_free,
+    );
+
+    if let Some(conntrack) = &stats.conntrack {
+        write_gauge(
+            &mut out,
+            "conntrack_entries",
+            "Number of tracked connections",
+            conntrack.count,
+        );
+        write_gauge(
+            &mut out,
+            "conntrack_entries_limit",
+            "Maximum number of tracked connections",
+            conntrack.max,
+        );
+    }
+
+    #[cfg(feature = "interrupts")]
+    if let Some(interrupts) = &stats.interrupts_by_cpu {
+        let _ = writeln!(
+            out,
+            "# HELP {METRIC_PREFIX}interrupts_per_second Per-CPU hardware interrupt rate"
+        );
+        let _ = writeln!(out, "# TYPE {METRIC_PREFIX}interrupts_per_second gauge");
+        for (cpu, rate) in interrupts.cpus.iter().zip(&interrupts.per_cpu_per_second) {
+            let _ = writeln!(
+                out,
+                "{METRIC_PREFIX}interrupts_per_second{{cpu=\"{cpu}\"}} {rate}"
+            );
+        }
+    }
+
+    write_gauge(
+        &mut out,
+        "clock_synced",
+        "Whether the system clock is synchronized (1) or not (0)",
+        u8::from(stats.clock_synced),
+    );
+    if let Some(error) = stats.clock_estimated_error {
+        write_gauge(
+            &mut out,
+            "clock_estimated_error_seconds",
+            "Estimated system clock error reported by the kernel",
+            error.as_secs_f64(),
+        );
+    }
+
+    for (key, value) in &stats.custom {
+        let _ = writeln!(out, "# TYPE {CUSTOM_METRIC_PREFIX}{key} gauge");
+        let _ = writeln!(out, "{CUSTOM_METRIC_PREFIX}{key} {value}");
+    }
+
+    out
+}
+
+/// 追加后台采集间隔诊断指标
+pub fn render_timing_metrics(out: &mut String, timing: &TimingSnapshot) {
+    write_gauge(
+        out,
+        "collection_interval_seconds",
+        "Actual interval between the last two background collections",
+        timing.last_interval.as_secs_f64(),
+    );
+    write_gauge(
+        out,
+        "collection_jitter_seconds",
+        "Deviation of the last background collection interval from the cache TTL",
+        timing.last_jitter.as_secs_f64(),
+    );
+    write_gauge(
+        out,
+        "collection_jitter_max_seconds",
+        "Largest observed deviation of the background collection interval from the cache TTL",
+        timing.max_jitter.as_secs_f64(),
+    );
+}
+
+/// 追加监控进程自身的 jemalloc 分配器统计
+#[cfg(feature = "jemalloc")]
+pub fn render_allocator_metrics(out: &mut String, stats: &crate::allocator::AllocatorStats) {
+    let gauges = [
+        (
+            "allocator_allocated_bytes",
+            "Bytes allocated by this process and not yet freed",
+            stats.allocated,
+        ),
+        (
+            "allocator_active_bytes",
+            "Bytes in active pages holding allocations",
+            stats.active,
+        ),
+        (
+            "allocator_resident_bytes",
+            "Bytes of physical memory held by the allocator",
+            stats.resident,
+        ),
+        (
+            "allocator_mapped_bytes",
+            "Bytes of virtual memory mapped by the allocator",
+            stats.mapped,
+        ),
+        (
+            "allocator_retained_bytes",
+            "Bytes retained by the allocator instead of being returned to the OS",
+            stats.retained,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        write_gauge(out, name, help, value);
+    }
+    write_gauge(
+        out,
+        "allocator_fragmentation_ratio",
+        "Fraction of active pages not used by allocations",
+        stats.fragmentation_ratio,
+    );
+}
+
+/// 追加采集断路器状态
+pub fn render_breaker_metrics(out: &mut String, breaker: &BreakerSnapshot) {
+    write_gauge(
+        out,
+        "collection_circuit_state",
+        "Collection circuit breaker state (0 = closed, 1 = open, 2 = half-open)",
+        breaker.state.as_metric(),
+    );
+    write_gauge(
+        out,
+        "collection_consecutive_failures",
+        "Number of consecutive failed collections",
+        breaker.consecutive_failures,
+    );
+    write_counter(
+        out,
+        "collection_circuit_trips_total",
+        "Number of times the collection circuit breaker has opened",
+        breaker.trips,
+    );
+}
+
+/// 估计抓取间隔时参考的最近间隔数
+const SCRAPE_WINDOW: usize = 8;
+
+/// 至少观察到多少个抓取间隔才给出建议
+const SCRAPE_MIN_SAMPLES: usize = 4;
+
+/// 两次抓取间隔建议之间的最短间隔
+const SCRAPE_HINT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// 追加缓存 TTL 与观察到的抓取间隔，便于把 Prometheus 抓取间隔与 TTL 对齐
+pub fn render_scrape_metrics(out: &mut String, ttl: Duration, observed: Option<Duration>) {
+    write_gauge(
+        out,
+        "cache_ttl_seconds",
+        "Cache TTL; align the Prometheus scrape_interval with it",
+        ttl.as_secs_f64(),
+    );
+    if let Some(observed) = observed {
+        write_gauge(
+            out,
+            "scrape_interval_seconds",
+            "Median interval between recent scrapes of this endpoint",
+            observed.as_secs_f64(),
+        );
+    }
+}
+
+/// 抓取间隔建议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrapeHint {
+    /// 抓取远比 TTL 频繁，多次抓到相同数据
+    TooFrequent { observed: Duration, ttl: Duration },
+    /// 抓取远比 TTL 稀疏，后台刷新时多数采集结果无人读取
+    TooSparse { observed: Duration, ttl: Duration },
+}
+
+impl std::fmt::Display for ScrapeHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::TooFrequent { observed, ttl } => write!(
+                f,
+                "/metrics 抓取间隔约 {:.1} 秒，远短于缓存 TTL {} 秒，多次抓取到的是相同数据；建议的 scrape_interval: {}s（或调小 --ttl）",
+                observed.as_secs_f64(),
+                ttl.as_secs(),
+                ttl.as_secs()
+            ),
+            Self::TooSparse { observed, ttl } => write!(
+                f,
+                "/metrics 抓取间隔约 {:.1} 秒，远长于缓存 TTL {} 秒，启用后台刷新时多数采集结果不会被抓取；建议的 scrape_interval: {}s（或调大 --ttl）",
+                observed.as_secs_f64(),
+                ttl.as_secs(),
+                ttl.as_secs()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScrapeState {
+    last_scrape: Option<Instant>,
+    intervals: VecDeque<Duration>,
+    last_hint: Option<Instant>,
+}
+
+/// 根据 `/metrics` 的抓取时间估计抓取间隔，并在与 TTL 相差过大时给出建议
+///
+/// 多个 Prometheus 实例同时抓取时观察到的是合并后的间隔。
+#[derive(Debug, Default)]
+pub struct ScrapeAdvisor {
+    state: Mutex<ScrapeState>,
+}
+
+impl ScrapeAdvisor {
+    /// 记录一次抓取，抓取间隔中位数不在 TTL 的 0.5-2 倍之间时返回建议，同一小时内至多一次
+    pub fn observe(&self, now: Instant, ttl: Duration) -> Option<ScrapeHint> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(last) = state.last_scrape.replace(now) {
+            if state.intervals.len() == SCRAPE_WINDOW {
+                state.intervals.pop_front();
+            }
+            state
+                .intervals
+                .push_back(now.saturating_duration_since(last));
+        }
+        if state.intervals.len() < SCRAPE_MIN_SAMPLES
+            || ttl.is_zero()
+            || state
+                .last_hint
+                .is_some_and(|hint| now.saturating_duration_since(hint) < SCRAPE_HINT_INTERVAL)
+        {
+            return None;
+        }
+
+        let observed = median(&state.intervals);
+        let hint = if observed * 2 < ttl {
+            ScrapeHint::TooFrequent { observed, ttl }
+        } else if observed > ttl * 2 {
+            ScrapeHint::TooSparse { observed, ttl }
+        } else {
+            return None;
+        };
+        state.last_hint = Some(now);
+        Some(hint)
+    }
+
+    /// 最近抓取间隔的中位数，尚无间隔时返回 `None`
+    pub fn observed_interval(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (!state.intervals.is_empty()).then(|| median(&state.intervals))
+    }
+}
+
+#[inline]
+fn median(intervals: &VecDeque<Duration>) -> Duration {
+    let mut sorted: Vec<Duration> = intervals.iter().copied().collect();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+/// HTTP 端点，`path` 标签取值固定，标签基数不随请求路径增长
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Root,
+    Health,
+    ApiStats,
+    Metrics,
+    #[cfg(feature = "sqlite")]
+    ApiHistory,
+    #[cfg(feature = "jemalloc")]
+    DebugAllocator,
+    /// 未匹配任何路由的请求（含 404 与不支持的方法）
+    Other,
+}
+
+impl Endpoint {
+    /// 所有端点，顺序即导出顺序
+    pub const ALL: &[Endpoint] = &[
+        Endpoint::Root,
+        Endpoint::Health,
+        Endpoint::ApiStats,
+        Endpoint::Metrics,
+        #[cfg(feature = "sqlite")]
+        Endpoint::ApiHistory,
+        #[cfg(feature = "jemalloc")]
+        Endpoint::DebugAllocator,
+        Endpoint::Other,
+    ];
+
+    /// 导出时使用的 `path` 标签值
+    #[inline]
+    pub fn label(self) -> &'static str {
+        match self {
+            Endpoint::Root => "/",
+            Endpoint::Health => "/health",
+            Endpoint::ApiStats => "/api/stats",
+            Endpoint::Metrics => "/metrics",
+            #[cfg(feature = "sqlite")]
+            Endpoint::ApiHistory => "/api/history",
+            #[cfg(feature = "jemalloc")]
+            Endpoint::DebugAllocator => "/debug/allocator",
+            Endpoint::Other => "other",
+        }
+    }
+}
+
+/// 请求延迟直方图的桶上界（秒）
+const LATENCY_BUCKETS: [f64; 10] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0,
+];
+
+/// 单个端点的请求计数与延迟直方图
+#[derive(Debug, Default)]
+struct EndpointCounters {
+    /// 各桶的非累积计数，最后一个元素对应 `+Inf`
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+/// 按端点统计的 HTTP 请求计数与延迟
+#[derive(Debug, Default)]
+pub struct EndpointMetrics {
+    endpoints: [EndpointCounters; Endpoint::ALL.len()],
+}
+
+impl EndpointMetrics {
+    /// 记录一次请求
+    pub fn record(&self, endpoint: Endpoint, elapsed: Duration) {
+        let counters = &self.endpoints[endpoint as usize];
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        counters.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        counters
+            .sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        counters.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取某个端点的请求总数
+    #[inline]
+    pub fn count(&self, endpoint: Endpoint) -> u64 {
+        self.endpoints[endpoint as usize]
+            .count
+            .load(Ordering::Relaxed)
+    }
+
+    /// 追加按端点统计的请求计数与延迟直方图（`path` 标签）
+    pub fn render(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "# HELP {METRIC_PREFIX}http_requests_total Number of HTTP requests by endpoint"
+        );
+        let _ = writeln!(out, "# TYPE {METRIC_PREFIX}http_requests_total counter");
+        for &endpoint in Endpoint::ALL {
+            let _ = writeln!(
+                out,
+                "{METRIC_PREFIX}http_requests_total{{path=\"{}\"}} {}",
+                endpoint.label(),
+                self.count(endpoint)
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP {METRIC_PREFIX}http_request_duration_seconds HTTP request latency by endpoint"
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE {METRIC_PREFIX}http_request_duration_seconds histogram"
+        );
+        for &endpoint in Endpoint::ALL {
+            let counters = &self.endpoints[endpoint as usize];
+            let path = endpoint.label();
+            let mut cumulative = 0;
+            for (i, bucket) in counters.buckets.iter().enumerate() {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let le = LATENCY_BUCKETS
+                    .get(i)
+                    .map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+                let _ = writeln!(
+                    out,
+                    "{METRIC_PREFIX}http_request_duration_seconds_bucket{{path=\"{path}\",le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let sum = counters.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(
+                out,
+                "{METRIC_PREFIX}http_request_duration_seconds_sum{{path=\"{path}\"}} {sum}"
+            );
+            // 并发记录时 count 可能略小于最后一个桶的累计值，导出时以桶累计值为准保持一致
+            let _ = writeln!(
+                out,
+                "{METRIC_PREFIX}http_request_duration_seconds_count{{path=\"{path}\"}} {cumulative}"
+            );
+        }
+    }
+}
+
+/// HTTP 连接复用统计：连接建立与请求处理两个层面分别计数
+///
+/// 每连接平均请求数接近 1 说明客户端没有复用连接（或 keep-alive 超时过短）。
+#[derive(Debug, Default)]
+pub struct ConnectionMetrics {
+    connections: AtomicU64,
+    requests: AtomicU64,
+    reused_requests: AtomicU64,
+}
+
+impl ConnectionMetrics {
+    /// 记录一个新建连接
+    #[inline]
+    pub fn record_connection(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录连接上处理的一个请求，`served_before` 为该连接此前已处理的请求数
+    #[inline]
+    pub fn record_request(&self, served_before: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if served_before > 0 {
+            self.reused_requests.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 平均每个连接处理的请求数，尚无连接时为 0
+    pub fn requests_per_connection(&self) -> f64 {
+        let connections = self.connections.load(Ordering::Relaxed);
+        if connections == 0 {
+            return 0.0;
+        }
+        self.requests.load(Ordering::Relaxed) as f64 / connections as f64
+    }
+
+    /// 追加连接数、请求数与每连接平均请求数
+    pub fn render(&self, out: &mut String) {
+        write_counter(
+            out,
+            "http_connections_total",
+            "Number of accepted HTTP connections",
+            self.connections.load(Ordering::Relaxed),
+        );
+        write_counter(
+            out,
+            "http_connection_requests_total",
+            "Number of HTTP requests served, counted per connection",
+            self.requests.load(Ordering::Relaxed),
+        );
+        write_counter(
+            out,
+            "http_connection_reused_requests_total",
+            "Number of HTTP requests served on an already used (keep-alive) connection",
+            self.reused_requests.load(Ordering::Relaxed),
+        );
+        write_gauge(
+            out,
+            "http_requests_per_connection",
+            "Average number of HTTP requests served per connection",
+            self.requests_per_connection(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breaker::BreakerState;
+    use crate::stats::{ConntrackStats, CpuUsageBreakdown};
+
+    #[test]
+    fn test_render_metrics() {
+        let mut stats = SystemStats {
+            cpu_usage: 0.25,
+            memory_total: 1024,
+            memory_used: 512,
+            conntrack: Some(ConntrackStats {
+                count: 10,
+                max: 100,
+                usage_percent: 10.0,
+            }),
+            ..SystemStats::default()
+        };
+        stats.cpu_stats.per_core = vec![CpuUsageBreakdown {
+            total_percent: 50.0,
+            ..CpuUsageBreakdown::default()
+        }];
+        stats.custom.insert("queue_length".to_string(), 42.0);
+
+        let metrics = render_metrics(&stats);
+        assert!(metrics.contains("# TYPE swb_cpu_usage_ratio gauge\n"));
+        assert!(metrics.contains("swb_cpu_usage_ratio 0.25\n"));
+        assert!(metrics.contains("swb_cpu_core_usage_percent{core=\"0\"} 50\n"));
+
+        // 筛选核心后以实际核心编号作为标签
+        stats.cpu_stats.core_ids = Some(vec![3]);
+        assert!(render_metrics(&stats).contains("swb_cpu_core_usage_percent{core=\"3\"} 50\n"));
+        assert!(metrics.contains("swb_memory_total_bytes 1024\n"));
+        assert!(metrics.contains("swb_memory_used_bytes 512\n"));
+        assert!(metrics.contains("swb_conntrack_entries 10\n"));
+        assert!(metrics.contains("swb_conntrack_entries_limit 100\n"));
+        assert!(metrics.contains("swb_custom_queue_length 42\n"));
+        assert!(metrics.contains("swb_clock_synced 0\n"));
+        assert!(!metrics.contains("swb_clock_estimated_error_seconds"));
+
+        stats.clock_synced = true;
+        stats.clock_estimated_error = Some(Duration::from_millis(2));
+        let metrics = render_metrics(&stats);
+        assert!(metrics.contains("swb_clock_synced 1\n"));
+        assert!(metrics.contains("swb_clock_estimated_error_seconds 0.002\n"));
+    }
+
+    #[cfg(feature = "interrupts")]
+    #[test]
+    fn test_render_interrupt_metrics() {
+        let mut stats = SystemStats::default();
+        assert!(!render_metrics(&stats).contains("swb_interrupts_per_second"));
+
+        stats.interrupts_by_cpu = Some(crate::interrupts::InterruptStats {
+            cpus: vec![0, 2],
+            per_cpu_per_second: vec![1500.0, 20.5],
+            top_sources: Vec::new(),
+            hotspot_cpus: vec![0],
+        });
+        let metrics = render_metrics(&stats);
+        assert!(metrics.contains("swb_interrupts_per_second{cpu=\"0\"} 1500\n"));
+        assert!(metrics.contains("swb_interrupts_per_second{cpu=\"2\"} 20.5\n"));
+    }
+
+    #[test]
+    fn test_render_timing_metrics() {
+        let mut out = String::new();
+        render_timing_metrics(
+            &mut out,
+            &TimingSnapshot {
+                samples: 3,
+                last_interval: std::time::Duration::from_millis(10_250),
+                last_jitter: std::time::Duration::from_millis(250),
+                max_jitter: std::time::Duration::from_millis(500),
+            },
+        );
+        assert!(out.contains("swb_collection_interval_seconds 10.25\n"));
+        assert!(out.contains("swb_collection_jitter_seconds 0.25\n"));
+        assert!(out.contains("swb_collection_jitter_max_seconds 0.5\n"));
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[test]
+    fn test_render_allocator_metrics() {
+        use crate::allocator::AllocatorStats;
+        let mut out = String::new();
+        render_allocator_metrics(&mut out, &AllocatorStats::new(750, 1000, 2048, 4096, 512));
+        assert!(out.contains("# TYPE swb_allocator_allocated_bytes gauge\n"));
+        assert!(out.contains("swb_allocator_allocated_bytes 750\n"));
+        assert!(out.contains("swb_allocator_resident_bytes 2048\n"));
+        assert!(out.contains("swb_allocator_retained_bytes 512\n"));
+        assert!(out.contains("swb_allocator_fragmentation_ratio 0.25\n"));
+    }
+
+    #[test]
+    fn test_render_breaker_metrics() {
+        let mut out = String::new();
+        render_breaker_metrics(
+            &mut out,
+            &BreakerSnapshot {
+                state: BreakerState::Open,
+                consecutive_failures: 5,
+                trips: 2,
+            },
+        );
+        assert!(out.contains("swb_collection_circuit_state 1\n"));
+        assert!(out.contains("swb_collection_consecutive_failures 5\n"));
+        assert!(out.contains("# TYPE swb_collection_circuit_trips_total counter\n"));
+        assert!(out.contains("swb_collection_circuit_trips_total 2\n"));
+    }
+
+    #[test]
+    fn test_render_scrape_metrics() {
+        let mut out = String::new();
+        render_scrape_metrics(&mut out, Duration::from_secs(10), None);
+        assert!(out.contains("swb_cache_ttl_seconds 10\n"));
+        assert!(!out.contains("swb_scrape_interval_seconds"));
+
+        render_scrape_metrics(
+            &mut out,
+            Duration::from_secs(10),
+            Some(Duration::from_secs(15)),
+        );
+        assert!(out.contains("swb_scrape_interval_seconds 15\n"));
+    }
+
+    #[test]
+    fn test_scrape_advisor() {
+        let ttl = Duration::from_secs(10);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // 与 TTL 对齐时不给建议
+        let advisor = ScrapeAdvisor::default();
+        for i in 0..10 {
+            assert_eq!(advisor.observe(at(i * 10), ttl), None);
+        }
+        assert_eq!(advisor.observed_interval(), Some(ttl));
+
+        // 每秒抓取：样本足够时提示一次，随后一小时内不再重复
+        let advisor = ScrapeAdvisor::default();
+        let hints: Vec<_> = (0..20)
+            .filter_map(|i| advisor.observe(at(i), ttl))
+            .collect();
+        assert_eq!(
+            hints,
+            vec![ScrapeHint::TooFrequent {
+                observed: Duration::from_secs(1),
+                ttl
+            }]
+        );
+        assert!(hints[0].to_string().contains("建议的 scrape_interval: 10s"));
+
+        // 每分钟抓取
+        let advisor = ScrapeAdvisor::default();
+        let hint = (0..5)
+            .filter_map(|i| advisor.observe(at(i * 60), ttl))
+            .next();
+        assert!(matches!(hint, Some(ScrapeHint::TooSparse { .. })));
+    }
+
+    #[test]
+    fn test_endpoint_metrics() {
+        let metrics = EndpointMetrics::default();
+        metrics.record(Endpoint::Root, Duration::from_micros(300));
+        metrics.record(Endpoint::Root, Duration::from_millis(20));
+        metrics.record(Endpoint::Metrics, Duration::from_secs(2));
+
+        assert_eq!(metrics.count(Endpoint::Root), 2);
+        assert_eq!(metrics.count(Endpoint::Health), 0);
+
+        let mut out = String::new();
+        metrics.render(&mut out);
+        assert!(out.contains("swb_http_requests_total{path=\"/\"} 2\n"));
+        assert!(out.contains("swb_http_requests_total{path=\"other\"} 0\n"));
+        assert!(
+            out.contains("swb_http_request_duration_seconds_bucket{path=\"/\",le=\"0.0005\"} 1\n")
+        );
+        assert!(
+            out.contains("swb_http_request_duration_seconds_bucket{path=\"/\",le=\"0.01\"} 1\n")
+        );
+        assert!(
+            out.contains("swb_http_request_duration_seconds_bucket{path=\"/\",le=\"0.025\"} 2\n")
+        );
+        assert!(
+            out.contains(
+                "swb_http_request_duration_seconds_bucket{path=\"/metrics\",le=\"1\"} 0\n"
+            )
+        );
+        assert!(out.contains(
+            "swb_http_request_duration_seconds_bucket{path=\"/metrics\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(out.contains("swb_http_request_duration_seconds_count{path=\"/\"} 2\n"));
+        assert!(out.contains("swb_http_request_duration_seconds_sum{path=\"/\"} 0.0203\n"));
+    }
+
+    #[test]
+    fn test_connection_metrics() {
+        let metrics = ConnectionMetrics::default();
+        assert_eq!(metrics.requests_per_connection(), 0.0);
+
+        // 一个连接复用了 3 次，另一个只处理了 1 个请求
+        metrics.record_connection();
+        for served_before in 0..3 {
+            metrics.record_request(served_before);
+        }
+        metrics.record_connection();
+        metrics.record_request(0);
+        assert_eq!(metrics.requests_per_connection(), 2.0);
+
+        let mut out = String::new();
+        metrics.render(&mut out);
+        assert!(out.contains("# TYPE swb_http_connections_total counter\n"));
+        assert!(out.contains("swb_http_connections_total 2\n"));
+        assert!(out.contains("swb_http_connection_requests_total 4\n"));
+        assert!(out.contains("swb_http_connection_reused_requests_total 2\n"));
+        assert!(out.contains("swb_http_requests_per_connection 2\n"));
+    }
+
+    #[test]
+    fn test_render_metrics_without_optional_sections() {
+        let metrics = render_metrics(&SystemStats::default());
+        assert!(!metrics.contains("cpu_core_usage_percent"));
+        assert!(!metrics.contains("conntrack"));
+        assert!(!metrics.contains(CUSTOM_METRIC_PREFIX));
+    }
+}
@@ -0,0 +1,137 @@
+//! 复用文件句柄的 proc 文件读取
+//!
+//! /proc/stat、/proc/meminfo 每个采集周期都要读一次，每次 open/read/close 至少三个系统调用，
+//! 外加路径查找与 tokio 的阻塞线程池调度。这些文件的内容在每次读取时由内核重新生成，
+//! 大小不固定，不能 mmap，但打开后的句柄可以一直持有：从偏移 0 开始 `pread`，
+//! 内核会重新生成内容，读到 EOF 即得到最新数据。正常情况下一次读取只需两次 `pread`
+//! （一次读数据，一次确认 EOF）。
+//!
+//! 内容比上次长时缓冲区按需扩展，始终读到 EOF 为止，不会截断。读取出错时丢弃句柄，
+//! 下次读取重新打开。句柄指向打开时的文件，若文件被删除后重建（而不是原地改写），
+//! 需要重新创建采集器才能读到新文件，proc 文件不存在这种情况。
+
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 读缓冲区的初始大小，内容更长时加倍
+const INITIAL_CAPACITY: usize = 4096;
+
+#[derive(Debug, Default)]
+struct Inner {
+    file: Option<File>,
+    /// 跨读取复用的缓冲区，长度即可用容量，只在扩展时清零新增部分
+    buf: Vec<u8>,
+}
+
+/// 持有打开句柄、每次从头重读的 proc 文件
+#[derive(Debug)]
+pub struct ProcFile {
+    path: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+impl ProcFile {
+    /// 创建读取 `path` 的实例，首次读取时才打开文件
+    #[inline]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// 从头读取文件的完整内容
+    pub fn read_to_string(&self) -> io::Result<String> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Inner { file, buf } = &mut *inner;
+        // 读取失败时句柄已被取出并随之丢弃，下次重新打开
+        let opened = match file.take() {
+            Some(opened) => opened,
+            None => File::open(&self.path)?,
+        };
+
+        let mut filled = 0;
+        loop {
+            if filled == buf.len() {
+                buf.resize((buf.len() * 2).max(INITIAL_CAPACITY), 0);
+            }
+            match read_at(&opened, &mut buf[filled..], filled as u64) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        *file = Some(opened);
+
+        std::str::from_utf8(&buf[..filled])
+            .map(str::to_owned)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// 从 `offset` 处读取，不改变（unix）或不依赖（其他平台）文件的当前偏移
+#[cfg(unix)]
+#[inline]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(not(unix))]
+fn read_at(mut file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.read(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("swb-proc-file-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn test_reread_after_change() {
+        let path = temp_path("change");
+        fs::write(&path, "short").unwrap();
+        let file = ProcFile::new(&path);
+        assert_eq!(file.read_to_string().unwrap(), "short");
+
+        // 原地改写（同一 inode），内容变长后仍读到 EOF
+        let long = "x".repeat(INITIAL_CAPACITY * 3 + 7);
+        fs::write(&path, &long).unwrap();
+        assert_eq!(file.read_to_string().unwrap(), long);
+        fs::write(&path, "a").unwrap();
+        assert_eq!(file.read_to_string().unwrap(), "a");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_reopen_after_error() {
+        let path = temp_path("reopen");
+        let file = ProcFile::new(&path);
+        assert!(file.read_to_string().is_err());
+
+        fs::write(&path, "ok").unwrap();
+        assert_eq!(file.read_to_string().unwrap(), "ok");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_reread_proc_stat() {
+        let file = ProcFile::new("/proc/stat");
+        let first = file.read_to_string().unwrap();
+        let second = file.read_to_string().unwrap();
+        assert!(first.starts_with("cpu "));
+        assert!(second.starts_with("cpu "));
+        // 内核每次重新生成内容，行数不变
+        assert_eq!(first.lines().count(), second.lines().count());
+    }
+}
@@ -0,0 +1,401 @@
+//! Prometheus remote_write 推送
+//!
+//! NAT 或防火墙后的设备无法被 Prometheus 主动抓取时，由设备周期性地把与 `/metrics` 相同的
+//! 采集指标以 remote_write 协议（protobuf + snappy）推送到配置的 endpoint。
+//! 每条序列附加 `job` 与 `instance`（主机名）标签以区分设备。
+//!
+//! 推送失败只记录日志，不重试：下一个周期推送的是新数据，旧数据不补发。
+//! 目前只支持 `http://` endpoint，HTTPS 需经本机或网关上的反向代理转发。
+
+use crate::cache::CacheRef;
+use anyhow::{Context, Result, bail};
+use hyper::header::{CONTENT_ENCODING, CONTENT_TYPE, HeaderName, HeaderValue, USER_AGENT};
+use hyper::{Body, Client, Method, Request, Uri};
+use log::{debug, warn};
+use std::time::Duration;
+
+/// 推送时附加的 `job` 标签值
+pub const JOB_LABEL: &str = "swb-sys-monitor";
+
+/// 单次推送的超时
+pub const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// remote_write 推送配置
+#[derive(Debug, Clone)]
+pub struct RemoteWriteConfig {
+    /// 接收端地址，如 `http://prometheus:9090/api/v1/write`
+    pub endpoint: Uri,
+    /// 推送间隔
+    pub interval: Duration,
+    /// 附加的请求头（如认证用的 `Authorization`）
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl RemoteWriteConfig {
+    /// 解析 endpoint 与 `名称: 值` 形式的请求头
+    pub fn new(endpoint: &str, interval: Duration, headers: &[String]) -> Result<Self> {
+        let endpoint: Uri = endpoint
+            .parse()
+            .with_context(|| format!("无效的 remote_write 地址：{endpoint}"))?;
+        match endpoint.scheme_str() {
+            Some("http") => {}
+            Some("https") => bail!("remote_write 暂不支持 HTTPS，请经反向代理转发：{endpoint}"),
+            _ => bail!("remote_write 地址须以 http:// 开头：{endpoint}"),
+        }
+        let headers = headers
+            .iter()
+            .map(|header| parse_header(header))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            endpoint,
+            interval,
+            headers,
+        })
+    }
+}
+
+/// 解析 `名称: 值` 形式的请求头
+fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue)> {
+    let Some((name, value)) = header.split_once(':') else {
+        bail!("请求头格式应为 `名称: 值`：{header}");
+    };
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .with_context(|| format!("无效的请求头名称：{header}"))?;
+    let value =
+        HeaderValue::from_str(value.trim()).with_context(|| format!("无效的请求头值：{header}"))?;
+    Ok((name, value))
+}
+
+/// 一条时间序列：按名称排序的标签（含 `__name__`）与一个样本值
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries {
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+/// 解析 Prometheus 文本格式为时间序列，并给每条序列附加 `extra_labels`
+///
+/// 注释行与无法解析的行被忽略；已有同名标签时保留原值。
+pub fn parse_exposition(text: &str, extra_labels: &[(&str, &str)]) -> Vec<TimeSeries> {
+    text.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut series = parse_sample_line(line)?;
+            for &(name, value) in extra_labels {
+                if !series.labels.iter().any(|(existing, _)| existing == name) {
+                    series.labels.push((name.to_string(), value.to_string()));
+                }
+            }
+            series.labels.sort_by(|a, b| a.0.cmp(&b.0));
+            Some(series)
+        })
+        .collect()
+}
+
+/// 解析一行 `name{label="value",...} value`
+fn parse_sample_line(line: &str) -> Option<TimeSeries> {
+    let name_end = line.find(['{', ' '])?;
+    let mut labels = vec![("__name__".to_string(), line[..name_end].to_string())];
+    let mut rest = &line[name_end..];
+
+    if let Some(mut body) = rest.strip_prefix('{') {
+        loop {
+            body = body.trim_start_matches(',');
+            if let Some(after) = body.strip_prefix('}') {
+                rest = after;
+                break;
+            }
+            let (name, after_name) = body.split_once("=\"")?;
+            let (value, after_value) = parse_label_value(after_name)?;
+            labels.push((name.trim().to_string(), value));
+            body = after_value;
+        }
+    }
+
+    // 样本值之后可能跟着时间戳，忽略
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+    Some(TimeSeries { labels, value })
+}
+
+/// 解析带转义的标签值，返回值与结束引号之后的剩余部分
+fn parse_label_value(input: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &input[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                escaped => value.push(escaped),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// 按 remote_write 的 `WriteRequest` 消息编码（所有序列共用同一时间戳，Unix 毫秒）
+pub fn encode_write_request(series: &[TimeSeries], timestamp_ms: i64) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut series_buf = Vec::new();
+    let mut field_buf = Vec::new();
+    for entry in series {
+        series_buf.clear();
+        for (name, value) in &entry.labels {
+            // Label { string name = 1; string value = 2; }
+            field_buf.clear();
+            write_bytes_field(&mut field_buf, 1, name.as_bytes());
+            write_bytes_field(&mut field_buf, 2, value.as_bytes());
+            write_bytes_field(&mut series_buf, 1, &field_buf);
+        }
+        // Sample { double value = 1; int64 timestamp = 2; }
+        field_buf.clear();
+        write_key(&mut field_buf, 1, WIRE_FIXED64);
+        field_buf.extend_from_slice(&entry.value.to_le_bytes());
+        write_key(&mut field_buf, 2, WIRE_VARINT);
+        write_varint(&mut field_buf, timestamp_ms as u64);
+        write_bytes_field(&mut series_buf, 2, &field_buf);
+        // WriteRequest { repeated TimeSeries timeseries = 1; }
+        write_bytes_field(&mut out, 1, &series_buf);
+    }
+    out
+}
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+
+#[inline]
+fn write_key(out: &mut Vec<u8>, field: u32, wire_type: u8) {
+    write_varint(out, u64::from(field << 3 | u32::from(wire_type)));
+}
+
+#[inline]
+fn write_bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(out, field, WIRE_LEN);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// 采集一次并推送到 endpoint
+async fn push_once(
+    client: &Client<hyper::client::HttpConnector>,
+    config: &RemoteWriteConfig,
+    cache: &CacheRef,
+) -> Result<usize> {
+    // 刷新失败时推送上次成功的数据，断路器等诊断指标在故障期间照样送达
+    let stats = match cache.get_or_update().await {
+        Ok(stats) => stats,
+        Err(e) => match cache.get_with_age() {
+            Some((stats, _)) => stats,
+            None => return Err(e.into()),
+        },
+    };
+    let mut text = crate::metrics::render_metrics(&stats);
+    if let Some(timing) = cache.timing().snapshot() {
+        crate::metrics::render_timing_metrics(&mut text, &timing);
+    }
+    if let Some(breaker) = cache.breaker() {
+        crate::metrics::render_breaker_metrics(&mut text, &breaker.snapshot());
+    }
+
+    let series = parse_exposition(
+        &text,
+        &[("job", JOB_LABEL), ("instance", stats.hostname.as_str())],
+    );
+    // 样本时间取采集时刻，推送缓存中的旧数据时不会被误标为当前时刻
+    let timestamp_ms = match stats.collected_at_unix_ms {
+        0 => crate::stats::unix_millis(),
+        collected_at => collected_at,
+    } as i64;
+    let body = snap::raw::Encoder::new()
+        .compress_vec(&encode_write_request(&series, timestamp_ms))
+        .context("snappy 压缩失败")?;
+
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri(config.endpoint.clone())
+        .header(CONTENT_TYPE, "application/x-protobuf")
+        .header(CONTENT_ENCODING, "snappy")
+        .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+        .header(
+            USER_AGENT,
+            concat!("swb-sys-monitor/", env!("CARGO_PKG_VERSION")),
+        )
+        .body(Body::from(body))?;
+    for (name, value) in &config.headers {
+        request.headers_mut().insert(name.clone(), value.clone());
+    }
+
+    let response = tokio::time::timeout(PUSH_TIMEOUT, client.request(request))
+        .await
+        .context("推送超时")??;
+    let status = response.status();
+    if !status.is_success() {
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap_or_default();
+        bail!(
+            "接收端返回 HTTP {}：{}",
+            status.as_u16(),
+            String::from_utf8_lossy(&body).trim()
+        );
+    }
+    Ok(series.len())
+}
+
+/// 启动周期推送任务
+pub fn spawn(cache: CacheRef, config: RemoteWriteConfig) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = Client::new();
+        let mut interval = tokio::time::interval(config.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match push_once(&client, &config, &cache).await {
+                Ok(count) => debug!("已推送 {count} 条序列到 {}", config.endpoint),
+                Err(e) => warn!("remote_write 推送到 {} 失败: {e:#}", config.endpoint),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::SystemStats;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Response, Server};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_config() {
+        let headers = ["Authorization: Bearer secret".to_string()];
+        let config = RemoteWriteConfig::new(
+            "http://prom:9090/api/v1/write",
+            Duration::from_secs(15),
+            &headers,
+        )
+        .unwrap();
+        assert_eq!(config.endpoint.path(), "/api/v1/write");
+        assert_eq!(config.headers[0].0, "authorization");
+        assert_eq!(config.headers[0].1, "Bearer secret");
+
+        let interval = Duration::from_secs(15);
+        assert!(RemoteWriteConfig::new("https://prom/api/v1/write", interval, &[]).is_err());
+        assert!(RemoteWriteConfig::new("prom:9090", interval, &[]).is_err());
+        assert!(
+            RemoteWriteConfig::new("http://prom/", interval, &["no-colon".to_string()]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_exposition() {
+        let text = "# HELP swb_up Up\n# TYPE swb_up gauge\nswb_up 1\n\
+                    swb_core{core=\"0\",zone=\"a\\\"b\"} 12.5\n\
+                    swb_le{le=\"+Inf\"} 3 1700000000000\n\
+                    garbage line\n";
+        let series = parse_exposition(text, &[("job", "j"), ("instance", "h")]);
+        assert_eq!(series.len(), 3);
+        let labels = |i: usize| -> Vec<(&str, &str)> {
+            series[i]
+                .labels
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect()
+        };
+        assert_eq!(
+            labels(0),
+            [("__name__", "swb_up"), ("instance", "h"), ("job", "j")]
+        );
+        assert_eq!(series[0].value, 1.0);
+        assert_eq!(
+            labels(1),
+            [
+                ("__name__", "swb_core"),
+                ("core", "0"),
+                ("instance", "h"),
+                ("job", "j"),
+                ("zone", "a\"b")
+            ]
+        );
+        assert_eq!(series[1].value, 12.5);
+        assert_eq!(series[2].value, 3.0);
+    }
+
+    #[test]
+    fn test_encode_write_request() {
+        let series = [TimeSeries {
+            labels: vec![("__name__".to_string(), "up".to_string())],
+            value: 1.0,
+        }];
+        let encoded = encode_write_request(&series, 1000);
+        let mut expected = vec![0x0a, 0x1e]; // timeseries, 30 字节
+        expected.extend([0x0a, 0x0e, 0x0a, 0x08]); // labels -> name
+        expected.extend(b"__name__");
+        expected.extend([0x12, 0x02]);
+        expected.extend(b"up");
+        expected.extend([0x12, 0x0c, 0x09]); // samples -> value
+        expected.extend(1.0f64.to_le_bytes());
+        expected.extend([0x10, 0xe8, 0x07]); // timestamp = 1000
+        assert_eq!(encoded, expected);
+    }
+
+    #[tokio::test]
+    async fn test_push_once() {
+        let received = Arc::new(Mutex::new(None));
+        let sink = received.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            let sink = sink.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let sink = sink.clone();
+                    async move {
+                        let headers = req.headers().clone();
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        *sink.lock().unwrap() = Some((headers, body));
+                        Ok::<_, Infallible>(Response::new(Body::empty()))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let cache = crate::cache::create_cache(3600);
+        cache.update(SystemStats {
+            hostname: "edge-1".to_string(),
+            memory_total: 1024,
+            ..SystemStats::default()
+        });
+        let headers = ["Authorization: Bearer secret".to_string()];
+        let config = RemoteWriteConfig::new(
+            &format!("http://{addr}/api/v1/write"),
+            Duration::from_secs(15),
+            &headers,
+        )
+        .unwrap();
+        let count = push_once(&Client::new(), &config, &cache).await.unwrap();
+        assert!(count > 0);
+
+        let (headers, body) = received.lock().unwrap().take().unwrap();
+        assert_eq!(headers["content-encoding"], "snappy");
+        assert_eq!(headers["content-type"], "application/x-protobuf");
+        assert_eq!(headers["authorization"], "Bearer secret");
+        let decoded = snap::raw::Decoder::new().decompress_vec(&body).unwrap();
+        let contains = |needle: &[u8]| decoded.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"swb_memory_total_bytes"));
+        assert!(contains(b"edge-1"));
+        assert!(contains(JOB_LABEL.as_bytes()));
+    }
+}
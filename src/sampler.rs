@@ -0,0 +1,222 @@
+//! 独立的 CPU 采样子循环
+//!
+//! 整机采集得到的 CPU 使用率是相邻两次采集之间的平均值，采集周期较长时
+//! 会把瞬时峰值抹平。启用后，子循环以更短的间隔读取 /proc/stat 计算增量，
+//! 并以指数加权移动平均（EWMA）平滑，整机采集时直接取用平滑后的结果，
+//! 使 CPU 使用率反映更近的状态而不受整机采集周期影响。
+
+use crate::proc_file::ProcFile;
+use crate::stats::{
+    CoreTimes, CpuStats, CpuTimes, CpuUsageBreakdown, DEFAULT_PROC_ROOT, Result,
+    SUBSYSTEM_PER_CORE, SubsystemStatus, SystemStats,
+};
+use log::{debug, warn};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct SamplerState {
+    /// 上一次采样的总体与各核心时间
+    prev: Option<(CpuTimes, Vec<CoreTimes>)>,
+    /// 平滑后的 CPU 统计，至少完成两次采样后才有值
+    smoothed: Option<CpuStats>,
+}
+
+/// CPU 采样器
+#[derive(Debug)]
+pub struct CpuSampler {
+    proc_root: PathBuf,
+    interval: Duration,
+    alpha: f32,
+    reuse_fds: bool,
+    state: Mutex<SamplerState>,
+}
+
+impl CpuSampler {
+    /// 创建 CPU 采样器，`alpha` 为新样本的权重，限制在 (0, 1] 内
+    pub fn new(interval: Duration, alpha: f32) -> Self {
+        Self {
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            interval,
+            alpha: alpha.clamp(f32::EPSILON, 1.0),
+            reuse_fds: false,
+            state: Mutex::new(SamplerState::default()),
+        }
+    }
+
+    /// 指定 proc 根目录（默认 /proc）
+    #[inline]
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    /// 是否在子循环中复用 /proc/stat 的文件句柄（见 [`ProcFile`]）
+    #[inline]
+    pub fn with_reused_fds(mut self, enabled: bool) -> Self {
+        self.reuse_fds = enabled;
+        self
+    }
+
+    /// 平滑后的 CPU 统计，尚未完成两次采样时返回 `None`
+    pub fn current(&self) -> Option<CpuStats> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .smoothed
+            .clone()
+    }
+
+    /// 用采样结果替换整机采集得到的 CPU 数据，尚无采样结果时保持不变
+    pub fn apply(&self, stats: &mut SystemStats) {
+        if let Some(cpu_stats) = self.current() {
+            stats.cpu_usage = cpu_stats.overall.total_percent / 100.0;
+            stats.cpu_stats = cpu_stats;
+            // 采集器因 CPU 预算跳过了每核心数据时，不以平滑结果补回
+            if stats.subsystems.get(SUBSYSTEM_PER_CORE) == Some(&SubsystemStatus::Throttled) {
+                stats.cpu_stats.per_core.clear();
+            }
+        }
+    }
+
+    /// 处理一次 /proc/stat 内容
+    fn sample(&self, content: &str) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        let (overall, per_core, core_count) = crate::stats::parse_proc_stat(content)?;
+        #[cfg(not(target_os = "linux"))]
+        let (overall, per_core, core_count): (CpuTimes, Vec<CoreTimes>, usize) = {
+            let _ = content;
+            return Err(crate::stats::StatsError::UnsupportedPlatform);
+        };
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((prev_overall, prev_per_core)) = &state.prev {
+            let sample = CpuStats {
+                overall: crate::stats::calculate_cpu_usage_breakdown(prev_overall, &overall),
+                // 按核心编号匹配上一次采样，刚上线的核心本周期记为 0
+                per_core: per_core
+                    .iter()
+                    .map(|(id, current)| {
+                        crate::stats::find_core(prev_per_core, *id)
+                            .map(|prev| crate::stats::calculate_cpu_usage_breakdown(prev, current))
+                            .unwrap_or_default()
+                    })
+                    .collect(),
+                core_count,
+                core_ids: crate::stats::non_contiguous_core_ids(&per_core),
+            };
+
+            state.smoothed = Some(match state.smoothed.take() {
+                // 核心集合变化（CPU 热插拔）时重新开始平滑
+                Some(smoothed)
+                    if smoothed.per_core.len() == sample.per_core.len()
+                        && smoothed.core_ids == sample.core_ids =>
+                {
+                    CpuStats {
+                        overall: ewma(&smoothed.overall, &sample.overall, self.alpha),
+                        per_core: smoothed
+                            .per_core
+                            .iter()
+                            .zip(&sample.per_core)
+                            .map(|(old, new)| ewma(old, new, self.alpha))
+                            .collect(),
+                        core_count,
+                        core_ids: smoothed.core_ids,
+                    }
+                }
+                _ => sample,
+            });
+        }
+        state.prev = Some((overall, per_core));
+        Ok(())
+    }
+
+    /// 启动采样子循环，与整机采集周期无关
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let sampler = Arc::clone(self);
+        let stat_path = self.proc_root.join("stat");
+        let reused = self.reuse_fds.then(|| ProcFile::new(&stat_path));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(sampler.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let content = match &reused {
+                    Some(file) => file.read_to_string(),
+                    None => tokio::fs::read_to_string(&stat_path).await,
+                };
+                let result = match content {
+                    Ok(content) => sampler.sample(&content),
+                    Err(e) => Err(e.into()),
+                };
+                match result {
+                    Ok(()) => debug!("CPU 采样完成"),
+                    Err(e) => warn!("CPU 采样失败: {e}"),
+                }
+            }
+        })
+    }
+}
+
+/// 对单个使用率分解做一次 EWMA
+#[inline]
+fn ewma(old: &CpuUsageBreakdown, new: &CpuUsageBreakdown, alpha: f32) -> CpuUsageBreakdown {
+    let mix = |old: f32, new: f32| alpha * new + (1.0 - alpha) * old;
+    CpuUsageBreakdown {
+        user_percent: mix(old.user_percent, new.user_percent),
+        nice_percent: mix(old.nice_percent, new.nice_percent),
+        system_percent: mix(old.system_percent, new.system_percent),
+        total_percent: mix(old.total_percent, new.total_percent),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    /// 构造 /proc/stat 内容：总体与单个核心的 user 与 idle 时间
+    fn proc_stat(user: u64, idle: u64) -> String {
+        format!("cpu  {user} 0 0 {idle} 0 0 0 0 0 0\ncpu0 {user} 0 0 {idle} 0 0 0 0 0 0\n")
+    }
+
+    #[test]
+    fn test_sampler_ewma() {
+        let sampler = CpuSampler::new(Duration::from_secs(1), 0.5);
+        sampler.sample(&proc_stat(0, 0)).unwrap();
+        assert!(sampler.current().is_none());
+
+        // 第一个增量：100% 使用
+        sampler.sample(&proc_stat(100, 0)).unwrap();
+        let stats = sampler.current().unwrap();
+        assert_eq!(stats.overall.total_percent, 100.0);
+
+        // 第二个增量：0% 使用，EWMA 后为 50%
+        sampler.sample(&proc_stat(100, 100)).unwrap();
+        let stats = sampler.current().unwrap();
+        assert_eq!(stats.overall.total_percent, 50.0);
+        assert_eq!(stats.overall.user_percent, 50.0);
+        #[cfg(feature = "per-core")]
+        assert_eq!(stats.per_core[0].total_percent, 50.0);
+        assert_eq!(stats.core_count, 1);
+    }
+
+    #[test]
+    fn test_sampler_apply() {
+        let sampler = CpuSampler::new(Duration::from_secs(1), 1.0);
+        let mut stats = SystemStats {
+            cpu_usage: 0.9,
+            ..SystemStats::default()
+        };
+
+        // 尚无采样结果时保持整机采集的值
+        sampler.apply(&mut stats);
+        assert_eq!(stats.cpu_usage, 0.9);
+
+        sampler.sample(&proc_stat(0, 0)).unwrap();
+        sampler.sample(&proc_stat(25, 75)).unwrap();
+        sampler.apply(&mut stats);
+        assert_eq!(stats.cpu_usage, 0.25);
+        assert_eq!(stats.cpu_stats.overall.total_percent, 25.0);
+    }
+}
@@ -1,16 +1,35 @@
-use crate::cache::CacheRef;
-use anyhow::Result;
+use crate::cache::{CacheRef, RefreshState};
+use crate::format::Locale;
+use crate::i18n::{self, Language};
+use crate::listener::{
+    InterfaceFamily, MultiIncoming, interface_addresses, select_interface_addresses,
+};
+use crate::metrics::{ConnectionMetrics, Endpoint, EndpointMetrics, ScrapeAdvisor};
+use crate::stats::StatsError;
+use anyhow::{Context, Result};
+use hyper::header::HeaderValue;
 use hyper::http::StatusCode;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, Server};
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
+use std::any::Any;
 use std::convert::Infallible;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Instant;
 
 /// 状态服务器
 pub struct StatusServer {
     cache: CacheRef,
     cache_ttl_seconds: u64,
+    locale: Locale,
+    language: Language,
+    frame_policy: FramePolicy,
 }
 
 impl StatusServer {
@@ -30,56 +49,129 @@ impl StatusServer {
         Self {
             cache,
             cache_ttl_seconds,
+            locale: Locale::default(),
+            language: Language::default(),
+            frame_policy: FramePolicy::default(),
         }
     }
 
-    /// 运行服务器
-    pub async fn run(self, addr: SocketAddr) -> Result<()> {
-        let cache = self.cache.clone();
-        let cache_ttl_seconds = self.cache_ttl_seconds;
-
-        let make_svc = make_service_fn(move |_conn| {
-            let cache = cache.clone();
-            let cache_ttl_seconds = cache_ttl_seconds;
-            async move {
-                Ok::<_, Infallible>(service_fn(move |req| {
-                    let cache = cache.clone();
-                    let cache_ttl_seconds = cache_ttl_seconds;
-                    Self::handle_request(req, cache, cache_ttl_seconds)
-                }))
-            }
-        });
+    /// 指定页面数字格式的区域
+    #[inline]
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
 
-        // 创建服务器并配置高并发参数
-        let server = Server::bind(&addr)
-            .http1_keepalive(true)
-            .http1_half_close(false)
-            .tcp_keepalive(Some(std::time::Duration::from_secs(10)))
-            .tcp_nodelay(true)
-            .serve(make_svc);
+    /// 指定页面默认语言，请求未通过 `lang` 参数或 `Accept-Language` 指定支持的语言时使用
+    #[inline]
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
 
-        info!("服务器运行在: http://{addr}");
-        info!("已启用高并发模式，支持 HTTP/1.1 keep-alive");
+    /// 指定允许以 iframe 嵌入页面的来源，默认只允许同源嵌入
+    #[inline]
+    pub fn with_frame_policy(mut self, frame_policy: FramePolicy) -> Self {
+        self.frame_policy = frame_policy;
+        self
+    }
 
-        server.await.map_err(|e| {
-            error!("服务器错误: {e}");
-            anyhow::anyhow!("服务器运行错误: {e}")
-        })?;
+    /// 运行服务器，同时监听 `addrs` 中的所有地址
+    ///
+    /// 所有地址共用一个 accept 循环与同一份请求处理状态。
+    pub async fn run(self, addrs: &[SocketAddr]) -> Result<()> {
+        self.bind(addrs)?.serve().await
+    }
 
-        info!("服务器正常关闭");
-        Ok(())
+    /// 绑定 `addrs` 中的所有地址，暂不接受连接
+    ///
+    /// 绑定端口 0 时可先通过 [`BoundServer::local_addrs`] 取得系统分配的端口，
+    /// 再调用 [`BoundServer::serve`] 开始服务，其间端口始终由本服务器持有。
+    /// 需要在 tokio 运行时内调用。
+    pub fn bind(self, addrs: &[SocketAddr]) -> Result<BoundServer> {
+        let incoming = MultiIncoming::bind(addrs)?;
+        Ok(BoundServer {
+            server: self,
+            incoming,
+        })
     }
 
-    /// 处理 HTTP 请求
+    /// 处理 HTTP 请求，并按端点记录请求计数与延迟
     async fn handle_request(
         req: Request<Body>,
         cache: CacheRef,
         cache_ttl_seconds: u64,
+        state: Arc<ServerState>,
+    ) -> std::result::Result<Response<Body>, Infallible> {
+        let endpoint = Self::route(&req);
+        let handler = Self::dispatch(endpoint, &req, cache, cache_ttl_seconds, &state);
+        Self::run_handler(&req, endpoint, &state, handler).await
+    }
+
+    /// 运行端点的处理函数，记录请求计数与延迟并附加框架策略头
+    ///
+    /// 处理函数（尤其是模板渲染）中的意外 panic 只影响本次请求，返回 500 而不是让连接任务崩溃。
+    async fn run_handler(
+        req: &Request<Body>,
+        endpoint: Endpoint,
+        state: &ServerState,
+        handler: impl Future<Output = std::result::Result<Response<Body>, Infallible>>,
     ) -> std::result::Result<Response<Body>, Infallible> {
-        // 添加连接信息头部，便于调试
+        let start = Instant::now();
+        let response = match CatchUnwind::new(handler).await {
+            Ok(response) => response,
+            Err(payload) => {
+                error!(
+                    "处理请求 {} {} 时发生 panic: {}",
+                    req.method(),
+                    req.uri().path(),
+                    panic_message(payload.as_ref())
+                );
+                Ok(Self::serve_error(
+                    "服务器内部错误".to_string(),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ))
+            }
+        };
+        state.endpoint_metrics.record(endpoint, start.elapsed());
+        response.map(|mut response| {
+            state.frame_policy.apply(response.headers_mut());
+            response
+        })
+    }
+
+    /// 将请求匹配到端点
+    #[inline]
+    fn route(req: &Request<Body>) -> Endpoint {
         match (req.method(), req.uri().path()) {
-            (&Method::GET, "/") => {
-                match Self::serve_html(cache, cache_ttl_seconds).await {
+            (&Method::GET, "/") => Endpoint::Root,
+            (&Method::GET, "/health") => Endpoint::Health,
+            (&Method::GET, "/api/stats") => Endpoint::ApiStats,
+            (&Method::GET, "/metrics") => Endpoint::Metrics,
+            #[cfg(feature = "sqlite")]
+            (&Method::GET, "/api/history") => Endpoint::ApiHistory,
+            #[cfg(feature = "jemalloc")]
+            (&Method::GET, "/debug/allocator") => Endpoint::DebugAllocator,
+            _ => Endpoint::Other,
+        }
+    }
+
+    /// 调用端点对应的处理函数
+    async fn dispatch(
+        endpoint: Endpoint,
+        req: &Request<Body>,
+        cache: CacheRef,
+        cache_ttl_seconds: u64,
+        state: &ServerState,
+    ) -> std::result::Result<Response<Body>, Infallible> {
+        match endpoint {
+            Endpoint::Root => {
+                let accept_language = req
+                    .headers()
+                    .get(hyper::header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok());
+                let language = i18n::negotiate(req.uri().query(), accept_language, state.language);
+                match Self::serve_html(cache, cache_ttl_seconds, state.locale, language).await {
                     Ok(mut response) => {
                         // 添加缓存控制头，允许客户端在 TTL 秒内使用缓存
                         // 与 HTML meta refresh 和服务器缓存 TTL 保持一致，减少服务器负载
@@ -100,19 +192,54 @@ impl StatusServer {
                     )),
                 }
             }
-            (&Method::GET, "/health") => Ok(Self::serve_health()),
-            _ => Ok(Self::serve_404()),
+            Endpoint::Health => Ok(Self::serve_health(&cache)),
+            Endpoint::ApiStats => match Self::serve_json(cache).await {
+                Ok(response) => Ok(response),
+                Err(_) => Ok(Self::serve_error(
+                    "数据获取失败".to_string(),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )),
+            },
+            Endpoint::Metrics => match Self::serve_metrics(cache, cache_ttl_seconds, state).await {
+                Ok(response) => Ok(response),
+                Err(_) => Ok(Self::serve_error(
+                    "数据获取失败".to_string(),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )),
+            },
+            #[cfg(feature = "sqlite")]
+            Endpoint::ApiHistory => Ok(Self::serve_history(cache, req.uri().query()).await),
+            #[cfg(feature = "jemalloc")]
+            Endpoint::DebugAllocator => Ok(Self::serve_allocator()),
+            Endpoint::Other => Ok(Self::serve_404()),
         }
     }
 
-    /// 提供健康检查端点
-    #[inline]
-    fn serve_health() -> Response<Body> {
+    /// 提供健康检查端点，根据缓存刷新状态区分健康、降级与不可用
+    fn serve_health(cache: &CacheRef) -> Response<Body> {
+        let (status, body) = match cache.refresh_state() {
+            RefreshState::Failed => match cache.get_with_age() {
+                Some((_, age)) => (
+                    StatusCode::OK,
+                    format!(
+                        "DEGRADED: last refresh failed, serving data from {}s ago",
+                        age.as_secs()
+                    ),
+                ),
+                None => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "UNAVAILABLE: data collection failed".to_string(),
+                ),
+            },
+            RefreshState::Refreshing => (StatusCode::OK, "OK: refreshing".to_string()),
+            RefreshState::Empty | RefreshState::Fresh => (StatusCode::OK, "OK".to_string()),
+        };
+
         Response::builder()
-            .status(StatusCode::OK)
+            .status(status)
             .header("content-type", "text/plain")
             .header("Cache-Control", "no-cache")
-            .body(Body::from("OK"))
+            .body(Body::from(body))
             .unwrap()
     }
 
@@ -137,28 +264,200 @@ impl StatusServer {
             .unwrap()
     }
 
+    /// 提供 JSON 统计数据端点
+    async fn serve_json(cache: CacheRef) -> Result<Response<Body>> {
+        let stats = cache.get_or_update().await.inspect_err(log_collect_error)?;
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/json")
+            .header("Cache-Control", "no-cache")
+            .body(Body::from(serde_json::to_vec(&stats)?))
+            .unwrap())
+    }
+
+    /// 提供 Prometheus 指标端点
+    async fn serve_metrics(
+        cache: CacheRef,
+        cache_ttl_seconds: u64,
+        state: &ServerState,
+    ) -> Result<Response<Body>> {
+        let ttl = std::time::Duration::from_secs(cache_ttl_seconds);
+        if let Some(hint) = state.scrape_advisor.observe(Instant::now(), ttl) {
+            info!("{hint}");
+        }
+
+        // 刷新失败时退回上次成功的数据，使断路器等诊断指标在故障期间仍可抓取
+        let stats = match cache.get_or_update().await {
+            Ok(stats) => stats,
+            Err(e) => {
+                log_collect_error(&e);
+                match cache.get_with_age() {
+                    Some((stats, _)) => stats,
+                    None => return Err(e.into()),
+                }
+            }
+        };
+
+        let mut metrics = crate::metrics::render_metrics(&stats);
+        if let Some(timing) = cache.timing().snapshot() {
+            crate::metrics::render_timing_metrics(&mut metrics, &timing);
+        }
+        crate::metrics::render_scrape_metrics(
+            &mut metrics,
+            ttl,
+            state.scrape_advisor.observed_interval(),
+        );
+        #[cfg(feature = "jemalloc")]
+        match crate::allocator::read() {
+            Ok(allocator) => crate::metrics::render_allocator_metrics(&mut metrics, &allocator),
+            Err(e) => warn!("读取分配器统计失败: {e}"),
+        }
+        if let Some(breaker) = cache.breaker() {
+            crate::metrics::render_breaker_metrics(&mut metrics, &breaker.snapshot());
+        }
+        state.endpoint_metrics.render(&mut metrics);
+        state.connection_metrics.render(&mut metrics);
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "text/plain; version=0.0.4; charset=utf-8")
+            .header("Cache-Control", "no-cache")
+            .body(Body::from(metrics))
+            .unwrap())
+    }
+
+    /// 提供采集历史查询端点 `/api/history?from=..&to=..`（Unix 毫秒时间戳）
+    ///
+    /// 结果可能较大，以 chunked 方式分批发送，避免整体拼接后才开始响应
+    #[cfg(feature = "sqlite")]
+    async fn serve_history(cache: CacheRef, query: Option<&str>) -> Response<Body> {
+        let Some(history) = cache.history() else {
+            return Self::serve_404();
+        };
+        let (from, to) = match parse_history_range(query) {
+            Ok(range) => range,
+            Err(message) => return Self::serve_error(message, StatusCode::BAD_REQUEST),
+        };
+        let records = match history.query(from, to).await {
+            Ok(records) => records,
+            Err(e) => {
+                error!("查询采集历史失败: {e}");
+                return Self::serve_error(
+                    "历史查询失败".to_string(),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                );
+            }
+        };
+
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            if sender.send_data("[".into()).await.is_err() {
+                return;
+            }
+            for (i, chunk) in records.chunks(HISTORY_CHUNK_RECORDS).enumerate() {
+                let mut buf = Vec::with_capacity(chunk.len() * 256);
+                for (j, record) in chunk.iter().enumerate() {
+                    if i > 0 || j > 0 {
+                        buf.push(b',');
+                    }
+                    if serde_json::to_writer(&mut buf, record).is_err() {
+                        sender.abort();
+                        return;
+                    }
+                }
+                // 客户端已断开时停止发送
+                if sender.send_data(buf.into()).await.is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send_data("]".into()).await;
+        });
+
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/json")
+            .header("Cache-Control", "no-cache")
+            .body(body)
+            .unwrap()
+    }
+
+    /// 提供监控进程自身的分配器统计端点 `/debug/allocator`
+    #[cfg(feature = "jemalloc")]
+    fn serve_allocator() -> Response<Body> {
+        let stats = match crate::allocator::read() {
+            Ok(stats) => stats,
+            Err(e) => {
+                error!("读取分配器统计失败: {e}");
+                return Self::serve_error(
+                    "分配器统计读取失败".to_string(),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                );
+            }
+        };
+        match serde_json::to_vec(&stats) {
+            Ok(body) => Response::builder()
+                .status(StatusCode::OK)
+                .header("content-type", "application/json")
+                .header("Cache-Control", "no-cache")
+                .body(Body::from(body))
+                .unwrap(),
+            Err(e) => Self::serve_error(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
     /// 提供主页面
-    async fn serve_html(cache: CacheRef, cache_ttl_seconds: u64) -> Result<Response<Body>> {
-        // 获取系统数据
-        let stats = cache.get_or_update().await.map_err(|e| {
-            error!("获取系统数据失败: {e}");
-            e
-        })?;
+    async fn serve_html(
+        cache: CacheRef,
+        cache_ttl_seconds: u64,
+        locale: Locale,
+        language: Language,
+    ) -> Result<Response<Body>> {
+        // 获取系统数据，刷新失败时退回上次成功的数据
+        let stats = match cache.get_or_update().await {
+            Ok(stats) => stats,
+            Err(e) => {
+                log_collect_error(&e);
+                match cache.get_with_age() {
+                    Some((stats, _)) => stats,
+                    None => return Err(e.into()),
+                }
+            }
+        };
 
         // 渲染 HTML 模板
-        let html = Self::render_html_template(&stats, cache_ttl_seconds);
+        let context = RenderContext {
+            refresh_state: Some(cache.refresh_state()),
+            locale,
+            language,
+        };
+        let html = Self::render_html_template_with(&stats, cache_ttl_seconds, &context);
 
         Ok(Response::builder()
             .status(StatusCode::OK)
             .header("content-type", "text/html; charset=utf-8")
+            .header("Content-Language", language.tag())
+            // 同一 URL 按请求头返回不同语言，共享缓存需按该头区分
+            .header("Vary", "Accept-Language")
             .body(Body::from(html))
             .unwrap())
     }
 
     /// 渲染 HTML 模板
+    #[inline]
+    #[allow(dead_code)] // 库 API，供基准测试与外部调用使用
     pub fn render_html_template(
         stats: &crate::stats::SystemStats,
         cache_ttl_seconds: u64,
+    ) -> String {
+        Self::render_html_template_with(stats, cache_ttl_seconds, &RenderContext::default())
+    }
+
+    /// 携带渲染上下文渲染 HTML 模板
+    pub fn render_html_template_with(
+        stats: &crate::stats::SystemStats,
+        cache_ttl_seconds: u64,
+        context: &RenderContext,
     ) -> String {
         let total_mb = stats.memory_total / 1024 / 1024;
         let used_mb = stats.memory_used / 1024 / 1024;
@@ -166,31 +465,139 @@ impl StatusServer {
         let cached_mb = stats.memory_cached / 1024 / 1024;
         let free_mb = stats.memory_free / 1024 / 1024;
 
+        // 展示文本按区域格式化并自动换算单位，进度条的 value/max 保持裸数字
+        let locale = context.locale;
+        let language = context.language;
+        let messages = language.messages();
+        let separator = messages.separator;
+        let memory_total = locale.format_bytes(stats.memory_total);
+        let memory_used = locale.format_bytes(stats.memory_used);
+        let memory_available = locale.format_bytes(stats.memory_available);
+        let memory_cached = locale.format_bytes(stats.memory_cached);
+        let memory_free = locale.format_bytes(stats.memory_free);
+
         let cpu_percent = (stats.cpu_usage * 100.0) as u32;
         let cpu_user_percent = stats.cpu_stats.overall.user_percent as u32;
         let cpu_system_percent = stats.cpu_stats.overall.system_percent as u32;
         let cpu_nice_percent = stats.cpu_stats.overall.nice_percent as u32;
 
-        // 生成多核 CPU 部分
-        let cpu_cores_section = if stats.cpu_stats.core_count > 0 {
-            let mut cores_html = String::from("<fieldset><legend>处理器 - 各核心使用率</legend>");
-            for (i, core_stats) in stats.cpu_stats.per_core.iter().enumerate() {
+        // 生成多核 CPU 部分：每核心一张紧凑卡片（编号 + 迷你条 + 百分比），由模板中的 CSS 排成响应式网格
+        let cpu_cores_section = if !stats.cpu_stats.per_core.is_empty() {
+            let cores = &stats.cpu_stats.per_core;
+            let mut cores_html = String::with_capacity(96 + cores.len() * 192);
+            cores_html.push_str(&format!(
+                "<fieldset><legend>{}</legend><div class=\"cores\">",
+                messages.cores_legend
+            ));
+            for (index, core_stats) in cores.iter().enumerate() {
+                let i = stats.cpu_stats.core_id(index);
+                let percent = core_stats.total_percent as u32;
                 cores_html.push_str(&format!(
-                    "<p>核心 {}：<progress title=\"{}%\" value=\"{}\" max=\"100\">{}%</progress></p>",
-                    i, core_stats.total_percent as u32, core_stats.total_percent as u32, core_stats.total_percent as u32
+                    "<div class=\"core\" title=\"{core_title}{i}{separator}{percent}%\"><span class=\"core-id\">{i}</span><progress value=\"{percent}\" max=\"100\">{percent}%</progress><span class=\"core-percent\">{percent}%</span></div>",
+                    core_title = messages.core_title,
                 ));
             }
-            cores_html.push_str("</fieldset>");
+            cores_html.push_str("</div></fieldset>");
             cores_html
         } else {
             String::new()
         };
 
+        // 生成 conntrack 部分（未加载 conntrack 模块时不显示）
+        let conntrack_section = match &stats.conntrack {
+            Some(conntrack) => {
+                let warning = if conntrack.usage_percent >= crate::stats::CONNTRACK_WARN_PERCENT {
+                    format!("<p><strong>{}</strong></p>", messages.conntrack_warning)
+                } else {
+                    String::new()
+                };
+                format!(
+                    "<fieldset><legend>{legend}</legend><p>{used}<progress title=\"{count_text}/{max_text}\" value=\"{count}\" max=\"{max}\">{count_text}/{max_text}</progress></p>{warning}</fieldset>",
+                    legend = messages.conntrack_legend,
+                    used = messages.conntrack_used,
+                    count = conntrack.count,
+                    max = conntrack.max,
+                    count_text = locale.format_integer(conntrack.count),
+                    max_text = locale.format_integer(conntrack.max),
+                )
+            }
+            None => String::new(),
+        };
+
+        // 生成自定义指标部分
+        let custom_section = if stats.custom.is_empty() {
+            String::new()
+        } else {
+            let mut custom_html = format!("<fieldset><legend>{}</legend>", messages.custom_legend);
+            for (key, value) in &stats.custom {
+                custom_html.push_str(&format!(
+                    "<p>{key}{separator}{}</p>",
+                    locale.format_number(*value)
+                ));
+            }
+            custom_html.push_str("</fieldset>");
+            custom_html
+        };
+
+        // 时钟未同步时提示
+        let clock_section = if stats.clock_synced {
+            String::new()
+        } else {
+            format!("<p><strong>{}</strong></p>", messages.clock_warning)
+        };
+
+        // 明显偏离近期水平的指标，放在页面顶部提示
+        let anomalies_section = if stats.anomalies.is_empty() {
+            String::new()
+        } else {
+            let percent = |value: f64| locale.format_number((value * 10.0).round() / 10.0);
+            let mut html = format!("<fieldset><legend>{}</legend>", messages.anomalies_legend);
+            for (name, anomaly) in &stats.anomalies {
+                html.push_str(&format!(
+                    "<p><strong>{}{separator}{}</strong></p>",
+                    language.anomaly_label(name),
+                    language.anomaly_detail(
+                        &percent(anomaly.value),
+                        &percent(anomaly.mean),
+                        &percent(anomaly.stddev)
+                    )
+                ));
+            }
+            html.push_str("</fieldset>");
+            html
+        };
+
+        // 各采集子系统的状态，说明页面上为什么缺少某些区块
+        let subsystems_section = if stats.subsystems.is_empty() {
+            String::new()
+        } else {
+            let mut html = format!("<fieldset><legend>{}</legend>", messages.subsystems_legend);
+            for (name, status) in &stats.subsystems {
+                html.push_str(&format!(
+                    "<p>{}{separator}{}</p>",
+                    language.subsystem_label(name),
+                    language.subsystem_status(*status)
+                ));
+            }
+            html.push_str("</fieldset>");
+            html
+        };
+
+        // 生成数据状态部分
+        let refresh_state_section = match context.refresh_state {
+            Some(state) => format!(
+                "<p>{}{}</p>",
+                messages.refresh_state_label,
+                language.refresh_state(state)
+            ),
+            None => String::new(),
+        };
+
         // 格式化时间戳为可读格式
         let timestamp = format!("{:?}", stats.timestamp);
 
-        // 使用内置模板（编译进二进制文件）
-        let template = include_str!("../templates/index.html");
+        // 使用对应语言的内置模板（编译进二进制文件）
+        let template = language.template();
 
         // 使用 String::with_capacity 预分配容量，减少重新分配
         let mut result = String::with_capacity(template.len() + 512);
@@ -203,25 +610,219 @@ impl StatusServer {
         result = result.replace("{cpu_system_percent}", &cpu_system_percent.to_string());
         result = result.replace("{cpu_nice_percent}", &cpu_nice_percent.to_string());
         result = result.replace("{cpu_cores_section}", &cpu_cores_section);
+        result = result.replace("{memory_total}", &memory_total);
+        result = result.replace("{memory_used}", &memory_used);
+        result = result.replace("{memory_available}", &memory_available);
+        result = result.replace("{memory_cached}", &memory_cached);
+        result = result.replace("{memory_free}", &memory_free);
         result = result.replace("{memory_total_mb}", &total_mb.to_string());
         result = result.replace("{memory_used_mb}", &used_mb.to_string());
         result = result.replace("{memory_available_mb}", &available_mb.to_string());
         result = result.replace("{memory_cached_mb}", &cached_mb.to_string());
         result = result.replace("{memory_free_mb}", &free_mb.to_string());
+        result = result.replace("{conntrack_section}", &conntrack_section);
+        result = result.replace("{custom_section}", &custom_section);
         result = result.replace("{timestamp}", &timestamp);
+        result = result.replace("{refresh_state_section}", &refresh_state_section);
+        result = result.replace("{clock_section}", &clock_section);
+        result = result.replace("{subsystems_section}", &subsystems_section);
+        result = result.replace("{anomalies_section}", &anomalies_section);
         result = result.replace("{ttl}", &cache_ttl_seconds.to_string());
 
         result
     }
 }
 
+/// 已绑定监听地址、尚未开始服务的状态服务器
+pub struct BoundServer {
+    server: StatusServer,
+    incoming: MultiIncoming,
+}
+
+impl BoundServer {
+    /// 实际监听的地址（绑定端口 0 时为系统分配的端口）
+    #[inline]
+    #[allow(dead_code)] // 库 API，二进制通过 run 直接服务
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.incoming.local_addrs()
+    }
+
+    /// 开始接受连接并处理请求，直到服务器出错
+    pub async fn serve(self) -> Result<()> {
+        let cache = self.server.cache.clone();
+        let cache_ttl_seconds = self.server.cache_ttl_seconds;
+        let state = Arc::new(ServerState {
+            endpoint_metrics: EndpointMetrics::default(),
+            connection_metrics: ConnectionMetrics::default(),
+            scrape_advisor: ScrapeAdvisor::default(),
+            locale: self.server.locale,
+            language: self.server.language,
+            frame_policy: self.server.frame_policy,
+        });
+
+        let make_svc = make_service_fn(move |_conn| {
+            let cache = cache.clone();
+            let cache_ttl_seconds = cache_ttl_seconds;
+            let state = state.clone();
+            // 每个连接一个服务实例，在此统计新建连接与该连接已处理的请求数
+            state.connection_metrics.record_connection();
+            let served = Arc::new(AtomicU64::new(0));
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let cache = cache.clone();
+                    let cache_ttl_seconds = cache_ttl_seconds;
+                    let state = state.clone();
+                    let served_before = served.fetch_add(1, Ordering::Relaxed);
+                    state.connection_metrics.record_request(served_before);
+                    StatusServer::handle_request(req, cache, cache_ttl_seconds, state)
+                }))
+            }
+        });
+
+        let local_addrs = self.incoming.local_addrs();
+
+        // 创建服务器并配置高并发参数
+        let server = Server::builder(self.incoming)
+            .http1_keepalive(true)
+            .http1_half_close(false)
+            .serve(make_svc);
+
+        for addr in &local_addrs {
+            info!("服务器运行在: http://{addr}");
+        }
+        info!("已启用高并发模式，支持 HTTP/1.1 keep-alive");
+
+        server.await.map_err(|e| {
+            error!("服务器错误: {e}");
+            anyhow::anyhow!("服务器运行错误: {e}")
+        })?;
+
+        info!("服务器正常关闭");
+        Ok(())
+    }
+}
+
+/// 记录采集失败；断路器打开期间被拒绝的采集只记调试日志，避免每个请求刷屏
+fn log_collect_error(e: &StatsError) {
+    match e {
+        StatsError::CircuitOpen { .. } => debug!("跳过采集: {e}"),
+        _ => error!("获取系统数据失败: {e}"),
+    }
+}
+
+/// 捕获内部 future 轮询期间 panic 的包装，panic 时以 `Err(payload)` 结束
+///
+/// 请求间共享的互斥锁（断路器、CPU 基准、采样器、异常检测等）加锁时都从中毒状态恢复，
+/// 刷新状态与断路器的试探许可在 panic 展开时由 drop 复位，因此一次 panic 不会让后续请求
+/// 跟着失败，以 `AssertUnwindSafe` 包装。panic 发生时正在进行的那次更新可能只完成一半
+/// （如 CPU 基准已替换而结果未写入缓存），由下一次采集覆盖。
+struct CatchUnwind<F> {
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> CatchUnwind<F> {
+    #[inline]
+    fn new(inner: F) -> Self {
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let inner = self.inner.as_mut();
+        match std::panic::catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// 取出 panic 负载中的消息，非字符串负载给出占位文字
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<非字符串 panic 负载>")
+}
+
+/// 监听地址绑定失败
+#[derive(Debug)]
+pub struct BindError {
+    pub addr: SocketAddr,
+    pub source: std::io::Error,
+}
+
+impl std::fmt::Display for BindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let port = self.addr.port();
+        match self.source.kind() {
+            std::io::ErrorKind::AddrInUse => write!(
+                f,
+                "端口 {port} 已被占用，请通过 --port 换用其他端口，或停止占用该端口的进程（可用 `ss -ltnp | grep :{port}` 查看）"
+            ),
+            std::io::ErrorKind::PermissionDenied if port < 1024 => write!(
+                f,
+                "端口 {port} 需要 root 权限（或 CAP_NET_BIND_SERVICE 能力），请以 root 运行或通过 --port 换用 1024 以上的端口"
+            ),
+            std::io::ErrorKind::PermissionDenied => {
+                write!(f, "没有权限绑定 {}，请检查系统安全策略", self.addr)
+            }
+            std::io::ErrorKind::AddrNotAvailable => write!(
+                f,
+                "地址 {} 不属于本机任何网络接口，请通过 --address 指定本机地址",
+                self.addr.ip()
+            ),
+            _ => write!(f, "无法绑定 {}: {}", self.addr, self.source),
+        }
+    }
+}
+
+impl std::error::Error for BindError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// 历史查询响应每个数据块包含的记录数
+#[cfg(feature = "sqlite")]
+const HISTORY_CHUNK_RECORDS: usize = 500;
+
+/// 解析历史查询的 `from`/`to` 参数，缺省时分别为最早与最晚
+#[cfg(feature = "sqlite")]
+fn parse_history_range(query: Option<&str>) -> std::result::Result<(u64, u64), String> {
+    let (mut from, mut to) = (0, u64::MAX);
+    for pair in query.unwrap_or_default().split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let target = match key {
+            "from" => &mut from,
+            "to" => &mut to,
+            _ => continue,
+        };
+        *target = value
+            .parse()
+            .map_err(|_| format!("参数 {key} 必须是 Unix 毫秒时间戳"))?;
+    }
+    if from > to {
+        return Err("参数 from 不能大于 to".to_string());
+    }
+    Ok((from, to))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cache::create_cache;
-    use crate::stats::SystemStats;
+    use crate::stats::{
+        SUBSYSTEM_CONNTRACK, SUBSYSTEM_CUSTOM, SUBSYSTEM_PER_CORE, SubsystemStatus, SystemStats,
+    };
     use hyper::{Body, Request, StatusCode};
-    use std::time::Instant;
+    use std::time::{Duration, Instant};
 
     fn create_test_stats(hostname: &str, cpu_usage: f32) -> SystemStats {
         SystemStats {
@@ -236,6 +837,7 @@ mod tests {
                 },
                 per_core: Vec::new(),
                 core_count: 0,
+                core_ids: None,
             },
             memory_total: 1024 * 1024 * 1024,    // 1GB
             memory_used: 512 * 1024 * 1024,      // 512MB
@@ -243,59 +845,202 @@ mod tests {
             memory_cached: 128 * 1024 * 1024,    // 128MB
             memory_free: 128 * 1024 * 1024,      // 128MB
             timestamp: Instant::now(),
+            ..SystemStats::default()
         }
     }
 
+    #[tokio::test]
+    async fn test_catch_unwind() {
+        assert_eq!(CatchUnwind::new(async { 42 }).await.unwrap(), 42);
+
+        let payload = CatchUnwind::new(async {
+            tokio::task::yield_now().await;
+            panic!("模板渲染失败");
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "模板渲染失败");
+
+        let payload = CatchUnwind::new(async { panic!("第 {} 个占位符", 3) })
+            .await
+            .unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "第 3 个占位符");
+
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(7)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "<非字符串 panic 负载>");
+    }
+
     #[tokio::test]
     async fn test_config_default() {
         let config = Config::default();
-        assert_eq!(config.bind_address, "::");
+        assert_eq!(config.bind_addresses, ["::"]);
         assert_eq!(config.port, 8080);
         assert_eq!(config.cache_ttl_seconds, 10);
     }
 
-    #[tokio::test]
-    async fn test_config_address_ipv4() {
-        let config = Config {
-            bind_address: "0.0.0.0".to_string(),
-            port: 8080,
-            cache_ttl_seconds: 10,
-        };
-        let addr = config.address();
+    #[test]
+    fn test_parse_bind_address_ipv4() {
+        let addr = parse_bind_address("0.0.0.0", 8080).unwrap();
         assert_eq!(addr.to_string(), "0.0.0.0:8080");
     }
 
-    #[tokio::test]
-    async fn test_config_address_ipv6() {
+    #[test]
+    fn test_parse_bind_address_ipv6() {
+        let addr = parse_bind_address("::", 8080).unwrap();
+        assert_eq!(addr.to_string(), "[::]:8080");
+    }
+
+    #[test]
+    fn test_parse_bind_address_ipv6_specific() {
+        let addr = parse_bind_address("2001:db8::1", 9090).unwrap();
+        assert_eq!(addr.to_string(), "[2001:db8::1]:9090");
+    }
+
+    #[test]
+    fn test_parse_bind_address_ipv6_with_brackets() {
+        let addr = parse_bind_address("[::1]", 8080).unwrap();
+        assert_eq!(addr.to_string(), "[::1]:8080");
+    }
+
+    #[test]
+    fn test_parse_bind_address_with_port() {
+        let addr = |address| parse_bind_address(address, 8080).unwrap().to_string();
+        assert_eq!(addr("127.0.0.1:9090"), "127.0.0.1:9090");
+        assert_eq!(addr("[::1]:9090"), "[::1]:9090");
+        assert_eq!(addr("[fe80::1%3]:9090"), "[fe80::1%3]:9090");
+        assert!(parse_bind_address("127.0.0.1:http", 8080).is_err());
+        assert!(parse_bind_address("[::1]:99999", 8080).is_err());
+    }
+
+    #[test]
+    fn test_config_addresses() {
         let config = Config {
-            bind_address: "::".to_string(),
+            bind_addresses: vec!["127.0.0.1".to_string(), "[::1]:9090".to_string()],
             port: 8080,
-            cache_ttl_seconds: 10,
+            ..Config::default()
         };
-        let addr = config.address();
-        assert_eq!(addr.to_string(), "[::]:8080");
-    }
+        let addrs: Vec<String> = config
+            .addresses()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(addrs, ["127.0.0.1:8080", "[::1]:9090"]);
 
-    #[tokio::test]
-    async fn test_config_address_ipv6_specific() {
         let config = Config {
-            bind_address: "2001:db8::1".to_string(),
-            port: 9090,
-            cache_ttl_seconds: 10,
+            bind_addresses: vec!["127.0.0.1".to_string(), "bogus".to_string()],
+            ..Config::default()
         };
-        let addr = config.address();
-        assert_eq!(addr.to_string(), "[2001:db8::1]:9090");
+        assert!(config.addresses().is_err());
+
+        let config = Config {
+            bind_addresses: Vec::new(),
+            ..Config::default()
+        };
+        assert!(config.addresses().is_err());
     }
 
-    #[tokio::test]
-    async fn test_config_address_ipv6_with_brackets() {
+    #[test]
+    fn test_parse_interface_keyword() {
+        let keyword = |address| parse_interface_keyword(address, 8080).unwrap();
+        assert_eq!(keyword("all-v4"), Some((InterfaceFamily::V4, 8080)));
+        assert_eq!(keyword("all-v6:9090"), Some((InterfaceFamily::V6, 9090)));
+        assert_eq!(keyword("all"), Some((InterfaceFamily::Any, 8080)));
+        assert_eq!(keyword("127.0.0.1:9090"), None);
+        assert_eq!(keyword("::"), None);
+        assert!(parse_interface_keyword("all:http", 8080).is_err());
+    }
+
+    #[test]
+    fn test_config_addresses_interface_keyword() {
         let config = Config {
-            bind_address: "[::1]".to_string(),
+            bind_addresses: vec!["127.0.0.1".to_string(), "all:9090".to_string()],
             port: 8080,
-            cache_ttl_seconds: 10,
+            ..Config::default()
         };
-        let addr = config.address();
-        assert_eq!(addr.to_string(), "[::1]:8080");
+        let expected =
+            select_interface_addresses(&interface_addresses().unwrap(), InterfaceFamily::Any, 9090);
+        match config.addresses() {
+            Ok(addrs) => {
+                assert_eq!(addrs[0].to_string(), "127.0.0.1:8080");
+                assert_eq!(addrs[1..], expected);
+                assert!(addrs[1..].iter().all(|addr| !addr.ip().is_loopback()));
+            }
+            // 只有回环网卡的环境中没有可展开的地址
+            Err(e) => {
+                assert!(expected.is_empty());
+                assert!(e.to_string().contains("all:9090"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_bind_address_ipv6_scope_id() {
+        let address = |bind_address: &str| parse_bind_address(bind_address, 8080);
+
+        let addr = address("fe80::1%3").unwrap();
+        let SocketAddr::V6(v6) = addr else {
+            panic!("应为 IPv6 地址");
+        };
+        assert_eq!(v6.scope_id(), 3);
+        assert_eq!(addr.to_string(), "[fe80::1%3]:8080");
+
+        // 回环接口总是存在，接口名被解析为其索引
+        let addr = address("[fe80::1%lo]").unwrap();
+        let SocketAddr::V6(v6) = addr else {
+            panic!("应为 IPv6 地址");
+        };
+        assert_ne!(v6.scope_id(), 0);
+
+        let error = address("fe80::1%no-such-iface0").unwrap_err();
+        assert!(error.to_string().contains("no-such-iface0"));
+        assert!(address("not-an-ip%lo").is_err());
+        assert!(address("999.1.1.1").is_err());
+    }
+
+    #[test]
+    fn test_bind_error_message() {
+        let error = |port, kind| BindError {
+            addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            source: std::io::Error::from(kind),
+        };
+
+        let message = error(8080, std::io::ErrorKind::AddrInUse).to_string();
+        assert!(message.contains("端口 8080 已被占用"));
+        let message = error(80, std::io::ErrorKind::PermissionDenied).to_string();
+        assert!(message.contains("端口 80 需要 root 权限"));
+        let message = error(8080, std::io::ErrorKind::PermissionDenied).to_string();
+        assert!(message.contains("没有权限绑定 127.0.0.1:8080"));
+        let message = error(8080, std::io::ErrorKind::AddrNotAvailable).to_string();
+        assert!(message.contains("地址 127.0.0.1 不属于本机"));
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_port_in_use() {
+        let occupied = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = occupied.local_addr().unwrap();
+
+        let server = StatusServer::new_with_ttl(create_cache(10), 10);
+        let error = server.run(&[addr]).await.unwrap_err();
+        let bind_error = error.downcast_ref::<BindError>().unwrap();
+        assert_eq!(bind_error.source.kind(), std::io::ErrorKind::AddrInUse);
+    }
+
+    #[tokio::test]
+    async fn test_run_multiple_addresses() {
+        let addrs: Vec<SocketAddr> = vec!["127.0.0.1:0".parse().unwrap(); 2];
+        let server = StatusServer::new_with_ttl(create_cache(10), 10)
+            .bind(&addrs)
+            .unwrap();
+        // 端口在 bind 时已由服务器持有，开始服务前即可连接
+        let local_addrs = server.local_addrs();
+        let handle = tokio::spawn(server.serve());
+
+        for addr in local_addrs {
+            let result = crate::healthcheck::probe(addr, crate::healthcheck::PROBE_TIMEOUT).await;
+            assert!(result.is_ok(), "{addr}: {result:?}");
+        }
+        handle.abort();
     }
 
     #[tokio::test]
@@ -307,7 +1052,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_serve_health() {
-        let response = StatusServer::serve_health();
+        let cache = create_cache(10);
+        let response = StatusServer::serve_health(&cache);
         assert_eq!(response.status(), StatusCode::OK);
 
         let headers = response.headers();
@@ -317,6 +1063,25 @@ mod tests {
         assert_eq!(std::str::from_utf8(&body).unwrap(), "OK");
     }
 
+    #[tokio::test]
+    async fn test_serve_health_failed_refresh() {
+        // 从未成功采集且刷新失败：不可用
+        let cache = std::sync::Arc::new(crate::cache::SystemStatsCache::new(
+            std::time::Duration::from_secs(10),
+        ));
+        cache.mark_refresh_failed();
+        let response = StatusServer::serve_health(&cache);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // 有旧数据时降级但仍可用
+        cache.update(create_test_stats("health", 0.1));
+        cache.mark_refresh_failed();
+        let response = StatusServer::serve_health(&cache);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(std::str::from_utf8(&body).unwrap().starts_with("DEGRADED"));
+    }
+
     #[tokio::test]
     async fn test_serve_404() {
         let response = StatusServer::serve_404();
@@ -369,6 +1134,24 @@ mod tests {
         assert!(html.contains("低优先级"));
     }
 
+    #[tokio::test]
+    async fn test_render_html_template_cores_grid() {
+        let mut stats = create_test_stats("test", 0.5);
+        stats.cpu_stats.per_core = (0..128)
+            .map(|i| crate::stats::CpuUsageBreakdown {
+                total_percent: (i % 100) as f32,
+                ..Default::default()
+            })
+            .collect();
+        let html = StatusServer::render_html_template(&stats, 10);
+
+        assert_eq!(html.matches("<div class=\"cores\">").count(), 1);
+        assert_eq!(html.matches("<div class=\"core\"").count(), 128);
+        assert!(html.contains(
+            "<div class=\"core\" title=\"核心 127：27%\"><span class=\"core-id\">127</span><progress value=\"27\" max=\"100\">27%</progress><span class=\"core-percent\">27%</span></div>"
+        ));
+    }
+
     #[tokio::test]
     async fn test_render_html_template_special_chars() {
         let stats = create_test_stats("主机<>&\"'", 0.5);
@@ -406,6 +1189,7 @@ mod tests {
                     },
                 ],
                 core_count: 2,
+                core_ids: None,
             },
             memory_total: 2048 * 1024 * 1024,    // 2GB
             memory_used: 1024 * 1024 * 1024,     // 1GB
@@ -413,6 +1197,7 @@ mod tests {
             memory_cached: 256 * 1024 * 1024,    // 256MB
             memory_free: 256 * 1024 * 1024,      // 256MB
             timestamp: Instant::now(),
+            ..SystemStats::default()
         };
 
         let html = StatusServer::render_html_template(&stats, 10);
@@ -425,6 +1210,331 @@ mod tests {
         assert!(html.contains("256")); // 空闲内存 256MB
     }
 
+    #[tokio::test]
+    async fn test_render_html_template_locale() {
+        let mut stats = create_test_stats("test", 0.5);
+        stats.memory_total = 1536 * 1024 * 1024;
+        stats.memory_used = 512 * 1024 * 1024;
+        stats.custom.insert("requests".to_string(), 1234.5);
+
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(html.contains("512 MiB/1.5 GiB"));
+        assert!(html.contains("requests：1,234.5"));
+        // 进度条属性保持裸数字
+        assert!(html.contains("value=\"512\" max=\"1536\""));
+
+        let context = RenderContext {
+            locale: Locale::Dot,
+            ..RenderContext::default()
+        };
+        let html = StatusServer::render_html_template_with(&stats, 10, &context);
+        assert!(html.contains("512 MiB/1,5 GiB"));
+        assert!(html.contains("requests：1.234,5"));
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_conntrack() {
+        let mut stats = create_test_stats("test", 0.5);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(!html.contains("连接跟踪"));
+        assert!(!html.contains("{conntrack_section}"));
+
+        stats.conntrack = Some(crate::stats::ConntrackStats {
+            count: 62000,
+            max: 65536,
+            usage_percent: 94.6,
+        });
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(html.contains("连接跟踪"));
+        assert!(html.contains("62,000/65,536"));
+        assert!(html.contains("value=\"62000\" max=\"65536\""));
+        assert!(html.contains("即将用尽"));
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_clock() {
+        let mut stats = create_test_stats("test", 0.5);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(html.contains("系统时钟未同步"));
+        assert!(!html.contains("{clock_section}"));
+
+        stats.clock_synced = true;
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(!html.contains("系统时钟未同步"));
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_anomalies() {
+        let mut stats = create_test_stats("test", 0.5);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(!html.contains("异常指标"));
+        assert!(!html.contains("{anomalies_section}"));
+
+        stats.anomalies.insert(
+            crate::anomaly::METRIC_CPU_USAGE,
+            crate::stats::Anomaly {
+                value: 97.04,
+                mean: 12.46,
+                stddev: 3.0,
+            },
+        );
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(html.contains("异常指标"));
+        assert!(html.contains("CPU 使用率：97%（近期均值 12.5%，标准差 3）"));
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_subsystems() {
+        let mut stats = create_test_stats("test", 0.5);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(!html.contains("采集子系统"));
+        assert!(!html.contains("{subsystems_section}"));
+
+        stats.subsystems = std::collections::BTreeMap::from([
+            (SUBSYSTEM_PER_CORE, SubsystemStatus::Ok),
+            (SUBSYSTEM_CONNTRACK, SubsystemStatus::Failed),
+            (SUBSYSTEM_CUSTOM, SubsystemStatus::Disabled),
+        ]);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(html.contains("<legend>采集子系统</legend>"));
+        assert!(html.contains("<p>每核心 CPU：正常</p>"));
+        assert!(html.contains("<p>连接跟踪：已启用，采集失败</p>"));
+        assert!(html.contains("<p>自定义采集：未启用</p>"));
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_english() {
+        let mut stats = create_test_stats("test", 0.5);
+        stats.custom.insert("queue_length".to_string(), 42.0);
+        stats.subsystems =
+            std::collections::BTreeMap::from([(SUBSYSTEM_CONNTRACK, SubsystemStatus::Failed)]);
+        stats.clock_synced = false;
+        stats.cpu_stats.per_core = vec![stats.cpu_stats.overall.clone()];
+        let context = RenderContext {
+            refresh_state: Some(RefreshState::Fresh),
+            language: Language::En,
+            ..RenderContext::default()
+        };
+        let html = StatusServer::render_html_template_with(&stats, 10, &context);
+        assert!(html.contains("<legend>CPU - per-core usage</legend>"));
+        assert!(html.contains("title=\"Core 0: "));
+        assert!(html.contains("<p>queue_length: 42</p>"));
+        assert!(html.contains("<p>Connection tracking: enabled, collection failed</p>"));
+        assert!(html.contains("<p>Data status: fresh</p>"));
+        assert!(html.contains("the system clock is not synchronized"));
+        // 英文页面不应残留中文文字
+        assert!(!html.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)));
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_custom() {
+        let mut stats = create_test_stats("test", 0.5);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(!html.contains("自定义指标"));
+        assert!(!html.contains("{custom_section}"));
+
+        stats.custom.insert("queue_length".to_string(), 42.0);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(html.contains("自定义指标"));
+        assert!(html.contains("queue_length：42"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_metrics() {
+        let cache = create_cache(10);
+        let mut stats = create_test_stats("metrics", 0.5);
+        stats.custom.insert("queue_length".to_string(), 7.0);
+        cache.update(stats);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/metrics")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = StatusServer::handle_request(request, cache, 10, Arc::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let metrics = std::str::from_utf8(&body).unwrap();
+        assert!(metrics.contains("swb_cpu_usage_ratio 0.5"));
+        assert!(metrics.contains("swb_custom_queue_length 7"));
+        assert!(metrics.contains("swb_cache_ttl_seconds 10\n"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_metrics_circuit_open() {
+        use crate::breaker::CircuitBreaker;
+        use crate::stats::Collector;
+        let cache: CacheRef = Arc::new(
+            crate::SystemStatsCache::new(Duration::ZERO)
+                .with_collector(Collector::new("/nonexistent/proc"))
+                .with_circuit_breaker(CircuitBreaker::new(1, Duration::from_secs(60))),
+        );
+        cache.update(create_test_stats("stale", 0.5));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        // 第一次请求采集失败即打开断路器，第二次请求被熔断；两次都退回上次成功的数据
+        for _ in 0..2 {
+            let request = Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap();
+            let response = StatusServer::handle_request(request, cache.clone(), 10, Arc::default())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let metrics = std::str::from_utf8(&body).unwrap();
+            assert!(metrics.contains("swb_cpu_usage_ratio 0.5\n"));
+            assert!(metrics.contains("swb_collection_circuit_state 1\n"));
+            assert!(metrics.contains("swb_collection_circuit_trips_total 1\n"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_records_endpoint_metrics() {
+        let cache = create_cache(10);
+        cache.update(create_test_stats("endpoints", 0.5));
+        let state = Arc::new(ServerState::default());
+
+        for uri in ["/", "/health", "/health", "/notfound", "/metrics"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            StatusServer::handle_request(request, cache.clone(), 10, state.clone())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(state.endpoint_metrics.count(Endpoint::Root), 1);
+        assert_eq!(state.endpoint_metrics.count(Endpoint::Health), 2);
+        assert_eq!(state.endpoint_metrics.count(Endpoint::Other), 1);
+        // /metrics 渲染时自身尚未记录，渲染完成后才计入
+        assert_eq!(state.endpoint_metrics.count(Endpoint::Metrics), 1);
+        assert_eq!(state.endpoint_metrics.count(Endpoint::ApiStats), 0);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_parse_history_range() {
+        assert_eq!(parse_history_range(None), Ok((0, u64::MAX)));
+        assert_eq!(
+            parse_history_range(Some("from=1000&to=2000&x=y")),
+            Ok((1000, 2000))
+        );
+        assert_eq!(parse_history_range(Some("to=5")), Ok((0, 5)));
+        assert!(parse_history_range(Some("from=abc")).is_err());
+        assert!(parse_history_range(Some("from=2&to=1")).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_handle_request_api_history() {
+        use crate::cache::SystemStatsCache;
+        use crate::history::HistoryStore;
+        use std::time::Duration;
+
+        let request = || {
+            Request::builder()
+                .method("GET")
+                .uri("/api/history?from=0")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // 未启用历史时端点不存在
+        let response =
+            StatusServer::handle_request(request(), create_cache(10), 10, Arc::default())
+                .await
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let dir = std::env::temp_dir().join(format!("swb-history-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = HistoryStore::open(&dir.join("history.db"), Duration::from_secs(3600)).unwrap();
+        let cache = Arc::new(SystemStatsCache::new(Duration::from_secs(10)).with_history(store));
+        cache
+            .history()
+            .unwrap()
+            .record(&create_test_stats("history", 0.5));
+        // 写入在后台进行，等待其完成
+        for _ in 0..100 {
+            if !cache
+                .history()
+                .unwrap()
+                .query(0, u64::MAX)
+                .await
+                .unwrap()
+                .is_empty()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let response = StatusServer::handle_request(request(), cache, 10, Arc::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 1);
+        assert_eq!(value[0]["cpu_usage_ratio"], 0.5);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_render_html_template_refresh_state() {
+        let stats = create_test_stats("test", 0.5);
+        let html = StatusServer::render_html_template(&stats, 10);
+        assert!(!html.contains("数据状态"));
+        assert!(!html.contains("{refresh_state_section}"));
+
+        let context = RenderContext {
+            refresh_state: Some(RefreshState::Failed),
+            ..RenderContext::default()
+        };
+        let html = StatusServer::render_html_template_with(&stats, 10, &context);
+        assert!(html.contains("数据状态：刷新失败"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_api_stats() {
+        let cache = create_cache(10);
+        cache.update(create_test_stats("json", 0.5));
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/api/stats")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = StatusServer::handle_request(request, cache, 10, Arc::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["hostname"], "json");
+        assert_eq!(value["cpu_usage_ratio"], 0.5);
+        assert_eq!(value["memory_total_bytes"], 1024 * 1024 * 1024);
+    }
+
     #[tokio::test]
     async fn test_handle_request_root() {
         let cache = create_cache(10);
@@ -434,12 +1544,163 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response = StatusServer::handle_request(request, cache, 10)
+        let response = StatusServer::handle_request(request, cache, 10, Arc::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_root_language() {
+        let cache = create_cache(10);
+        cache.update(create_test_stats("test", 0.5));
+        let cases = [
+            ("/", None, "zh-CN", "资源占用"),
+            (
+                "/",
+                Some("fr;q=1, en-US;q=0.8, zh;q=0.5"),
+                "en",
+                "Resource usage",
+            ),
+            ("/?lang=zh", Some("en"), "zh-CN", "资源占用"),
+            ("/?lang=en", None, "en", "Resource usage"),
+        ];
+        for (uri, accept_language, tag, text) in cases {
+            let mut builder = Request::builder().method("GET").uri(uri);
+            if let Some(accept_language) = accept_language {
+                builder = builder.header("Accept-Language", accept_language);
+            }
+            let request = builder.body(Body::empty()).unwrap();
+            let response = StatusServer::handle_request(request, cache.clone(), 10, Arc::default())
+                .await
+                .unwrap();
+            assert_eq!(response.headers()["content-language"], tag, "{uri}");
+            assert_eq!(response.headers()["vary"], "Accept-Language");
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            assert!(std::str::from_utf8(&body).unwrap().contains(text), "{uri}");
+        }
+
+        // 请求未指定支持的语言时使用配置的默认语言
+        let state = Arc::new(ServerState {
+            language: Language::En,
+            ..ServerState::default()
+        });
+        let request = Request::builder()
+            .uri("/")
+            .header("Accept-Language", "fr, de")
+            .body(Body::empty())
+            .unwrap();
+        let response = StatusServer::handle_request(request, cache, 10, state)
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-language"], "en");
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_panic_returns_500() {
+        let cache = create_cache(10);
+        cache.update(create_test_stats("test", 0.5));
+        let state = Arc::new(ServerState::default());
+        let request = |uri| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        // 模拟处理函数中的意外 panic
+        let panicking = async { panic!("测试用 panic") };
+        let response = StatusServer::run_handler(&request("/"), Endpoint::Root, &state, panicking)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        // panic 的请求同样带上框架策略头
+        assert!(response.headers().contains_key("content-security-policy"));
+
+        let response = StatusServer::handle_request(request("/api/stats"), cache, 10, state)
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_handle_request_frame_policy() {
+        let cache = create_cache(10);
+        cache.update(create_test_stats("test", 0.5));
+        let cases = [
+            (vec![], "frame-ancestors 'self'", Some("SAMEORIGIN")),
+            (vec!["'none'"], "frame-ancestors 'none'", Some("DENY")),
+            (
+                vec!["'self'", "https://dashboard.example.com"],
+                "frame-ancestors 'self' https://dashboard.example.com",
+                None,
+            ),
+        ];
+        for (sources, policy, x_frame_options) in cases {
+            let sources: Vec<String> = sources.into_iter().map(String::from).collect();
+            let state = Arc::new(ServerState {
+                frame_policy: FramePolicy::new(&sources).unwrap(),
+                ..ServerState::default()
+            });
+            for uri in ["/", "/missing"] {
+                let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+                let response =
+                    StatusServer::handle_request(request, cache.clone(), 10, state.clone())
+                        .await
+                        .unwrap();
+                let headers = response.headers();
+                assert_eq!(headers["content-security-policy"], policy, "{uri}");
+                assert_eq!(
+                    headers
+                        .get("x-frame-options")
+                        .map(|value| value.to_str().unwrap()),
+                    x_frame_options,
+                    "{uri}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_policy_rejects_invalid_sources() {
+        for sources in [
+            vec!["https://a.example.com; script-src *"],
+            vec!["https://a.example.com https://b.example.com"],
+            vec![""],
+            vec!["'none'", "https://a.example.com"],
+        ] {
+            let sources: Vec<String> = sources.into_iter().map(String::from).collect();
+            assert!(FramePolicy::new(&sources).is_err(), "{sources:?}");
+        }
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[tokio::test]
+    async fn test_handle_request_debug_allocator() {
+        let request = Request::builder()
+            .uri("/debug/allocator")
+            .body(Body::empty())
+            .unwrap();
+        let response = StatusServer::handle_request(request, create_cache(10), 10, Arc::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        // 测试二进制同样以 jemalloc 为全局分配器，已分配字节数不为 0
+        assert!(value["allocated_bytes"].as_u64().unwrap() > 0);
+        assert!(value["resident_bytes"].is_u64());
+        assert!(value["fragmentation_ratio"].is_f64());
+    }
+
+    #[cfg(not(feature = "jemalloc"))]
+    #[tokio::test]
+    async fn test_handle_request_debug_allocator_disabled() {
+        let request = Request::builder()
+            .uri("/debug/allocator")
+            .body(Body::empty())
+            .unwrap();
+        let response = StatusServer::handle_request(request, create_cache(10), 10, Arc::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn test_handle_request_health() {
         let cache = create_cache(10);
@@ -449,7 +1710,7 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response = StatusServer::handle_request(request, cache, 10)
+        let response = StatusServer::handle_request(request, cache, 10, Arc::default())
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
@@ -467,7 +1728,7 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response = StatusServer::handle_request(request, cache, 10)
+        let response = StatusServer::handle_request(request, cache, 10, Arc::default())
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
@@ -482,7 +1743,7 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response = StatusServer::handle_request(request, cache, 10)
+        let response = StatusServer::handle_request(request, cache, 10, Arc::default())
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
@@ -503,7 +1764,7 @@ mod tests {
             .body(Body::empty())
             .unwrap();
 
-        let response = StatusServer::handle_request(request, cache.clone(), 10)
+        let response = StatusServer::handle_request(request, cache.clone(), 10, Arc::default())
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
@@ -517,15 +1778,175 @@ mod tests {
     }
 }
 
+/// 页面渲染时除统计数据以外的上下文
+#[derive(Debug, Clone, Default)]
+pub struct RenderContext {
+    /// 缓存刷新状态，`None` 时不显示
+    pub refresh_state: Option<RefreshState>,
+    /// 页面数字格式的区域
+    pub locale: Locale,
+    /// 页面语言
+    pub language: Language,
+}
+
+/// 服务器运行期间各请求共享的状态
+#[derive(Debug, Default)]
+pub struct ServerState {
+    /// 按端点统计的请求计数与延迟
+    pub endpoint_metrics: EndpointMetrics,
+    /// 连接复用统计
+    pub connection_metrics: ConnectionMetrics,
+    /// 根据 `/metrics` 抓取频率给出 scrape_interval 建议
+    pub scrape_advisor: ScrapeAdvisor,
+    /// 页面数字格式的区域
+    pub locale: Locale,
+    /// 请求未指定支持的语言时使用的页面语言
+    pub language: Language,
+    /// 允许以 iframe 嵌入页面的来源
+    pub frame_policy: FramePolicy,
+}
+
+/// 页面被 iframe 嵌入的限制，以 `Content-Security-Policy: frame-ancestors` 头表达
+///
+/// 默认只允许同源嵌入（`'self'`），同时给出 `X-Frame-Options: SAMEORIGIN` 兼容旧浏览器。
+/// 配置了允许的来源后只输出 CSP 头：`X-Frame-Options` 无法表达来源列表，
+/// 且同时存在时部分浏览器仍会按它拒绝跨域嵌入。
+#[derive(Debug, Clone)]
+pub struct FramePolicy {
+    content_security_policy: HeaderValue,
+    x_frame_options: Option<HeaderValue>,
+}
+
+impl Default for FramePolicy {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            content_security_policy: HeaderValue::from_static("frame-ancestors 'self'"),
+            x_frame_options: Some(HeaderValue::from_static("SAMEORIGIN")),
+        }
+    }
+}
+
+impl FramePolicy {
+    /// 由允许嵌入的来源列表创建，为空时等同默认策略
+    ///
+    /// 来源使用 CSP 的写法，如 `https://dashboard.example.com`、`https://*.example.com`、
+    /// `'self'`；`'none'` 表示禁止任何嵌入，不能与其他来源同时使用。
+    pub fn new(sources: &[String]) -> Result<Self> {
+        if sources.is_empty() {
+            return Ok(Self::default());
+        }
+        for source in sources {
+            if source.is_empty()
+                || !source
+                    .bytes()
+                    .all(|b| b.is_ascii_graphic() && b != b';' && b != b',')
+            {
+                anyhow::bail!("无效的 frame-ancestors 来源: {source:?}");
+            }
+        }
+        if sources.iter().any(|source| source == "'none'") {
+            if sources.len() > 1 {
+                anyhow::bail!("frame-ancestors 的 'none' 不能与其他来源同时使用");
+            }
+            return Ok(Self {
+                content_security_policy: HeaderValue::from_static("frame-ancestors 'none'"),
+                x_frame_options: Some(HeaderValue::from_static("DENY")),
+            });
+        }
+
+        let policy = format!("frame-ancestors {}", sources.join(" "));
+        Ok(Self {
+            content_security_policy: HeaderValue::from_str(&policy)
+                .with_context(|| format!("无效的 frame-ancestors 策略: {policy}"))?,
+            x_frame_options: None,
+        })
+    }
+
+    /// 为响应添加嵌入限制头
+    #[inline]
+    fn apply(&self, headers: &mut hyper::HeaderMap) {
+        headers.insert(
+            hyper::header::CONTENT_SECURITY_POLICY,
+            self.content_security_policy.clone(),
+        );
+        if let Some(x_frame_options) = &self.x_frame_options {
+            headers.insert(hyper::header::X_FRAME_OPTIONS, x_frame_options.clone());
+        }
+    }
+}
+
 /// 配置结构
 #[derive(Debug, Clone)]
 pub struct Config {
-    /// 服务端绑定地址（支持 IPv4 和 IPv6）
-    pub bind_address: String,
-    /// 服务端端口
+    /// 服务端绑定地址（支持 IPv4 和 IPv6），可带端口（`127.0.0.1:9090`、`[::1]:9090`）
+    pub bind_addresses: Vec<String>,
+    /// 未带端口的绑定地址使用的端口
     pub port: u16,
     /// 缓存 TTL（秒）
     pub cache_ttl_seconds: u64,
+    /// 异常值剔除阈值（百分点），`None` 表示不启用
+    pub outlier_threshold: Option<f32>,
+    /// 异常值剔除参考的最近样本数
+    pub outlier_window: usize,
+    /// 异常标注参考的最近采集次数，`None` 表示不启用
+    pub anomaly_window: Option<usize>,
+    /// 异常标注的标准差倍数
+    pub anomaly_sigma: f64,
+    /// 采集连续失败多少次后打开断路器（0 表示不启用）
+    pub breaker_threshold: u32,
+    /// 断路器打开后的冷却秒数
+    pub breaker_cooldown_seconds: u64,
+    /// 是否启用后台刷新（每个 TTL 周期主动采集，而非按需采集）
+    pub background_refresh: bool,
+    /// 后台刷新的采样时刻是否对齐到墙上时钟的整 TTL 边界
+    pub align_refresh: bool,
+    /// proc 文件系统根目录
+    pub proc_root: std::path::PathBuf,
+    /// 是否复用 /proc/stat 与 /proc/meminfo 的文件句柄
+    pub reuse_proc_fds: bool,
+    /// 本进程的 CPU 时间预算（占单个核心的百分比，`None` 表示不限制）
+    pub cpu_budget_percent: Option<f64>,
+    /// CPU 时间预算的滚动窗口秒数
+    pub cpu_budget_window_seconds: u64,
+    /// 独立 CPU 采样间隔毫秒数（`None` 表示不启用）
+    pub cpu_sample_interval_ms: Option<u64>,
+    /// 独立 CPU 采样的 EWMA 平滑系数
+    pub cpu_ewma_alpha: f32,
+    /// 只保留这些核心的每核心数据（`None` 表示全部）
+    pub cpu_cores: Option<Vec<usize>>,
+    /// 缓存中的每核心使用率是否以定点数紧凑保存
+    pub compact_cores: bool,
+    /// 外部采集命令，每个命令输出 `key=value` 行
+    #[cfg(feature = "custom")]
+    pub custom_commands: Vec<String>,
+    /// 单个外部采集命令的超时（毫秒）
+    #[cfg(feature = "custom")]
+    pub custom_timeout_ms: u64,
+    /// 单个外部采集命令的最大输出字节数
+    #[cfg(feature = "custom")]
+    pub custom_max_output_bytes: usize,
+    /// 页面数字格式的区域，决定千位分隔符与小数点
+    pub locale: Locale,
+    /// 页面默认语言
+    pub language: Language,
+    /// 允许以 iframe 嵌入页面的来源（CSP `frame-ancestors` 写法），为空时只允许同源嵌入
+    pub frame_ancestors: Vec<String>,
+    /// 采集历史 SQLite 数据库路径（`None` 表示不记录历史）
+    #[cfg(feature = "sqlite")]
+    pub history_db: Option<std::path::PathBuf>,
+    /// 采集历史保留小时数
+    #[cfg(feature = "sqlite")]
+    pub history_retention_hours: u64,
+    /// remote_write 接收端地址（`None` 表示不推送）
+    #[cfg(feature = "remote-write")]
+    pub remote_write_url: Option<String>,
+    /// remote_write 推送间隔（秒）
+    #[cfg(feature = "remote-write")]
+    pub remote_write_interval_seconds: u64,
+    /// remote_write 请求附加的请求头，格式为 `名称: 值`
+    #[cfg(feature = "remote-write")]
+    pub remote_write_headers: Vec<String>,
 }
 
 impl Default for Config {
@@ -533,24 +1954,150 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             // 使用 :: 作为默认地址，支持 IPv4 和 IPv6 双栈
-            bind_address: "::".to_string(),
+            bind_addresses: vec!["::".to_string()],
             port: 8080,
             cache_ttl_seconds: 10, // 严格 10 秒过期
+            outlier_threshold: None,
+            outlier_window: 5,
+            anomaly_window: None,
+            anomaly_sigma: 3.0,
+            breaker_threshold: 5,
+            breaker_cooldown_seconds: 30,
+            background_refresh: false,
+            align_refresh: false,
+            proc_root: std::path::PathBuf::from(crate::stats::DEFAULT_PROC_ROOT),
+            reuse_proc_fds: false,
+            cpu_budget_percent: None,
+            cpu_budget_window_seconds: 60,
+            cpu_sample_interval_ms: None,
+            cpu_ewma_alpha: 0.3,
+            cpu_cores: None,
+            compact_cores: false,
+            locale: Locale::default(),
+            language: Language::default(),
+            frame_ancestors: Vec::new(),
+            #[cfg(feature = "custom")]
+            custom_commands: Vec::new(),
+            #[cfg(feature = "custom")]
+            custom_timeout_ms: 2000,
+            #[cfg(feature = "custom")]
+            custom_max_output_bytes: 4096,
+            #[cfg(feature = "sqlite")]
+            history_db: None,
+            #[cfg(feature = "sqlite")]
+            history_retention_hours: 168,
+            #[cfg(feature = "remote-write")]
+            remote_write_url: None,
+            #[cfg(feature = "remote-write")]
+            remote_write_interval_seconds: 30,
+            #[cfg(feature = "remote-write")]
+            remote_write_headers: Vec::new(),
         }
     }
 }
 
 impl Config {
-    /// 构建服务器地址
-    #[inline]
-    pub fn address(&self) -> SocketAddr {
-        // 对于 IPv6 地址，需要用方括号包围
-        let addr_str = if self.bind_address.contains(':') && !self.bind_address.starts_with('[') {
-            format!("[{}]:{}", self.bind_address, self.port)
-        } else {
-            format!("{}:{}", self.bind_address, self.port)
-        };
+    /// 解析全部监听地址，至少需要一个
+    ///
+    /// `all-v4`/`all-v6`/`all`（可带 `:端口`）展开为本机网卡上对应的非回环地址。
+    pub fn addresses(&self) -> Result<Vec<SocketAddr>> {
+        if self.bind_addresses.is_empty() {
+            anyhow::bail!("未指定监听地址");
+        }
+        let mut interfaces = None;
+        let mut addrs = Vec::new();
+        for address in &self.bind_addresses {
+            let Some((family, port)) = parse_interface_keyword(address, self.port)? else {
+                addrs.push(parse_bind_address(address, self.port)?);
+                continue;
+            };
+            if interfaces.is_none() {
+                interfaces = Some(interface_addresses().context("枚举本机网卡地址失败")?);
+            }
+            let expanded =
+                select_interface_addresses(interfaces.as_deref().unwrap_or_default(), family, port);
+            if expanded.is_empty() {
+                anyhow::bail!("{address} 没有匹配的网卡地址");
+            }
+            addrs.extend(expanded);
+        }
+        Ok(addrs)
+    }
+}
+
+/// 识别网卡地址通配关键字（`all-v4`、`all-v6`、`all`，可带 `:端口`），返回地址族与端口
+fn parse_interface_keyword(
+    address: &str,
+    default_port: u16,
+) -> Result<Option<(InterfaceFamily, u16)>> {
+    let (keyword, port) = match address.split_once(':') {
+        Some((keyword, port)) => (keyword, Some(port)),
+        None => (address, None),
+    };
+    let Some(family) = InterfaceFamily::from_keyword(keyword) else {
+        return Ok(None);
+    };
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .map_err(|_| anyhow::anyhow!("无效的端口: {address}"))?,
+        None => default_port,
+    };
+    Ok(Some((family, port)))
+}
+
+/// 解析一个监听地址，未带端口时使用 `default_port`
+///
+/// IPv6 地址带端口时需要用方括号包围（`[::1]:9090`），不带方括号的 IPv6 地址视为不带端口。
+/// IPv6 链路本地地址可以带 scope id（如 `fe80::1%eth0` 或 `fe80::1%2`），
+/// 接口名会被解析为接口索引
+pub fn parse_bind_address(address: &str, default_port: u16) -> Result<SocketAddr> {
+    let invalid_port = || anyhow::anyhow!("无效的端口: {address}");
+    let (host, port) = if let Some((host, port)) = address
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("]:"))
+    {
+        (host, port.parse().map_err(|_| invalid_port())?)
+    } else if let Some(host) = address
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+    {
+        (host, default_port)
+    } else if let Some((host, port)) = address.split_once(':')
+        && !port.contains(':')
+    {
+        (host, port.parse().map_err(|_| invalid_port())?)
+    } else {
+        (address, default_port)
+    };
+
+    if let Some((ip, scope)) = host.split_once('%') {
+        let ip: std::net::Ipv6Addr = ip
+            .parse()
+            .map_err(|_| anyhow::anyhow!("无效的 IPv6 地址: {ip}"))?;
+        let scope_id = parse_scope_id(scope)?;
+        return Ok(SocketAddr::V6(std::net::SocketAddrV6::new(
+            ip, port, 0, scope_id,
+        )));
+    }
+
+    host.parse::<std::net::IpAddr>()
+        .map(|ip| SocketAddr::new(ip, port))
+        .map_err(|_| anyhow::anyhow!("无效的地址格式: {address}"))
+}
+
+/// 将 scope id 解析为接口索引：数字直接使用，否则按接口名查找
+fn parse_scope_id(scope: &str) -> Result<u32> {
+    if let Ok(index) = scope.parse::<u32>() {
+        return Ok(index);
+    }
 
-        addr_str.parse().expect("无效的地址格式")
+    let name =
+        std::ffi::CString::new(scope).map_err(|_| anyhow::anyhow!("无效的网络接口名: {scope}"))?;
+    // SAFETY: name 是以 NUL 结尾的有效 C 字符串，if_nametoindex 不会保留该指针
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if index == 0 {
+        anyhow::bail!("网络接口 {scope} 不存在，无法作为 IPv6 scope id");
     }
+    Ok(index)
 }
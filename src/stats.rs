@@ -1,7 +1,9 @@
-use std::time::Instant;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// 单个 CPU 核心的时间统计
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct CpuTimes {
     pub user: u64,   // 用户态时间
     pub nice: u64,   // 低优先级进程时间
@@ -16,8 +18,11 @@ pub struct CpuTimes {
     pub total: u64,  // 总时间
 }
 
+/// 带核心编号（/proc/stat 中 `cpuN` 的 N）的单核时间统计
+pub type CoreTimes = (usize, CpuTimes);
+
 /// CPU 使用率分解
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct CpuUsageBreakdown {
     pub user_percent: f32,   // 用户态使用率百分比
     pub nice_percent: f32,   // 低优先级进程使用率百分比
@@ -25,35 +30,241 @@ pub struct CpuUsageBreakdown {
     pub total_percent: f32,  // 总使用率百分比
 }
 
+/// 紧凑的 CPU 使用率分解：各百分比以 0-10000 的定点数表示 0-100.00%
+///
+/// 每项 8 字节，是 [`CpuUsageBreakdown`]（16 字节）的一半，精度为 0.01 个百分点，
+/// 供内存敏感的设备在缓存中保存大量核心的数据。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactCpuUsage {
+    pub user: u16,
+    pub nice: u16,
+    pub system: u16,
+    pub total: u16,
+}
+
+/// 定点数中 1 个百分点对应的值
+const COMPACT_PERCENT_SCALE: f32 = 100.0;
+
+impl CompactCpuUsage {
+    /// 由浮点百分比转换，超出 0-100 的值截断，NaN 记为 0
+    #[inline]
+    pub fn from_breakdown(usage: &CpuUsageBreakdown) -> Self {
+        let fixed =
+            |percent: f32| (percent.clamp(0.0, 100.0) * COMPACT_PERCENT_SCALE).round() as u16;
+        Self {
+            user: fixed(usage.user_percent),
+            nice: fixed(usage.nice_percent),
+            system: fixed(usage.system_percent),
+            total: fixed(usage.total_percent),
+        }
+    }
+
+    /// 还原为浮点百分比
+    #[inline]
+    pub fn to_breakdown(self) -> CpuUsageBreakdown {
+        let percent = |fixed: u16| f32::from(fixed) / COMPACT_PERCENT_SCALE;
+        CpuUsageBreakdown {
+            user_percent: percent(self.user),
+            nice_percent: percent(self.nice),
+            system_percent: percent(self.system),
+            total_percent: percent(self.total),
+        }
+    }
+}
+
 /// 多核 CPU 统计信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CpuStats {
     pub overall: CpuUsageBreakdown,       // 总体 CPU 使用率
     pub per_core: Vec<CpuUsageBreakdown>, // 每个 CPU 核心的使用率
     pub core_count: usize,                // CPU 核心数量
+    /// `per_core` 各项对应的核心编号，`None` 表示第 i 项即核心 i
+    ///
+    /// 经过筛选，或 /proc/stat 中的核心编号不连续（如部分核心离线）时给出。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub core_ids: Option<Vec<usize>>,
+}
+
+impl CpuStats {
+    /// `per_core` 第 `index` 项对应的核心编号
+    #[inline]
+    pub fn core_id(&self, index: usize) -> usize {
+        self.core_ids
+            .as_ref()
+            .and_then(|ids| ids.get(index).copied())
+            .unwrap_or(index)
+    }
+
+    /// 只保留 `cores` 中列出的核心（按编号升序，重复的编号只保留一次），返回不存在的核心编号
+    ///
+    /// 按核心编号（/proc/stat 中 `cpuN` 的 N）而非 `per_core` 下标筛选。
+    /// 总体使用率 `overall` 与 `core_count` 不受影响；未采集每核心数据时不做处理。
+    pub fn retain_cores(&mut self, cores: &[usize]) -> Vec<usize> {
+        if self.per_core.is_empty() {
+            return Vec::new();
+        }
+        let mut selected: Vec<usize> = cores.to_vec();
+        selected.sort_unstable();
+        selected.dedup();
+
+        let per_core = std::mem::take(&mut self.per_core);
+        let (ids, per_core): (Vec<usize>, Vec<CpuUsageBreakdown>) = per_core
+            .into_iter()
+            .enumerate()
+            .map(|(index, core)| (self.core_id(index), core))
+            .filter(|(id, _)| selected.binary_search(id).is_ok())
+            .unzip();
+        selected.retain(|core| !ids.contains(core));
+        self.per_core = per_core;
+        self.core_ids = Some(ids);
+        selected
+    }
+}
+
+/// conntrack 连接跟踪表使用情况
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConntrackStats {
+    pub count: u64,         // 当前跟踪的连接数
+    pub max: u64,           // 连接跟踪表上限
+    pub usage_percent: f32, // 使用率百分比
+}
+
+/// conntrack 使用率达到该百分比时告警
+pub const CONNTRACK_WARN_PERCENT: f32 = 90.0;
+
+/// 相对最近采集窗口明显异常的指标值（见 [`crate::anomaly`]）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    pub value: f64,  // 本次采集的值
+    pub mean: f64,   // 窗口内的均值
+    pub stddev: f64, // 窗口内的标准差
+}
+
+/// 可选采集子系统名称，用作 `SystemStats::subsystems` 的键
+pub const SUBSYSTEM_PER_CORE: &str = "per_core";
+pub const SUBSYSTEM_CONNTRACK: &str = "conntrack";
+pub const SUBSYSTEM_INTERRUPTS: &str = "interrupts";
+pub const SUBSYSTEM_CUSTOM: &str = "custom";
+
+/// 可选采集子系统的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubsystemStatus {
+    /// 已启用且本次采集成功
+    Ok,
+    /// 已启用但本次采集失败（权限不足、内核模块未加载、命令出错等）
+    Failed,
+    /// 未编译或未配置
+    Disabled,
+    /// 采集器超出 CPU 预算，本次跳过
+    Throttled,
 }
 
-use std::sync::Mutex;
+impl SubsystemStatus {
+    /// 未启用时为 `Disabled`，否则按 `succeeded` 的结果为 `Ok` 或 `Failed`
+    #[inline]
+    pub fn enabled_if(enabled: bool, succeeded: impl FnOnce() -> bool) -> Self {
+        if !enabled {
+            Self::Disabled
+        } else if succeeded() {
+            Self::Ok
+        } else {
+            Self::Failed
+        }
+    }
+
+    /// 页面展示用的状态描述
+    #[inline]
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Ok => "正常",
+            Self::Failed => "已启用，采集失败",
+            Self::Disabled => "未启用",
+            Self::Throttled => "超出 CPU 预算，本次跳过",
+        }
+    }
+}
+
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use crate::budget::CpuBudget;
+#[cfg(feature = "interrupts")]
+use crate::interrupts::{InterruptSampler, InterruptStats};
+use crate::proc_file::ProcFile;
 // 注意：AtomicU64 和 Ordering 导入暂时保留，为未来优化预留
 // #[allow(dead_code)] use std::sync::atomic::{AtomicU64, Ordering};
 
-/// 全局 CPU 时间缓存，用于增量计算
-static CPU_PREV_OVERALL: Mutex<Option<CpuTimes>> = Mutex::new(None);
-static CPU_PREV_PER_CORE: Mutex<Vec<CpuTimes>> = Mutex::new(Vec::new());
-static CPU_TIMES_INIT: std::sync::Once = std::sync::Once::new();
+/// 当前墙上时间（Unix 毫秒），时钟早于 Unix 纪元时为 0
+#[inline]
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// 默认的 proc 文件系统挂载点
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
+
+/// 某核心连续多少个周期恰好为 100% 时重新采集基准
+#[cfg(all(target_os = "linux", feature = "per-core"))]
+const STUCK_CORE_CYCLES: u32 = 3;
+
+/// `collect_system_stats` 使用的进程内共享采集器
+static DEFAULT_COLLECTOR: LazyLock<Collector> = LazyLock::new(Collector::default);
 
 /// 系统资源统计数据结构
-#[derive(Debug, Clone)]
+///
+/// 序列化为 JSON 时，带单位的数值字段使用单位后缀命名（如 `memory_used_bytes`、`cpu_usage_ratio`），
+/// 结构体内部字段名保持不变。
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemStats {
     pub hostname: String,
-    pub cpu_usage: f32,        // CPU 使用率 (0.0-1.0) - 保持向后兼容
-    pub cpu_stats: CpuStats,   // 详细的 CPU 统计信息
-    pub memory_total: u64,     // 总内存字节数
-    pub memory_used: u64,      // 已用内存字节数
+    #[serde(rename = "cpu_usage_ratio")]
+    pub cpu_usage: f32, // CPU 使用率 (0.0-1.0) - 保持向后兼容
+    pub cpu_stats: CpuStats, // 详细的 CPU 统计信息
+    #[serde(rename = "memory_total_bytes")]
+    pub memory_total: u64, // 总内存字节数
+    #[serde(rename = "memory_used_bytes")]
+    pub memory_used: u64, // 已用内存字节数
+    #[serde(rename = "memory_available_bytes")]
     pub memory_available: u64, // 可用内存字节数
-    pub memory_cached: u64,    // 缓存内存字节数
-    pub memory_free: u64,      // 空闲内存字节数
-    pub timestamp: Instant,    // 数据获取时间戳
+    #[serde(rename = "memory_cached_bytes")]
+    pub memory_cached: u64, // 缓存内存字节数
+    #[serde(rename = "memory_free_bytes")]
+    pub memory_free: u64, // 空闲内存字节数
+    pub conntrack: Option<ConntrackStats>, // conntrack 使用情况（未加载模块时为 None）
+    pub custom: BTreeMap<String, f64>, // 外部命令采集的自定义指标
+    /// 按 CPU 的中断分布（首次采集或读取失败时为 None）
+    #[cfg(feature = "interrupts")]
+    pub interrupts_by_cpu: Option<InterruptStats>,
+    /// 各可选采集子系统本次的采集状态，键为 `SUBSYSTEM_*`
+    pub subsystems: BTreeMap<&'static str, SubsystemStatus>,
+    /// 相对最近窗口明显异常的指标，键为 `anomaly::METRIC_*`（未启用异常检测时为空）
+    pub anomalies: BTreeMap<&'static str, Anomaly>,
+    /// 系统时钟是否已与外部时间源同步（adjtimex 未报告 STA_UNSYNC）
+    pub clock_synced: bool,
+    /// 内核估计的时钟误差（未同步或未知时为 None）
+    #[serde(
+        rename = "clock_estimated_error_seconds",
+        serialize_with = "serialize_duration_secs"
+    )]
+    pub clock_estimated_error: Option<Duration>,
+    /// 与上一次采集之间的实际间隔（首次采集为 None），速率类计算应使用该值而非名义 TTL
+    #[serde(
+        rename = "collection_interval_seconds",
+        serialize_with = "serialize_duration_secs"
+    )]
+    pub collection_interval: Option<Duration>,
+    /// 采集序列号：每次采集尝试（含失败与后台刷新错过的周期）加 1，从 1 开始；
+    /// 消费方看到序列号跳变即说明中间有采集失败或被跳过
+    pub sequence: u64,
+    /// 采集时刻的墙上时间（Unix 毫秒），用于展示与持久化；时钟可能被回拨或跳变，不用于计算间隔
+    pub collected_at_unix_ms: u64,
+    /// 采集时刻的单调时钟读数，不受时钟回拨影响，间隔、年龄与速率一律以它计算（不参与序列化）
+    #[serde(skip)]
+    pub timestamp: Instant,
 }
 
 impl Default for SystemStats {
@@ -66,17 +277,40 @@ impl Default for SystemStats {
                 overall: CpuUsageBreakdown::default(),
                 per_core: Vec::new(),
                 core_count: 0,
+                core_ids: None,
             },
             memory_total: 0,
             memory_used: 0,
             memory_available: 0,
             memory_cached: 0,
             memory_free: 0,
+            conntrack: None,
+            custom: BTreeMap::new(),
+            #[cfg(feature = "interrupts")]
+            interrupts_by_cpu: None,
+            subsystems: BTreeMap::new(),
+            anomalies: BTreeMap::new(),
+            clock_synced: false,
+            clock_estimated_error: None,
+            collection_interval: None,
+            sequence: 0,
+            collected_at_unix_ms: 0,
             timestamp: Instant::now(),
         }
     }
 }
 
+/// 将 `Option<Duration>` 序列化为秒数
+fn serialize_duration_secs<S: serde::Serializer>(
+    value: &Option<Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+        None => serializer.serialize_none(),
+    }
+}
+
 /// 系统资源获取错误类型
 #[derive(Debug)]
 pub enum StatsError {
@@ -84,6 +318,10 @@ pub enum StatsError {
     ParseError(String),
     #[allow(dead_code)] // 为未来跨平台支持预留
     UnsupportedPlatform,
+    /// 连续采集失败导致断路器打开，冷却期内不再尝试采集
+    CircuitOpen {
+        retry_after: Duration,
+    },
 }
 
 impl From<std::io::Error> for StatsError {
@@ -99,6 +337,11 @@ impl std::fmt::Display for StatsError {
             StatsError::IoError(e) => write!(f, "IO 错误: {e}"),
             StatsError::ParseError(s) => write!(f, "解析错误: {s}"),
             StatsError::UnsupportedPlatform => write!(f, "不支持的平台"),
+            StatsError::CircuitOpen { retry_after } => write!(
+                f,
+                "采集断路器已打开，{:.1} 秒后重试",
+                retry_after.as_secs_f64()
+            ),
         }
     }
 }
@@ -107,45 +350,418 @@ impl std::error::Error for StatsError {}
 
 pub type Result<T> = std::result::Result<T, StatsError>;
 
-/// 收集系统统计数据
+/// 收集系统统计数据（使用进程内共享的默认采集器）
+///
+/// 所有调用方共享同一份 CPU 采样基准；同一进程内运行多个监控实例时，
+/// 应为每个实例创建各自的 [`Collector`]。
+#[allow(dead_code)] // 库 API，二进制通过缓存持有的 Collector 采集
 pub async fn collect_system_stats() -> Result<SystemStats> {
-    #[cfg(target_os = "linux")]
-    {
-        collect_linux_stats().await
+    DEFAULT_COLLECTOR.collect().await
+}
+
+/// 系统统计采集器
+///
+/// 持有 proc 根目录与 CPU 增量计算所需的上一次采样，不同采集器之间互不影响。
+/// 将 proc 根目录指向 fixture 目录即可在测试中驱动完整的采集流程，
+/// 容器中也可以指向挂载进来的宿主机 /proc。
+#[derive(Debug)]
+pub struct Collector {
+    proc_root: PathBuf,
+    cpu_prev: Mutex<Option<(CpuTimes, Vec<CoreTimes>)>>,
+    /// 各核心（按核心编号）连续恰好为 100% 的周期数
+    #[cfg(feature = "per-core")]
+    stuck_cores: Mutex<BTreeMap<usize, u32>>,
+    /// 上一次读取的 /proc/interrupts 计数
+    #[cfg(feature = "interrupts")]
+    interrupts: InterruptSampler,
+    /// 复用的 /proc/stat 与 /proc/meminfo 句柄，未启用时每次重新打开
+    reused_files: Option<ReusedFiles>,
+    /// 本进程的 CPU 时间预算，超出时跳过较重的子采集
+    budget: Option<CpuBudget>,
+}
+
+/// 采集器持有的高频读取文件
+#[derive(Debug)]
+struct ReusedFiles {
+    stat: ProcFile,
+    meminfo: ProcFile,
+}
+
+impl Default for Collector {
+    #[inline]
+    fn default() -> Self {
+        Self::new(DEFAULT_PROC_ROOT)
     }
+}
 
-    #[cfg(not(target_os = "linux"))]
-    {
-        Err(StatsError::UnsupportedPlatform)
+impl Collector {
+    /// 创建以 `proc_root` 为 proc 根目录的采集器
+    #[inline]
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            cpu_prev: Mutex::new(None),
+            #[cfg(feature = "per-core")]
+            stuck_cores: Mutex::new(BTreeMap::new()),
+            #[cfg(feature = "interrupts")]
+            interrupts: InterruptSampler::default(),
+            reused_files: None,
+            budget: None,
+        }
+    }
+
+    /// 是否复用 /proc/stat 与 /proc/meminfo 的文件句柄
+    ///
+    /// 启用后首次读取时打开文件并一直持有，之后每次用 `pread` 从头重读，
+    /// 省去 open/close 与阻塞线程池调度，适合高频采集。
+    ///
+    /// `pread` 直接在异步工作线程上执行，只适合读取由内核即时生成、不会阻塞的 procfs；
+    /// proc 根目录指向较慢的文件系统（如网络文件系统上的副本）时，读取会阻塞工作线程，
+    /// 此时不要启用。
+    pub fn with_reused_fds(mut self, enabled: bool) -> Self {
+        self.reused_files = enabled.then(|| ReusedFiles {
+            stat: ProcFile::new(self.proc_root.join("stat")),
+            meminfo: ProcFile::new(self.proc_root.join("meminfo")),
+        });
+        self
+    }
+
+    /// 设置本进程的 CPU 时间预算（默认不限制）
+    ///
+    /// 每次采集前检查，超出预算时本次跳过每核心 CPU 与中断分布，
+    /// 这两个子系统的状态记为 [`SubsystemStatus::Throttled`]。
+    #[inline]
+    pub fn with_cpu_budget(mut self, budget: CpuBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// 收集系统统计数据
+    pub async fn collect(&self) -> Result<SystemStats> {
+        #[cfg(target_os = "linux")]
+        {
+            self.collect_linux_stats().await
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(StatsError::UnsupportedPlatform)
+        }
     }
 }
 
 /// Linux 系统统计数据收集
 #[cfg(target_os = "linux")]
-async fn collect_linux_stats() -> Result<SystemStats> {
-    let hostname = get_hostname()?;
-    let cpu_stats = get_cpu_stats().await?;
-    let cpu_usage = cpu_stats.overall.total_percent / 100.0; // 转换为 0.0-1.0 范围
-    let memory_info = get_memory_info().await?;
-
-    Ok(SystemStats {
-        hostname,
-        cpu_usage,
-        cpu_stats,
-        memory_total: memory_info.total,
-        memory_used: memory_info.used,
-        memory_available: memory_info.available,
-        memory_cached: memory_info.cached,
-        memory_free: memory_info.free,
-        timestamp: Instant::now(),
-    })
+impl Collector {
+    async fn collect_linux_stats(&self) -> Result<SystemStats> {
+        let root = self.proc_root.as_path();
+        let hostname = get_hostname(root)?;
+        let throttled = self.budget.as_ref().is_some_and(CpuBudget::check);
+        let cpu_stats = self.get_cpu_stats(throttled).await?;
+        let cpu_usage = cpu_stats.overall.total_percent / 100.0; // 转换为 0.0-1.0 范围
+        let meminfo = self
+            .read_proc_file(self.reused_files.as_ref().map(|f| &f.meminfo), "meminfo")
+            .await?;
+        let memory_info = parse_meminfo(&meminfo);
+
+        #[cfg(feature = "conntrack")]
+        let conntrack = get_conntrack_stats(root).await;
+        #[cfg(not(feature = "conntrack"))]
+        let conntrack = None;
+
+        #[cfg(feature = "interrupts")]
+        let (interrupts_by_cpu, interrupts_status) = if throttled {
+            (None, SubsystemStatus::Throttled)
+        } else {
+            match self.get_interrupt_stats().await {
+                Ok(stats) => (stats, SubsystemStatus::Ok),
+                Err(e) => {
+                    log::debug!("读取 /proc/interrupts 失败: {e}");
+                    (None, SubsystemStatus::Failed)
+                }
+            }
+        };
+        #[cfg(not(feature = "interrupts"))]
+        let interrupts_status = SubsystemStatus::Disabled;
+
+        let subsystems = BTreeMap::from([
+            (
+                SUBSYSTEM_PER_CORE,
+                if throttled && cfg!(feature = "per-core") {
+                    SubsystemStatus::Throttled
+                } else {
+                    SubsystemStatus::enabled_if(cfg!(feature = "per-core"), || {
+                        !cpu_stats.per_core.is_empty()
+                    })
+                },
+            ),
+            (
+                SUBSYSTEM_CONNTRACK,
+                SubsystemStatus::enabled_if(cfg!(feature = "conntrack"), || conntrack.is_some()),
+            ),
+            (SUBSYSTEM_INTERRUPTS, interrupts_status),
+        ]);
+
+        let clock = get_clock_status();
+
+        Ok(SystemStats {
+            hostname,
+            cpu_usage,
+            cpu_stats,
+            memory_total: memory_info.total,
+            memory_used: memory_info.used,
+            memory_available: memory_info.available,
+            memory_cached: memory_info.cached,
+            memory_free: memory_info.free,
+            conntrack,
+            custom: BTreeMap::new(),
+            #[cfg(feature = "interrupts")]
+            interrupts_by_cpu,
+            subsystems,
+            anomalies: BTreeMap::new(),
+            clock_synced: clock.synced,
+            clock_estimated_error: clock.estimated_error,
+            collection_interval: None,
+            sequence: 0,
+            collected_at_unix_ms: unix_millis(),
+            timestamp: Instant::now(),
+        })
+    }
+
+    /// 读取 proc 根目录下的 `name`，有复用句柄时直接 `pread`
+    async fn read_proc_file(
+        &self,
+        reused: Option<&ProcFile>,
+        name: &str,
+    ) -> std::io::Result<String> {
+        match reused {
+            Some(file) => file.read_to_string(),
+            None => tokio::fs::read_to_string(self.proc_root.join(name)).await,
+        }
+    }
+
+    /// 读取 /proc/stat
+    #[inline]
+    async fn read_stat(&self) -> std::io::Result<String> {
+        self.read_proc_file(self.reused_files.as_ref().map(|f| &f.stat), "stat")
+            .await
+    }
+
+    /// 获取详细的 CPU 统计信息
+    ///
+    /// `skip_per_core` 时只解析 /proc/stat 的总体一行，不解析、不计算各核心；
+    /// 各核心沿用上一次的基准，恢复后的增量覆盖整个跳过期间。
+    async fn get_cpu_stats(&self, skip_per_core: bool) -> Result<CpuStats> {
+        let content = self.read_stat().await?;
+        let (current_overall, current_per_core, core_count) = if skip_per_core {
+            (parse_cpu_times(&content)?, None, count_cpu_cores(&content))
+        } else {
+            let (overall, per_core, core_count) = parse_proc_stat(&content)?;
+            (overall, Some(per_core), core_count)
+        };
+
+        // 取出上一次采样并写入本次采样（线程安全），第一次调用时没有上一次采样
+        let prev = {
+            let mut cpu_prev = self.cpu_prev.lock().unwrap_or_else(|e| e.into_inner());
+            let baseline_per_core = match &current_per_core {
+                Some(per_core) => per_core.clone(),
+                None => cpu_prev
+                    .as_ref()
+                    .map(|(_, per_core)| per_core.clone())
+                    .unwrap_or_default(),
+            };
+            cpu_prev.replace((current_overall.clone(), baseline_per_core))
+        };
+
+        // 如果是第一次调用，返回 0 使用率
+        let overall_usage = match &prev {
+            Some((prev_overall, _)) => {
+                checked_usage_breakdown(None, prev_overall, &current_overall)
+            }
+            None => CpuUsageBreakdown::default(),
+        };
+
+        // 计算每个 CPU 核心的使用率，按核心编号匹配上一次采样（核心上下线时下标会错位）
+        #[cfg(feature = "per-core")]
+        let (per_core_usage, core_ids) = match &current_per_core {
+            None => (Vec::new(), None),
+            Some(current_per_core) => {
+                let prev_per_core = prev.as_ref().map_or(&[][..], |(_, cores)| cores);
+                let usage: Vec<CpuUsageBreakdown> = current_per_core
+                    .iter()
+                    .map(|(id, current_core)| match find_core(prev_per_core, *id) {
+                        Some(prev_core) => {
+                            checked_usage_breakdown(Some(*id), prev_core, current_core)
+                        }
+                        None => CpuUsageBreakdown::default(),
+                    })
+                    .collect();
+                let stuck = update_stuck_counters(
+                    &mut self.stuck_cores.lock().unwrap_or_else(|e| e.into_inner()),
+                    current_per_core.iter().map(|(id, _)| *id).zip(&usage),
+                );
+                if !stuck.is_empty() {
+                    self.rebaseline_cores(&stuck).await;
+                }
+                (usage, non_contiguous_core_ids(current_per_core))
+            }
+        };
+        #[cfg(not(feature = "per-core"))]
+        let (per_core_usage, core_ids) = (Vec::new(), None);
+
+        Ok(CpuStats {
+            overall: overall_usage,
+            per_core: per_core_usage,
+            core_count,
+            core_ids,
+        })
+    }
+
+    /// 立即重读一次 /proc/stat，以新读数替换 `cores` 中各核心的上一次采样
+    ///
+    /// 真实负载下使用率会有波动，长时间精确停在 100% 多半是上一次采样被污染
+    /// （例如采样窗口内解析出错），下个周期的增量即从干净的基准算起。
+    #[cfg(feature = "per-core")]
+    async fn rebaseline_cores(&self, cores: &[usize]) {
+        let fresh = match self.read_stat().await {
+            Ok(content) => parse_proc_stat(&content),
+            Err(e) => Err(e.into()),
+        };
+        let fresh_per_core = match fresh {
+            Ok((_, per_core, _)) => per_core,
+            Err(e) => {
+                log::debug!("重新采集 CPU 基准失败: {e}");
+                return;
+            }
+        };
+
+        let mut cpu_prev = self.cpu_prev.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((_, prev_per_core)) = cpu_prev.as_mut() else {
+            return;
+        };
+        for &id in cores {
+            let prev = prev_per_core
+                .binary_search_by_key(&id, |&(core_id, _)| core_id)
+                .ok()
+                .map(|index| &mut prev_per_core[index].1);
+            if let (Some(prev), Some(fresh)) = (prev, find_core(&fresh_per_core, id)) {
+                log::debug!("核心 {id} 连续 {STUCK_CORE_CYCLES} 个周期恒为 100%，已重新采集基准");
+                *prev = fresh.clone();
+            }
+        }
+    }
+
+    /// 读取 /proc/interrupts 并计算与上一次采集之间的中断速率，第一次采集时为 `Ok(None)`
+    #[cfg(feature = "interrupts")]
+    async fn get_interrupt_stats(&self) -> std::io::Result<Option<InterruptStats>> {
+        let content = tokio::fs::read_to_string(self.proc_root.join("interrupts")).await?;
+        let stats = self.interrupts.sample(&content, Instant::now());
+        if let Some(stats) = &stats
+            && !stats.hotspot_cpus.is_empty()
+        {
+            log::debug!("中断集中在 CPU {:?}", stats.hotspot_cpus);
+        }
+        Ok(stats)
+    }
+}
+
+/// 更新各核心连续恰好为 100% 的周期数，返回达到阈值需要重新采集基准的核心编号（其计数清零）
+///
+/// 已下线的核心不再计数。
+#[cfg(all(target_os = "linux", feature = "per-core"))]
+fn update_stuck_counters<'a>(
+    counters: &mut BTreeMap<usize, u32>,
+    per_core_usage: impl Iterator<Item = (usize, &'a CpuUsageBreakdown)>,
+) -> Vec<usize> {
+    let mut stuck = Vec::new();
+    let previous = std::mem::take(counters);
+    for (id, usage) in per_core_usage {
+        let mut count = 0;
+        if usage.total_percent == 100.0 {
+            count = previous.get(&id).copied().unwrap_or(0) + 1;
+            if count >= STUCK_CORE_CYCLES {
+                count = 0;
+                stuck.push(id);
+            }
+        }
+        counters.insert(id, count);
+    }
+    stuck
+}
+
+/// 计算两次采样之间的使用率，计数器回退时本周期记为 0
+///
+/// 计数器只增不减，出现回退说明其中一次读数不可信（如核心下线后重新上线计数清零、
+/// 内核的 idle 统计回退）。此时按饱和减法会把回退的 idle 算成 0，得到虚假的 100%，
+/// 因此本周期不计算；当前读数已作为下一周期的基准，下一周期自动恢复。
+/// `core` 为核心编号，`None` 表示总体一行，只用于日志。
+#[cfg(target_os = "linux")]
+fn checked_usage_breakdown(
+    core: Option<usize>,
+    prev: &CpuTimes,
+    current: &CpuTimes,
+) -> CpuUsageBreakdown {
+    if counters_regressed(prev, current) {
+        match core {
+            Some(id) => {
+                log::debug!("核心 {id} 的 CPU 计数器回退，本周期记为 0，以当前读数为新基准")
+            }
+            None => log::debug!("总体 CPU 计数器回退，本周期记为 0，以当前读数为新基准"),
+        }
+        return CpuUsageBreakdown::default();
+    }
+    calculate_cpu_usage_breakdown(prev, current)
+}
+
+/// 任一累计计数器小于上一次采样
+#[cfg(target_os = "linux")]
+fn counters_regressed(prev: &CpuTimes, current: &CpuTimes) -> bool {
+    current.user < prev.user
+        || current.nice < prev.nice
+        || current.system < prev.system
+        || current.idle < prev.idle
+        || current.iowait < prev.iowait
+        || current.irq < prev.irq
+        || current.softirq < prev.softirq
+        || current.total < prev.total
+}
+
+/// 系统时钟同步状态
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct ClockStatus {
+    synced: bool,
+    estimated_error: Option<Duration>,
+}
+
+/// 通过 adjtimex（只读）获取时钟同步状态，调用失败时视为未同步
+#[cfg(target_os = "linux")]
+fn get_clock_status() -> ClockStatus {
+    // SAFETY: timex 为纯数据结构，全零是合法值；modes 为 0 时 adjtimex 只读取不修改时钟
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut timex) };
+    if state == -1 {
+        log::debug!("读取时钟同步状态失败: {}", std::io::Error::last_os_error());
+        return ClockStatus::default();
+    }
+    clock_status_from_timex(state, timex.status, timex.esterror as i64)
+}
+
+/// 由 adjtimex 的返回值、状态位与估计误差（微秒）判断同步状态
+#[cfg(target_os = "linux")]
+fn clock_status_from_timex(state: i32, status: i32, esterror_us: i64) -> ClockStatus {
+    let synced = state != libc::TIME_ERROR && status & libc::STA_UNSYNC == 0;
+    ClockStatus {
+        synced,
+        estimated_error: synced.then(|| Duration::from_micros(esterror_us.max(0) as u64)),
+    }
 }
 
 /// 获取主机名
 #[cfg(target_os = "linux")]
 #[inline]
-fn get_hostname() -> Result<String> {
-    std::fs::read_to_string("/proc/sys/kernel/hostname")
+fn get_hostname(root: &Path) -> Result<String> {
+    std::fs::read_to_string(root.join("sys/kernel/hostname"))
         .map(|s| s.trim().to_string())
         .map_err(From::from)
 }
@@ -160,14 +776,9 @@ struct MemoryInfo {
     free: u64,
 }
 
-/// 获取内存信息
+/// 解析 /proc/meminfo 的内存信息
 #[cfg(target_os = "linux")]
-async fn get_memory_info() -> Result<MemoryInfo> {
-    // 预估 /proc/meminfo 的大小，预分配容量
-    let mut content = String::with_capacity(2048);
-    let file_content = tokio::fs::read_to_string("/proc/meminfo").await?;
-    content.push_str(&file_content);
-
+fn parse_meminfo(content: &str) -> MemoryInfo {
     let mut info = MemoryInfo::default();
 
     for line in content.lines() {
@@ -188,13 +799,143 @@ async fn get_memory_info() -> Result<MemoryInfo> {
     // 计算已用内存 = 总内存 - 可用内存
     info.used = info.total.saturating_sub(info.available);
 
-    Ok(info)
+    info
+}
+
+/// 获取 conntrack 连接跟踪表使用情况
+///
+/// 未加载 nf_conntrack 模块时相关文件不存在，返回 `None`。
+#[cfg(all(target_os = "linux", feature = "conntrack"))]
+async fn get_conntrack_stats(root: &Path) -> Option<ConntrackStats> {
+    let netfilter = root.join("sys/net/netfilter");
+    let count = tokio::fs::read_to_string(netfilter.join("nf_conntrack_count")).await;
+    let max = tokio::fs::read_to_string(netfilter.join("nf_conntrack_max")).await;
+
+    let stats = match (count, max) {
+        (Ok(count), Ok(max)) => parse_conntrack(&count, &max),
+        (Err(e), _) | (_, Err(e)) => {
+            log::debug!("读取 conntrack 信息失败（可能未加载 nf_conntrack 模块）: {e}");
+            return None;
+        }
+    };
+
+    if let Some(stats) = &stats
+        && stats.usage_percent >= CONNTRACK_WARN_PERCENT
+    {
+        log::warn!(
+            "conntrack 连接跟踪表即将用尽: {}/{} ({:.1}%)，新连接可能被丢弃",
+            stats.count,
+            stats.max,
+            stats.usage_percent
+        );
+    }
+
+    stats
+}
+
+/// 解析 conntrack 计数与上限
+#[cfg(all(target_os = "linux", any(feature = "conntrack", test)))]
+#[inline]
+fn parse_conntrack(count: &str, max: &str) -> Option<ConntrackStats> {
+    let count = count.trim().parse::<u64>().ok()?;
+    let max = max.trim().parse::<u64>().ok()?;
+    if max == 0 {
+        return None;
+    }
+
+    Some(ConntrackStats {
+        count,
+        max,
+        usage_percent: (count as f32 / max as f32 * 100.0).clamp(0.0, 100.0),
+    })
+}
+
+/// 快速解析 ASCII 十进制无符号整数
+///
+/// 语义与 `str::parse::<u64>().unwrap_or(0)` 一致（允许前导 `+`，非法或溢出时为 0），
+/// 但省去了通用解析的进制与错误构造开销。超多核机器上 /proc/stat 有上千个字段，
+/// 高频采集时差异可见。
+#[inline]
+fn parse_u64_fast(field: &[u8]) -> u64 {
+    let digits = match field {
+        [b'+', rest @ ..] => rest,
+        _ => field,
+    };
+    // 不超过 19 位的十进制数不会溢出 u64，逐位累加时无需检查溢出；
+    // 更长的字段极少出现，交给标准库处理以保持相同的溢出语义
+    if digits.is_empty() || digits.len() > 19 {
+        return std::str::from_utf8(field)
+            .ok()
+            .and_then(|field| field.parse().ok())
+            .unwrap_or(0);
+    }
+
+    let mut value: u64 = 0;
+    for &byte in digits {
+        let digit = byte.wrapping_sub(b'0');
+        if digit > 9 {
+            return 0;
+        }
+        value = value * 10 + u64::from(digit);
+    }
+    value
+}
+
+/// 解析 /proc/stat 中的一行 CPU 时间，返回标签（`cpu`、`cpu0` 等）与时间统计
+///
+/// 缺失的字段视为 0，忽略 steal、guest、guest_nice 字段
+#[cfg(target_os = "linux")]
+#[inline]
+fn parse_cpu_line(line: &str) -> (&str, CpuTimes) {
+    let bytes = line.as_bytes();
+    let mut pos = 0;
+    // 依次取出以 ASCII 空白分隔的字段，不经过 UTF-8 与迭代器适配层
+    let mut next_field = || {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        (start, pos)
+    };
+
+    let (label_start, label_end) = next_field();
+    // 字段边界都在 ASCII 字节处，切片总是合法的 UTF-8
+    let label = &line[label_start..label_end];
+    let mut next = || {
+        let (start, end) = next_field();
+        parse_u64_fast(&bytes[start..end])
+    };
+
+    let user = next();
+    let nice = next();
+    let system = next();
+    let idle = next();
+    let iowait = next();
+    let irq = next();
+    let softirq = next();
+    let total = user + nice + system + idle + iowait + irq + softirq;
+
+    (
+        label,
+        CpuTimes {
+            user,
+            nice,
+            system,
+            idle,
+            iowait,
+            irq,
+            softirq,
+            total,
+        },
+    )
 }
 
-/// 解析 CPU 时间统计（为未来功能预留）
+/// 解析 CPU 时间统计（仅总体）
 #[cfg(target_os = "linux")]
 #[inline]
-#[allow(dead_code)] // 为未来功能预留
 fn parse_cpu_times(content: &str) -> Result<CpuTimes> {
     // 解析第一行 CPU 总时间
     let first_line = content
@@ -202,69 +943,37 @@ fn parse_cpu_times(content: &str) -> Result<CpuTimes> {
         .next()
         .ok_or_else(|| StatsError::ParseError("无法解析 /proc/stat".to_string()))?;
 
-    let mut parts = first_line.split_whitespace().skip(1); // 跳过 "cpu"
-
-    let user: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let nice: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let system: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let idle: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let iowait: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let irq: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let softirq: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-
-    // 忽略其他字段 (steal, guest, guest_nice)
-    let total = user + nice + system + idle + iowait + irq + softirq;
-
-    Ok(CpuTimes {
-        user,
-        nice,
-        system,
-        idle,
-        iowait,
-        irq,
-        softirq,
-        total,
-    })
+    Ok(parse_cpu_line(first_line).1)
 }
 
-/// 解析所有 CPU 核心的时间统计
+/// 统计 /proc/stat 中的 CPU 核心数量（不解析各核心时间）
 #[cfg(target_os = "linux")]
 #[inline]
-fn parse_all_cpu_times(content: &str) -> Result<(CpuTimes, Vec<CpuTimes>)> {
-    let lines = content.lines();
+fn count_cpu_cores(content: &str) -> usize {
+    content
+        .lines()
+        .filter(|line| {
+            line.strip_prefix("cpu")
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .count()
+}
+
+/// 解析所有 CPU 核心的时间统计，各核心附带 `cpuN` 标签中的核心编号
+///
+/// 离线的核心不出现在 /proc/stat 中，编号可能不连续。
+#[cfg(all(target_os = "linux", any(feature = "per-core", test)))]
+#[inline]
+fn parse_all_cpu_times(content: &str) -> Result<(CpuTimes, Vec<CoreTimes>)> {
     let mut overall_times = None;
     let mut per_core_times = Vec::new();
 
-    for line in lines {
-        if line.starts_with("cpu") {
-            let mut parts = line.split_whitespace();
-            let cpu_label = parts.next().unwrap_or("");
-
-            let user: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-            let nice: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-            let system: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-            let idle: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-            let iowait: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-            let irq: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-            let softirq: u64 = parts.next().unwrap_or("0").parse().unwrap_or(0);
-
-            let total = user + nice + system + idle + iowait + irq + softirq;
-            let times = CpuTimes {
-                user,
-                nice,
-                system,
-                idle,
-                iowait,
-                irq,
-                softirq,
-                total,
-            };
-
-            if cpu_label == "cpu" {
-                overall_times = Some(times);
-            } else if cpu_label.starts_with("cpu") {
-                per_core_times.push(times);
-            }
+    for line in content.lines().filter(|line| line.starts_with("cpu")) {
+        let (cpu_label, times) = parse_cpu_line(line);
+        if cpu_label == "cpu" {
+            overall_times = Some(times);
+        } else if let Some(id) = cpu_label.strip_prefix("cpu").and_then(|n| n.parse().ok()) {
+            per_core_times.push((id, times));
         }
     }
 
@@ -274,72 +983,61 @@ fn parse_all_cpu_times(content: &str) -> Result<(CpuTimes, Vec<CpuTimes>)> {
     }
 }
 
+/// 按核心编号查找（`cores` 按编号升序，与 /proc/stat 的顺序一致）
+#[inline]
+pub(crate) fn find_core(cores: &[CoreTimes], id: usize) -> Option<&CpuTimes> {
+    cores
+        .binary_search_by_key(&id, |&(core_id, _)| core_id)
+        .ok()
+        .map(|index| &cores[index].1)
+}
+
+/// 核心编号不是 0..n 时返回各核心的编号，供 [`CpuStats::core_ids`] 使用
+#[inline]
+pub(crate) fn non_contiguous_core_ids(cores: &[CoreTimes]) -> Option<Vec<usize>> {
+    let contiguous = cores
+        .iter()
+        .enumerate()
+        .all(|(index, &(id, _))| index == id);
+    (!contiguous).then(|| cores.iter().map(|&(id, _)| id).collect())
+}
+
 /// CPU 使用率计算（使用增量算法）（为向后兼容预留）
 #[cfg(target_os = "linux")]
 #[allow(dead_code)] // 为向后兼容预留
 async fn get_cpu_usage() -> Result<f32> {
-    let cpu_stats = get_cpu_stats().await?;
+    let cpu_stats = DEFAULT_COLLECTOR.get_cpu_stats(false).await?;
     Ok(cpu_stats.overall.total_percent / 100.0)
 }
 
-/// 获取详细的 CPU 统计信息
+/// 解析 /proc/stat，返回总体时间、各核心的编号与时间、核心数
+///
+/// 未启用 per-core 时只解析总体时间，各核心时间为空，核心数单独统计
 #[cfg(target_os = "linux")]
-async fn get_cpu_stats() -> Result<CpuStats> {
-    // 预估 /proc/stat 的大小，预分配容量
-    let mut content = String::with_capacity(2048);
-    let file_content = tokio::fs::read_to_string("/proc/stat").await?;
-    content.push_str(&file_content);
-
-    let (current_overall, current_per_core) = parse_all_cpu_times(&content)?;
-
-    // 获取之前的时间统计（线程安全）
-    let (prev_overall, prev_per_core) = {
-        let mut prev_overall_guard = CPU_PREV_OVERALL.lock().unwrap();
-        let mut prev_per_core_guard = CPU_PREV_PER_CORE.lock().unwrap();
-
-        CPU_TIMES_INIT.call_once(|| {
-            *prev_overall_guard = Some(current_overall.clone());
-            prev_per_core_guard.clone_from(&current_per_core);
-        });
-
-        (prev_overall_guard.clone(), prev_per_core_guard.clone())
-    };
-
-    // 如果是第一次调用，返回 0 使用率
-    let overall_usage = if let (Some(prev_overall), _) = (&prev_overall, &prev_per_core) {
-        calculate_cpu_usage_breakdown(prev_overall, &current_overall)
-    } else {
-        CpuUsageBreakdown::default()
-    };
-
-    // 计算每个 CPU 核心的使用率
-    let mut per_core_usage = Vec::new();
-    for (i, current_core) in current_per_core.iter().enumerate() {
-        if let Some(prev_core) = prev_per_core.get(i) {
-            per_core_usage.push(calculate_cpu_usage_breakdown(prev_core, current_core));
-        } else {
-            per_core_usage.push(CpuUsageBreakdown::default());
-        }
+#[inline]
+pub fn parse_proc_stat(content: &str) -> Result<(CpuTimes, Vec<CoreTimes>, usize)> {
+    #[cfg(feature = "per-core")]
+    {
+        let (overall, per_core) = parse_all_cpu_times(content)?;
+        let core_count = per_core.len();
+        Ok((overall, per_core, core_count))
     }
-
-    // 更新全局缓存
+    #[cfg(not(feature = "per-core"))]
     {
-        let mut prev_overall_guard = CPU_PREV_OVERALL.lock().unwrap();
-        let mut prev_per_core_guard = CPU_PREV_PER_CORE.lock().unwrap();
-        *prev_overall_guard = Some(current_overall.clone());
-        *prev_per_core_guard = current_per_core.clone();
+        Ok((
+            parse_cpu_times(content)?,
+            Vec::new(),
+            count_cpu_cores(content),
+        ))
     }
-
-    Ok(CpuStats {
-        overall: overall_usage,
-        per_core: per_core_usage,
-        core_count: current_per_core.len(),
-    })
 }
 
 /// 计算两个时间点之间的 CPU 使用率分解
 #[inline]
-fn calculate_cpu_usage_breakdown(prev: &CpuTimes, current: &CpuTimes) -> CpuUsageBreakdown {
+pub(crate) fn calculate_cpu_usage_breakdown(
+    prev: &CpuTimes,
+    current: &CpuTimes,
+) -> CpuUsageBreakdown {
     // 计算增量
     let total_diff = current.total.saturating_sub(prev.total);
 
@@ -370,6 +1068,85 @@ fn calculate_cpu_usage_breakdown(prev: &CpuTimes, current: &CpuTimes) -> CpuUsag
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_compact_cpu_usage() {
+        // 紧凑表示的占用是浮点表示的一半
+        assert_eq!(std::mem::size_of::<CpuUsageBreakdown>(), 16);
+        assert_eq!(std::mem::size_of::<CompactCpuUsage>(), 8);
+
+        let usage = CpuUsageBreakdown {
+            user_percent: 12.345,
+            nice_percent: 0.004,
+            system_percent: 99.996,
+            total_percent: 100.0,
+        };
+        let compact = CompactCpuUsage::from_breakdown(&usage);
+        assert_eq!(compact.user, 1235);
+        assert_eq!(compact.total, 10000);
+        let restored = compact.to_breakdown();
+        for (original, restored) in [
+            (usage.user_percent, restored.user_percent),
+            (usage.nice_percent, restored.nice_percent),
+            (usage.system_percent, restored.system_percent),
+            (usage.total_percent, restored.total_percent),
+        ] {
+            assert!((original - restored).abs() <= 0.005 + 1e-4);
+        }
+
+        // 超出范围与 NaN
+        let invalid = CpuUsageBreakdown {
+            user_percent: -3.0,
+            nice_percent: f32::NAN,
+            system_percent: 150.0,
+            total_percent: 0.0,
+        };
+        let compact = CompactCpuUsage::from_breakdown(&invalid);
+        assert_eq!((compact.user, compact.nice, compact.system), (0, 0, 10000));
+    }
+
+    #[test]
+    fn test_retain_cores() {
+        let core = |total_percent| CpuUsageBreakdown {
+            total_percent,
+            ..CpuUsageBreakdown::default()
+        };
+        let mut stats = CpuStats {
+            overall: core(25.0),
+            per_core: vec![core(10.0), core(20.0), core(30.0), core(40.0)],
+            core_count: 4,
+            core_ids: None,
+        };
+        assert_eq!(stats.core_id(2), 2);
+
+        let invalid = stats.retain_cores(&[3, 1, 9, 3]);
+        assert_eq!(invalid, vec![9]);
+        assert_eq!(stats.core_ids, Some(vec![1, 3]));
+        assert_eq!(stats.per_core.len(), 2);
+        assert_eq!(stats.per_core[1].total_percent, 40.0);
+        assert_eq!(stats.core_id(1), 3);
+        assert_eq!(stats.core_count, 4);
+        assert_eq!(stats.overall.total_percent, 25.0);
+
+        // 核心编号不连续时按编号而非下标筛选
+        let mut offline = CpuStats {
+            per_core: vec![core(10.0), core(30.0)],
+            core_ids: Some(vec![0, 2]),
+            ..stats.clone()
+        };
+        assert_eq!(offline.retain_cores(&[1, 2]), vec![1]);
+        assert_eq!(offline.core_ids, Some(vec![2]));
+        assert_eq!(offline.per_core[0].total_percent, 30.0);
+
+        // 未采集每核心数据时不做处理
+        let mut empty = CpuStats {
+            per_core: Vec::new(),
+            ..stats.clone()
+        };
+        empty.core_ids = None;
+        assert!(empty.retain_cores(&[0]).is_empty());
+        assert!(empty.core_ids.is_none());
+    }
+
     #[test]
     fn test_system_stats_default() {
         let stats = SystemStats::default();
@@ -382,6 +1159,44 @@ mod tests {
         assert_eq!(stats.memory_free, 0);
     }
 
+    #[test]
+    fn test_system_stats_json_field_names() {
+        let stats = SystemStats {
+            cpu_usage: 0.5,
+            memory_total: 1024,
+            memory_used: 512,
+            conntrack: Some(ConntrackStats {
+                count: 1,
+                max: 2,
+                usage_percent: 50.0,
+            }),
+            ..SystemStats::default()
+        };
+        let value = serde_json::to_value(&stats).unwrap();
+
+        assert_eq!(value["cpu_usage_ratio"], 0.5);
+        assert_eq!(value["memory_total_bytes"], 1024);
+        assert_eq!(value["memory_used_bytes"], 512);
+        assert!(value.get("memory_available_bytes").is_some());
+        assert!(value.get("memory_cached_bytes").is_some());
+        assert!(value.get("memory_free_bytes").is_some());
+        assert!(value.get("memory_used").is_none());
+        assert!(value.get("cpu_usage").is_none());
+        assert!(value.get("timestamp").is_none());
+        assert!(value["cpu_stats"]["overall"].get("total_percent").is_some());
+        assert_eq!(value["conntrack"]["usage_percent"], 50.0);
+        assert!(value["collection_interval_seconds"].is_null());
+        assert_eq!(value["clock_synced"], false);
+        assert!(value["clock_estimated_error_seconds"].is_null());
+
+        let stats = SystemStats {
+            collection_interval: Some(Duration::from_millis(1500)),
+            ..SystemStats::default()
+        };
+        let value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(value["collection_interval_seconds"], 1.5);
+    }
+
     #[test]
     fn test_cpu_times_default() {
         let times = CpuTimes::default();
@@ -447,7 +1262,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     async fn test_get_hostname() {
         // 测试获取主机名
-        match get_hostname() {
+        match get_hostname(Path::new(DEFAULT_PROC_ROOT)) {
             Ok(hostname) => {
                 assert!(!hostname.is_empty());
                 println!("主机名: {}", hostname);
@@ -461,10 +1276,11 @@ mod tests {
 
     #[tokio::test]
     #[cfg(target_os = "linux")]
-    async fn test_get_memory_info() {
-        // 测试获取内存信息
-        match get_memory_info().await {
-            Ok(info) => {
+    async fn test_parse_meminfo() {
+        // 测试解析内存信息
+        match tokio::fs::read_to_string(Path::new(DEFAULT_PROC_ROOT).join("meminfo")).await {
+            Ok(content) => {
+                let info = parse_meminfo(&content);
                 assert!(info.total > 0);
                 assert!(info.used <= info.total);
                 assert!(info.available <= info.total);
@@ -483,7 +1299,7 @@ mod tests {
     #[cfg(target_os = "linux")]
     async fn test_collect_linux_stats() {
         // 测试完整的 Linux 统计数据收集
-        match collect_linux_stats().await {
+        match Collector::default().collect_linux_stats().await {
             Ok(stats) => {
                 assert!(!stats.hostname.is_empty());
                 assert!(stats.cpu_usage >= 0.0 && stats.cpu_usage <= 1.0);
@@ -534,8 +1350,109 @@ mod tests {
         assert_eq!(overall.softirq, 300);
 
         assert_eq!(per_core.len(), 2);
-        assert_eq!(per_core[0].user, 617);
-        assert_eq!(per_core[1].user, 617);
+        assert_eq!(
+            per_core[0],
+            (0, parse_cpu_line("cpu0 617 283 445 617 50 100 150").1)
+        );
+        assert_eq!(per_core[1].0, 1);
+        assert_eq!(per_core[1].1.nice, 284);
+        assert_eq!(non_contiguous_core_ids(&per_core), None);
+    }
+
+    #[test]
+    fn test_parse_all_cpu_times_non_contiguous() {
+        // cpu1 离线
+        let content = "cpu  300 0 0 300 0 0 0\n\
+                        cpu0 100 0 0 100 0 0 0\n\
+                        cpu2 200 0 0 200 0 0 0";
+        let (_, per_core) = parse_all_cpu_times(content).unwrap();
+
+        assert_eq!(
+            per_core.iter().map(|&(id, _)| id).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(non_contiguous_core_ids(&per_core), Some(vec![0, 2]));
+        assert_eq!(find_core(&per_core, 2).map(|core| core.user), Some(200));
+        assert_eq!(find_core(&per_core, 1), None);
+    }
+
+    #[test]
+    fn test_parse_u64_fast() {
+        for field in [
+            "0",
+            "42",
+            "+7",
+            "9999999999999999999",
+            "18446744073709551615",
+            "007",
+        ] {
+            assert_eq!(
+                parse_u64_fast(field.as_bytes()),
+                field.parse::<u64>().unwrap()
+            );
+        }
+        // 非法或溢出时与 `parse().unwrap_or(0)` 一样返回 0
+        for field in ["", "+", "-1", "12a", "18446744073709551616", "1.5"] {
+            assert_eq!(parse_u64_fast(field.as_bytes()), 0, "{field}");
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_cpu_line_matches_std_parse() {
+        // 原先基于 `str::parse` 的实现，用于验证快速解析结果一致
+        fn reference(line: &str) -> CpuTimes {
+            let mut parts = line.split_whitespace().skip(1);
+            let mut next = || parts.next().unwrap_or("0").parse::<u64>().unwrap_or(0);
+            let (user, nice, system, idle) = (next(), next(), next(), next());
+            let (iowait, irq, softirq) = (next(), next(), next());
+            CpuTimes {
+                user,
+                nice,
+                system,
+                idle,
+                iowait,
+                irq,
+                softirq,
+                total: user + nice + system + idle + iowait + irq + softirq,
+            }
+        }
+
+        let lines = [
+            "cpu  74608 2520 24433 1117073 6176 4054 0 0 0 0",
+            "cpu255 1 2 3 4 5 6 7 8 9 10",
+            "cpu0   10\t20  30 40",
+            "cpu1 1 x 3 4 5 6 7",
+            "cpu2",
+            "cpu3 99999999999999999999 1 1 1 1 1 1",
+        ];
+        for line in lines {
+            assert_eq!(parse_cpu_line(line).1, reference(line), "{line}");
+        }
+        assert_eq!(parse_cpu_line(lines[1]).0, "cpu255");
+    }
+
+    #[test]
+    fn test_count_cpu_cores() {
+        let content = "cpu  1234 567 890 1234 100 200 300 0 0 0\n\
+                        cpu0 617 283 445 617 50 100 150\n\
+                        cpu1 617 284 445 617 50 100 150\n\
+                        intr 12345 0 0\n\
+                        ctxt 67890";
+        assert_eq!(count_cpu_cores(content), 2);
+        assert_eq!(count_cpu_cores(""), 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_conntrack() {
+        let stats = parse_conntrack("1024\n", "65536\n").unwrap();
+        assert_eq!(stats.count, 1024);
+        assert_eq!(stats.max, 65536);
+        assert!((stats.usage_percent - 1.5625).abs() < 0.001);
+
+        assert!(parse_conntrack("abc", "65536").is_none());
+        assert!(parse_conntrack("10", "0").is_none());
     }
 
     #[test]
@@ -593,14 +1510,108 @@ mod tests {
         assert_eq!(breakdown.total_percent, 0.0);
     }
 
+    #[test]
+    #[cfg(all(target_os = "linux", feature = "per-core"))]
+    fn test_update_stuck_counters() {
+        let usage = |percents: &[f32]| -> Vec<CpuUsageBreakdown> {
+            percents
+                .iter()
+                .map(|&total_percent| CpuUsageBreakdown {
+                    total_percent,
+                    ..CpuUsageBreakdown::default()
+                })
+                .collect()
+        };
+        let update = |counters: &mut BTreeMap<usize, u32>, ids: &[usize], percents: &[f32]| {
+            update_stuck_counters(counters, ids.iter().copied().zip(&usage(percents)))
+        };
+        let mut counters = BTreeMap::new();
+
+        // 核心 0 恒为 100%，核心 1 在 100% 附近波动
+        assert!(update(&mut counters, &[0, 1], &[100.0, 100.0]).is_empty());
+        assert!(update(&mut counters, &[0, 1], &[100.0, 99.5]).is_empty());
+        assert_eq!(update(&mut counters, &[0, 1], &[100.0, 100.0]), vec![0]);
+        // 触发后重新计数
+        assert_eq!(counters, BTreeMap::from([(0, 0), (1, 1)]));
+
+        // 按编号计数，下线的核心不再保留
+        assert!(update(&mut counters, &[1], &[100.0]).is_empty());
+        assert_eq!(counters, BTreeMap::from([(1, 2)]));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_checked_usage_breakdown_ignores_regressed_counters() {
+        let prev = parse_cpu_line("cpu0 500 50 250 4000 100 25 25 0 0 0").1;
+        // idle 回退而总量增加：饱和减法会得到 100%
+        let regressed = parse_cpu_line("cpu0 700 50 250 3900 100 25 25 0 0 0").1;
+        assert_eq!(
+            calculate_cpu_usage_breakdown(&prev, &regressed).total_percent,
+            100.0
+        );
+        assert_eq!(
+            checked_usage_breakdown(Some(0), &prev, &regressed).total_percent,
+            0.0
+        );
+
+        let next = parse_cpu_line("cpu0 800 50 250 4000 100 25 25 0 0 0").1;
+        assert_eq!(
+            checked_usage_breakdown(Some(0), &regressed, &next).total_percent,
+            50.0
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_clock_status_from_timex() {
+        let synced = clock_status_from_timex(libc::TIME_OK, 0, 1500);
+        assert!(synced.synced);
+        assert_eq!(synced.estimated_error, Some(Duration::from_micros(1500)));
+
+        let unsynced = clock_status_from_timex(libc::TIME_OK, libc::STA_UNSYNC, 16_000_000);
+        assert_eq!(unsynced, ClockStatus::default());
+        assert!(!clock_status_from_timex(libc::TIME_ERROR, 0, 0).synced);
+
+        // 实际调用不应失败到影响采集
+        let _ = get_clock_status();
+    }
+
+    #[tokio::test]
+    #[cfg(all(target_os = "linux", feature = "per-core"))]
+    async fn test_skip_per_core_keeps_per_core_baseline() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let dir = std::env::temp_dir().join(format!("swb-skip-per-core-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let collector = Collector::new(&dir);
+        let write_stat = |name: &str| std::fs::copy(fixtures.join(name), dir.join("stat")).unwrap();
+
+        write_stat("proc/stat");
+        collector.get_cpu_stats(false).await.unwrap();
+        write_stat("stat.next");
+        let skipped = collector.get_cpu_stats(true).await.unwrap();
+        assert!(skipped.per_core.is_empty());
+        assert_eq!(skipped.core_count, 2);
+        assert_eq!(skipped.overall.total_percent, 50.0);
+
+        // 恢复后各核心的增量从跳过之前的基准算起
+        let resumed = collector.get_cpu_stats(false).await.unwrap();
+        assert_eq!(resumed.overall.total_percent, 0.0);
+        assert!((resumed.per_core[0].total_percent - 60.0).abs() < 1e-4);
+        assert!((resumed.per_core[1].total_percent - 40.0).abs() < 1e-4);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[tokio::test]
     #[cfg(target_os = "linux")]
     async fn test_get_cpu_stats() {
         // 测试获取 CPU 统计信息
-        match get_cpu_stats().await {
+        match Collector::default().get_cpu_stats(false).await {
             Ok(stats) => {
                 assert!(stats.core_count > 0);
+                #[cfg(feature = "per-core")]
                 assert!(stats.per_core.len() == stats.core_count);
+                #[cfg(not(feature = "per-core"))]
+                assert!(stats.per_core.is_empty());
                 assert!(stats.overall.total_percent >= 0.0 && stats.overall.total_percent <= 100.0);
 
                 // 检查各个分量的合理性
@@ -0,0 +1,207 @@
+//! 标准输入输出（stdio）交互模式
+//!
+//! 作为子进程被父进程通过管道驱动，无需网络栈。
+//!
+//! 帧格式（JSON Lines）：
+//!
+//! - 请求：stdin 中的一行 UTF-8 文本，即一个命令，首尾空白会被忽略，空行被跳过；
+//!   - `stats`：返回当前系统统计数据（遵循缓存 TTL）；
+//!   - `ping`：存活检测；
+//!   - `quit`：结束会话（stdin 关闭时同样结束）。
+//! - 响应：stdout 中的一行 JSON 对象，以 `\n` 结尾。除空行与 `quit` 不产生响应外，
+//!   每个请求（包括无法识别的命令）恰好对应一个响应：
+//!   - 成功：`{"ok":true,"type":"stats","data":{...}}` 或 `{"ok":true,"type":"pong"}`；
+//!   - 失败：`{"ok":false,"error":"..."}`。
+//!
+//! 日志始终输出到 stderr，不会污染 stdout 中的响应帧。
+
+use crate::cache::CacheRef;
+use crate::stats::SystemStats;
+use anyhow::Result;
+use log::{debug, info};
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// stdio 模式下的请求
+#[derive(Debug, PartialEq, Eq)]
+enum StdioRequest {
+    Stats,
+    Ping,
+    Quit,
+}
+
+impl StdioRequest {
+    /// 解析一行请求，空行返回 `Ok(None)`
+    fn parse(line: &str) -> std::result::Result<Option<Self>, String> {
+        match line.trim() {
+            "" => Ok(None),
+            "stats" => Ok(Some(Self::Stats)),
+            "ping" => Ok(Some(Self::Ping)),
+            "quit" => Ok(Some(Self::Quit)),
+            other => Err(format!("未知命令: {other}")),
+        }
+    }
+}
+
+/// stdio 模式下的响应帧
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum StdioResponse<'a> {
+    Stats {
+        ok: bool,
+        #[serde(rename = "type")]
+        kind: &'static str,
+        data: &'a SystemStats,
+    },
+    Pong {
+        ok: bool,
+        #[serde(rename = "type")]
+        kind: &'static str,
+    },
+    Error {
+        ok: bool,
+        error: String,
+    },
+}
+
+impl StdioResponse<'_> {
+    #[inline]
+    fn error(message: String) -> Self {
+        Self::Error {
+            ok: false,
+            error: message,
+        }
+    }
+
+    /// 序列化为一行 JSON（含结尾换行符）
+    fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_else(|e| {
+            format!(
+                "{{\"ok\":false,\"error\":{}}}",
+                serde_json::Value::String(format!("序列化失败: {e}"))
+            )
+        });
+        line.push('\n');
+        line
+    }
+}
+
+/// 处理一行请求，返回需要写入 stdout 的响应行（空行与 `quit` 无响应）以及是否结束会话
+async fn handle_line(line: &str, cache: &CacheRef) -> (Option<String>, bool) {
+    match StdioRequest::parse(line) {
+        Ok(None) => (None, false),
+        Ok(Some(StdioRequest::Quit)) => (None, true),
+        Ok(Some(StdioRequest::Ping)) => (
+            Some(
+                StdioResponse::Pong {
+                    ok: true,
+                    kind: "pong",
+                }
+                .to_line(),
+            ),
+            false,
+        ),
+        Ok(Some(StdioRequest::Stats)) => match cache.get_or_update().await {
+            Ok(stats) => (
+                Some(
+                    StdioResponse::Stats {
+                        ok: true,
+                        kind: "stats",
+                        data: &stats,
+                    }
+                    .to_line(),
+                ),
+                false,
+            ),
+            Err(e) => (
+                Some(StdioResponse::error(format!("数据获取失败: {e}")).to_line()),
+                false,
+            ),
+        },
+        Err(message) => (Some(StdioResponse::error(message).to_line()), false),
+    }
+}
+
+/// 运行 stdio 模式，直到收到 `quit` 或 stdin 关闭
+pub async fn run(cache: CacheRef) -> Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    info!("stdio 模式已启动，等待 stdin 请求");
+
+    while let Some(line) = lines.next_line().await? {
+        debug!("stdio 请求: {line}");
+        let (response, quit) = handle_line(&line, &cache).await;
+        if let Some(response) = response {
+            stdout.write_all(response.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+        if quit {
+            break;
+        }
+    }
+
+    info!("stdio 模式结束");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::create_cache;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(StdioRequest::parse("stats"), Ok(Some(StdioRequest::Stats)));
+        assert_eq!(
+            StdioRequest::parse("  ping \r"),
+            Ok(Some(StdioRequest::Ping))
+        );
+        assert_eq!(StdioRequest::parse("quit"), Ok(Some(StdioRequest::Quit)));
+        assert_eq!(StdioRequest::parse(""), Ok(None));
+        assert!(StdioRequest::parse("reboot").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_line_ping() {
+        let cache = create_cache(10);
+        let (response, quit) = handle_line("ping", &cache).await;
+        assert!(!quit);
+        assert_eq!(response.unwrap(), "{\"ok\":true,\"type\":\"pong\"}\n");
+    }
+
+    #[tokio::test]
+    async fn test_handle_line_stats() {
+        let cache = create_cache(10);
+        let stats = SystemStats {
+            hostname: "stdio-test".to_string(),
+            ..SystemStats::default()
+        };
+        cache.update(stats);
+
+        let (response, quit) = handle_line("stats", &cache).await;
+        assert!(!quit);
+        let response = response.unwrap();
+        assert!(response.ends_with('\n'));
+
+        let value: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(value["ok"], true);
+        assert_eq!(value["type"], "stats");
+        assert_eq!(value["data"]["hostname"], "stdio-test");
+    }
+
+    #[tokio::test]
+    async fn test_handle_line_unknown_and_quit() {
+        let cache = create_cache(10);
+
+        let (response, quit) = handle_line("unknown", &cache).await;
+        assert!(!quit);
+        let value: serde_json::Value = serde_json::from_str(&response.unwrap()).unwrap();
+        assert_eq!(value["ok"], false);
+        assert!(value["error"].as_str().unwrap().contains("unknown"));
+
+        let (response, quit) = handle_line("quit", &cache).await;
+        assert!(quit);
+        assert!(response.is_none());
+    }
+}
@@ -0,0 +1,363 @@
+//! 基于 fixture 的采集端到端测试
+//!
+//! 测试把 `tests/fixtures` 下的 proc 文件复制到临时目录作为采集器的 proc 根目录，跑完整的采集流程，
+//! 断言每个字段都与按 fixture 手算的预期值一致。
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::ProcDir;
+use std::fs;
+use std::time::{Duration, Instant};
+use swb_sys_monitor::SystemStatsCache;
+use swb_sys_monitor::breaker::{BreakerState, CircuitBreaker};
+use swb_sys_monitor::budget::CpuBudget;
+use swb_sys_monitor::cache::RefreshState;
+use swb_sys_monitor::stats::{Collector, CpuUsageBreakdown, SubsystemStatus};
+
+fn assert_breakdown(actual: &CpuUsageBreakdown, user: f32, nice: f32, system: f32, total: f32) {
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+    assert!(
+        close(actual.user_percent, user)
+            && close(actual.nice_percent, nice)
+            && close(actual.system_percent, system)
+            && close(actual.total_percent, total),
+        "实际 {actual:?}，预期 user={user} nice={nice} system={system} total={total}"
+    );
+}
+
+#[tokio::test]
+async fn test_collect_from_fixture() {
+    let proc_dir = ProcDir::new("collect");
+    let collector = Collector::new(&proc_dir.0);
+
+    let before = swb_sys_monitor::stats::unix_millis();
+    let first = collector.collect().await.unwrap();
+    assert_eq!(first.hostname, "fixture-host");
+    assert!((before..=swb_sys_monitor::stats::unix_millis()).contains(&first.collected_at_unix_ms));
+    assert_eq!(first.memory_total, 2_048_000 * 1024);
+    assert_eq!(first.memory_free, 512_000 * 1024);
+    assert_eq!(first.memory_available, 1_024_000 * 1024);
+    assert_eq!(first.memory_cached, 256_000 * 1024);
+    assert_eq!(first.memory_used, (2_048_000 - 1_024_000) * 1024);
+    assert_eq!(first.cpu_stats.core_count, 2);
+
+    // 第一次采样没有上一次数据，使用率为 0
+    assert_eq!(first.cpu_usage, 0.0);
+    assert_breakdown(&first.cpu_stats.overall, 0.0, 0.0, 0.0, 0.0);
+
+    #[cfg(feature = "conntrack")]
+    {
+        let conntrack = first.conntrack.as_ref().unwrap();
+        assert_eq!((conntrack.count, conntrack.max), (300, 1000));
+        assert!((conntrack.usage_percent - 30.0).abs() < 1e-4);
+    }
+    #[cfg(not(feature = "conntrack"))]
+    assert!(first.conntrack.is_none());
+
+    // fixture 中各子系统的数据齐全，启用的均为正常
+    let expected = |enabled| {
+        if enabled {
+            SubsystemStatus::Ok
+        } else {
+            SubsystemStatus::Disabled
+        }
+    };
+    assert_eq!(
+        first.subsystems["per_core"],
+        expected(cfg!(feature = "per-core"))
+    );
+    assert_eq!(
+        first.subsystems["conntrack"],
+        expected(cfg!(feature = "conntrack"))
+    );
+    assert_eq!(
+        first.subsystems["interrupts"],
+        expected(cfg!(feature = "interrupts"))
+    );
+
+    // 第二次采样：总体 user +300 nice +100 system +100 idle +500，共 1000
+    proc_dir.advance_stat();
+    let second = collector.collect().await.unwrap();
+    assert_breakdown(&second.cpu_stats.overall, 30.0, 10.0, 10.0, 50.0);
+    assert!((second.cpu_usage - 0.5).abs() < 1e-6);
+
+    #[cfg(feature = "per-core")]
+    {
+        let cores = &second.cpu_stats.per_core;
+        assert_eq!(cores.len(), 2);
+        // cpu0: user +250 system +50 idle +200，共 500
+        assert_breakdown(&cores[0], 50.0, 0.0, 10.0, 60.0);
+        // cpu1: user +50 nice +100 system +50 idle +300，共 500
+        assert_breakdown(&cores[1], 10.0, 20.0, 10.0, 40.0);
+    }
+    #[cfg(not(feature = "per-core"))]
+    assert!(second.cpu_stats.per_core.is_empty());
+    assert_eq!(second.cpu_stats.core_count, 2);
+}
+
+#[cfg(feature = "interrupts")]
+#[tokio::test]
+async fn test_interrupts_from_fixture() {
+    let proc_dir = ProcDir::new("interrupts");
+    let collector = Collector::new(&proc_dir.0);
+    assert!(
+        collector
+            .collect()
+            .await
+            .unwrap()
+            .interrupts_by_cpu
+            .is_none()
+    );
+
+    proc_dir.advance_stat();
+    let stats = collector.collect().await.unwrap();
+    let interrupts = stats.interrupts_by_cpu.unwrap();
+    assert_eq!(interrupts.cpus, vec![0, 1]);
+    // cpu0: eth0 +49000 LOC +1000；cpu1: eth0 +100 LOC +1000，速率之比与增量之比一致
+    let [cpu0, cpu1] = interrupts.per_cpu_per_second[..] else {
+        panic!("应有两个 CPU");
+    };
+    assert!((cpu0 / cpu1 - 50_000.0 / 1_100.0).abs() < 1e-6);
+    assert_eq!(interrupts.top_sources[0].irq, "24");
+    assert_eq!(
+        interrupts.top_sources[0].description,
+        "PCI-MSI 524288-edge eth0"
+    );
+    assert_eq!(interrupts.hotspot_cpus, vec![0]);
+}
+
+#[tokio::test]
+async fn test_collectors_keep_independent_cpu_state() {
+    let proc_dir = ProcDir::new("independent");
+    let warmed = Collector::new(&proc_dir.0);
+    warmed.collect().await.unwrap();
+
+    proc_dir.advance_stat();
+    // 新采集器没有上一次采样，不受另一个采集器的影响
+    let fresh = Collector::new(&proc_dir.0).collect().await.unwrap();
+    assert_eq!(fresh.cpu_usage, 0.0);
+    let second = warmed.collect().await.unwrap();
+    assert!((second.cpu_usage - 0.5).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_collect_with_reused_fds_from_fixture() {
+    let proc_dir = ProcDir::new("reused-fds");
+    let collector = Collector::new(&proc_dir.0).with_reused_fds(true);
+    let first = collector.collect().await.unwrap();
+    assert_eq!(first.memory_total, 2_048_000 * 1024);
+
+    // fixture 被原地改写，复用的句柄从头重读到新内容
+    proc_dir.advance_stat();
+    let second = collector.collect().await.unwrap();
+    assert_breakdown(&second.cpu_stats.overall, 30.0, 10.0, 10.0, 50.0);
+    assert_eq!(second.memory_available, 1_024_000 * 1024);
+}
+
+#[tokio::test]
+async fn test_collect_over_cpu_budget_skips_heavy_subsystems() {
+    let proc_dir = ProcDir::new("cpu-budget");
+    // 预算为 0：两次采集之间只要本进程消耗了 CPU 时间即超出
+    let collector =
+        Collector::new(&proc_dir.0).with_cpu_budget(CpuBudget::new(0.0, Duration::from_secs(60)));
+    let first = collector.collect().await.unwrap();
+    assert_ne!(first.subsystems["per_core"], SubsystemStatus::Throttled);
+
+    let spin = Instant::now();
+    while spin.elapsed() < Duration::from_millis(100) {
+        std::hint::black_box(spin.elapsed());
+    }
+    proc_dir.advance_stat();
+    let second = collector.collect().await.unwrap();
+    // 总体使用率照常计算，较重的子采集被跳过
+    assert_breakdown(&second.cpu_stats.overall, 30.0, 10.0, 10.0, 50.0);
+    assert!(second.cpu_stats.per_core.is_empty());
+    if cfg!(feature = "per-core") {
+        assert_eq!(second.subsystems["per_core"], SubsystemStatus::Throttled);
+    }
+    if cfg!(feature = "interrupts") {
+        assert_eq!(second.subsystems["interrupts"], SubsystemStatus::Throttled);
+    }
+}
+
+#[cfg(feature = "per-core")]
+#[tokio::test]
+async fn test_regressed_core_counters_read_zero() {
+    let proc_dir = ProcDir::new("regressed");
+    let collector = Collector::new(&proc_dir.0);
+    let stat_path = proc_dir.0.join("stat");
+    let stat = fs::read_to_string(&stat_path).unwrap();
+    collector.collect().await.unwrap();
+
+    // 核心 1 的 idle 回退
+    let regressed = stat.replace(
+        "cpu1 500 50 250 4000 100 25 25 0 0 0",
+        "cpu1 700 50 250 3900 100 25 25 0 0 0",
+    );
+    fs::write(&stat_path, &regressed).unwrap();
+    let stats = collector.collect().await.unwrap();
+    assert_breakdown(&stats.cpu_stats.per_core[1], 0.0, 0.0, 0.0, 0.0);
+
+    // 回退后的读数成为新基准，下一周期恢复正常
+    fs::write(
+        &stat_path,
+        regressed.replace(
+            "cpu1 700 50 250 3900 100 25 25 0 0 0",
+            "cpu1 800 50 250 4000 100 25 25 0 0 0",
+        ),
+    )
+    .unwrap();
+    let stats = collector.collect().await.unwrap();
+    assert_breakdown(&stats.cpu_stats.per_core[1], 50.0, 0.0, 0.0, 50.0);
+}
+
+#[cfg(feature = "per-core")]
+#[tokio::test]
+async fn test_offline_core_matched_by_id() {
+    let proc_dir = ProcDir::new("offline-core");
+    let stat_path = proc_dir.0.join("stat");
+    let stat = fs::read_to_string(&stat_path).unwrap();
+    // 让核心 0 与核心 1 的基准不同，按下标匹配时结果会出错
+    fs::write(
+        &stat_path,
+        stat.replace(
+            "cpu0 500 50 250 4000 100 25 25 0 0 0",
+            "cpu0 100 0 0 1000 0 0 0 0 0 0",
+        ),
+    )
+    .unwrap();
+    let cache = SystemStatsCache::new(Duration::from_secs(10))
+        .with_collector(Collector::new(&proc_dir.0))
+        .with_cpu_cores(vec![1]);
+    cache.refresh().await.unwrap();
+
+    // 核心 0 下线，/proc/stat 中只剩 cpu1
+    proc_dir.advance_stat();
+    let next = fs::read_to_string(&stat_path).unwrap();
+    fs::write(
+        &stat_path,
+        next.replace("cpu0 750 50 300 4200 100 25 25 0 0 0\n", ""),
+    )
+    .unwrap();
+    let stats = cache.refresh().await.unwrap();
+
+    assert_eq!(stats.cpu_stats.core_count, 1);
+    assert_eq!(stats.cpu_stats.core_ids, Some(vec![1]));
+    assert_eq!(stats.cpu_stats.per_core.len(), 1);
+    assert_breakdown(&stats.cpu_stats.per_core[0], 10.0, 20.0, 10.0, 40.0);
+}
+
+#[tokio::test]
+async fn test_cache_refresh_from_fixture() {
+    let proc_dir = ProcDir::new("cache");
+    let cache =
+        SystemStatsCache::new(Duration::from_secs(10)).with_collector(Collector::new(&proc_dir.0));
+
+    cache.refresh().await.unwrap();
+    proc_dir.advance_stat();
+    let stats = cache.refresh().await.unwrap();
+    assert!((stats.cpu_usage - 0.5).abs() < 1e-6);
+    assert!(stats.collection_interval.is_some());
+    assert_eq!(cache.get().unwrap().hostname, "fixture-host");
+}
+
+#[tokio::test]
+async fn test_breaker_recovers_after_probe_dropped_mid_collect() {
+    let proc_dir = ProcDir::new("dropped-probe");
+    let stat_path = proc_dir.0.join("stat");
+    let stat = fs::read(&stat_path).unwrap();
+    let cache = SystemStatsCache::new(Duration::from_secs(10))
+        .with_collector(Collector::new(&proc_dir.0))
+        .with_circuit_breaker(CircuitBreaker::new(1, Duration::ZERO));
+    let breaker = cache.breaker().unwrap();
+
+    // 读不到 /proc/stat，断路器打开
+    fs::remove_file(&stat_path).unwrap();
+    assert!(cache.refresh().await.is_err());
+    assert_eq!(breaker.snapshot().state, BreakerState::Open);
+
+    // 没有写端的 FIFO 让试探采集一直阻塞在打开文件上，超时后丢弃该采集
+    let fifo = std::ffi::CString::new(stat_path.to_str().unwrap()).unwrap();
+    // SAFETY: 路径是以 NUL 结尾的有效 C 字符串
+    assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+    let probe = tokio::time::timeout(Duration::from_millis(200), cache.refresh()).await;
+    // 先打开写端再关闭，让阻塞的读取结束，断言失败时测试不会卡住
+    fs::write(&stat_path, "").unwrap();
+    assert!(probe.is_err(), "试探采集应阻塞到超时");
+    assert_eq!(breaker.snapshot().state, BreakerState::Open);
+    assert_eq!(cache.refresh_state(), RefreshState::Failed);
+
+    // 恢复正常的 /proc/stat 后下一次试探成功
+    fs::remove_file(&stat_path).unwrap();
+    fs::write(&stat_path, stat).unwrap();
+    cache.refresh().await.unwrap();
+    assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+    assert_eq!(cache.refresh_state(), RefreshState::Fresh);
+}
+
+#[cfg(feature = "per-core")]
+#[tokio::test]
+async fn test_cache_cpu_core_subset_from_fixture() {
+    let proc_dir = ProcDir::new("cores");
+    let cache = SystemStatsCache::new(Duration::from_secs(10))
+        .with_collector(Collector::new(&proc_dir.0))
+        .with_cpu_cores(vec![1, 7]);
+
+    cache.refresh().await.unwrap();
+    proc_dir.advance_stat();
+    let stats = cache.refresh().await.unwrap();
+    // 不存在的核心 7 被忽略，总体使用率仍按全部核心计算
+    assert_eq!(stats.cpu_stats.core_ids, Some(vec![1]));
+    assert_eq!(stats.cpu_stats.core_count, 2);
+    assert_breakdown(&stats.cpu_stats.per_core[0], 10.0, 20.0, 10.0, 40.0);
+    assert_breakdown(&stats.cpu_stats.overall, 30.0, 10.0, 10.0, 50.0);
+}
+
+#[tokio::test]
+async fn test_collect_missing_files() {
+    let dir = std::env::temp_dir().join(format!("swb-fixture-empty-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    assert!(Collector::new(&dir).collect().await.is_err());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(feature = "conntrack")]
+#[tokio::test]
+async fn test_subsystem_failure_from_fixture() {
+    let proc_dir = ProcDir::new("subsystem-failure");
+    // 模拟未加载 nf_conntrack 模块
+    fs::remove_dir_all(proc_dir.0.join("sys/net/netfilter")).unwrap();
+    let stats = Collector::new(&proc_dir.0).collect().await.unwrap();
+    assert!(stats.conntrack.is_none());
+    assert_eq!(stats.subsystems["conntrack"], SubsystemStatus::Failed);
+}
+
+#[tokio::test]
+async fn test_cache_reports_custom_subsystem() {
+    let proc_dir = ProcDir::new("custom-subsystem");
+    let cache =
+        SystemStatsCache::new(Duration::from_secs(10)).with_collector(Collector::new(&proc_dir.0));
+    let stats = cache.refresh().await.unwrap();
+    assert_eq!(stats.subsystems["custom"], SubsystemStatus::Disabled);
+
+    #[cfg(feature = "custom")]
+    {
+        use swb_sys_monitor::custom::CustomCollector;
+        let collector = |command: &str| {
+            CustomCollector::new(vec![command.to_string()], Duration::from_secs(5), 1024)
+        };
+        let cache = SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new(&proc_dir.0))
+            .with_custom_collector(collector("echo a=1"));
+        let stats = cache.refresh().await.unwrap();
+        assert_eq!(stats.subsystems["custom"], SubsystemStatus::Ok);
+
+        let cache = SystemStatsCache::new(Duration::from_secs(10))
+            .with_collector(Collector::new(&proc_dir.0))
+            .with_custom_collector(collector("exit 1"));
+        let stats = cache.refresh().await.unwrap();
+        assert_eq!(stats.subsystems["custom"], SubsystemStatus::Failed);
+    }
+}
@@ -0,0 +1,69 @@
+//! 集成测试共用的辅助
+//!
+//! `tests/fixtures/proc` 是一份伪造的 proc 目录，`tests/fixtures/stat.next` 与
+//! `tests/fixtures/interrupts.next` 是第二次采样时的 /proc/stat 与 /proc/interrupts。
+
+// 每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use swb_sys_monitor::cache::CacheRef;
+use swb_sys_monitor::server::StatusServer;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// 临时 proc 目录，drop 时删除
+pub struct ProcDir(pub PathBuf);
+
+impl ProcDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("swb-fixture-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        copy_dir(&Path::new(FIXTURES).join("proc"), &dir);
+        Self(dir)
+    }
+
+    /// 用第二次采样的 /proc/stat 与 /proc/interrupts 替换当前内容
+    pub fn advance_stat(&self) {
+        for name in ["stat", "interrupts"] {
+            fs::copy(
+                Path::new(FIXTURES).join(format!("{name}.next")),
+                self.0.join(name),
+            )
+            .unwrap();
+        }
+    }
+}
+
+impl Drop for ProcDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// 在系统分配的本地端口上启动服务器，返回其监听地址
+///
+/// 端口在返回前已由服务器绑定，可以立即连接。需要在 tokio 运行时内调用。
+pub fn spawn_server(cache: CacheRef, cache_ttl_seconds: u64) -> SocketAddr {
+    let server = StatusServer::new_with_ttl(cache, cache_ttl_seconds)
+        .bind(&["127.0.0.1:0".parse().unwrap()])
+        .unwrap();
+    let addr = server.local_addrs()[0];
+    tokio::spawn(server.serve());
+    addr
+}
@@ -0,0 +1,192 @@
+//! HTTP/1.1 管道化请求测试
+//!
+//! 在同一连接上一次性写入多个请求，不等待响应，再按顺序解析返回的响应：
+//! 每个响应都应与对应位置的请求匹配（状态码、内容类型、正文），且服务器不 panic。
+//! 多个连接同时管道化时，请求之间共享的缓存与指标状态也不应串扰。
+
+mod common;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use swb_sys_monitor::SystemStats;
+use swb_sys_monitor::cache::create_cache;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const HOSTNAME: &str = "pipeline-host";
+
+/// 解析出的响应
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    content_type: String,
+    body: String,
+}
+
+/// 启动服务器，缓存中预置数据且 TTL 足够长，请求期间不会触发采集
+fn spawn_server() -> SocketAddr {
+    let cache = create_cache(3600);
+    cache.update(SystemStats {
+        hostname: HOSTNAME.to_string(),
+        cpu_usage: 0.25,
+        memory_total: 4096 * 1024 * 1024,
+        memory_used: 1024 * 1024 * 1024,
+        ..SystemStats::default()
+    });
+    common::spawn_server(cache, 3600)
+}
+
+/// 在一个连接上一次写入全部请求（最后一个请求要求关闭连接），读到 EOF 后按顺序解析响应
+async fn pipeline(addr: SocketAddr, paths: &[&str]) -> Vec<Response> {
+    let mut requests = String::new();
+    for (i, path) in paths.iter().enumerate() {
+        let connection = if i + 1 == paths.len() {
+            "close"
+        } else {
+            "keep-alive"
+        };
+        requests.push_str(&format!(
+            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: {connection}\r\n\r\n"
+        ));
+    }
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(requests.as_bytes()).await.unwrap();
+    let mut raw = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), stream.read_to_end(&mut raw))
+        .await
+        .expect("读取响应超时")
+        .unwrap();
+
+    let responses = parse_responses(&raw);
+    assert_eq!(responses.len(), paths.len(), "响应数量与请求数量不一致");
+    responses
+}
+
+/// 按 `Content-Length` 依次切分连续的响应
+fn parse_responses(mut raw: &[u8]) -> Vec<Response> {
+    let mut responses = Vec::new();
+    while !raw.is_empty() {
+        let head_end = raw
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .expect("响应头不完整");
+        let head = std::str::from_utf8(&raw[..head_end]).unwrap();
+        let mut lines = head.split("\r\n");
+        let status = lines
+            .next()
+            .unwrap()
+            .split(' ')
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        let mut content_type = String::new();
+        let mut content_length = None;
+        for line in lines {
+            let (name, value) = line.split_once(':').unwrap();
+            match name.to_ascii_lowercase().as_str() {
+                "content-type" => content_type = value.trim().to_string(),
+                "content-length" => content_length = Some(value.trim().parse().unwrap()),
+                _ => {}
+            }
+        }
+        let length: usize = content_length.expect("响应缺少 Content-Length");
+
+        let body_start = head_end + 4;
+        let body = std::str::from_utf8(&raw[body_start..body_start + length])
+            .unwrap()
+            .to_string();
+        raw = &raw[body_start + length..];
+        responses.push(Response {
+            status,
+            content_type,
+            body,
+        });
+    }
+    responses
+}
+
+/// 断言响应与请求路径匹配
+fn assert_matches(path: &str, response: &Response) {
+    let expected = match path {
+        "/" => (200, "text/html; charset=utf-8", HOSTNAME),
+        "/health" => (200, "text/plain", "OK"),
+        "/api/stats" => (200, "application/json", "\"cpu_usage_ratio\":0.25"),
+        "/metrics" => (
+            200,
+            "text/plain; version=0.0.4; charset=utf-8",
+            "swb_cpu_usage_ratio",
+        ),
+        _ => (404, "text/plain; charset=utf-8", ""),
+    };
+    assert_eq!(response.status, expected.0, "{path}: {response:?}");
+    assert_eq!(response.content_type, expected.1, "{path}: {response:?}");
+    assert!(response.body.contains(expected.2), "{path}: {response:?}");
+}
+
+#[tokio::test]
+async fn test_pipelined_requests_answered_in_order() {
+    let addr = spawn_server();
+    let paths = [
+        "/",
+        "/metrics",
+        "/missing",
+        "/health",
+        "/api/stats",
+        "/",
+        "/metrics",
+    ];
+    let responses = pipeline(addr, &paths).await;
+    for (path, response) in paths.iter().zip(&responses) {
+        assert_matches(path, response);
+    }
+
+    // 同一连接上的请求逐个计入连接复用指标：最后一个 /metrics 之前已处理 6 个请求
+    let metrics = &responses[6].body;
+    assert!(
+        metrics.contains("swb_http_connection_reused_requests_total 6\n"),
+        "{metrics}"
+    );
+    assert!(
+        metrics.contains("swb_http_requests_total{path=\"/\"} 2\n"),
+        "{metrics}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_pipelined_connections() {
+    let addr = spawn_server();
+    let paths: Vec<&str> = ["/", "/metrics", "/api/stats", "/health"]
+        .iter()
+        .copied()
+        .cycle()
+        .take(40)
+        .collect();
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let paths = paths.clone();
+            tokio::spawn(async move {
+                let responses = pipeline(addr, &paths).await;
+                for (path, response) in paths.iter().zip(&responses) {
+                    assert_matches(path, response);
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    // 所有连接结束后服务器仍正常响应
+    let responses = pipeline(addr, &["/metrics"]).await;
+    assert!(
+        responses[0]
+            .body
+            .contains("swb_http_connection_requests_total 321\n"),
+        "{}",
+        responses[0].body
+    );
+}
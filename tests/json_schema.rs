@@ -0,0 +1,141 @@
+//! `/api/stats` 的 JSON Schema 契约测试
+//!
+//! `tests/schemas/stats.schema.json` 描述对外承诺的字段名、类型与必填项，且不允许出现
+//! 未声明的字段。`/api/stats` 直接序列化 `SystemStats`，这里对同一序列化结果做校验：
+//! 重构时字段改名或类型变化会让测试失败，有意的格式变更需要同步修改 schema。
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use swb_sys_monitor::SystemStats;
+use swb_sys_monitor::anomaly::METRIC_CPU_USAGE;
+use swb_sys_monitor::stats::{
+    Anomaly, ConntrackStats, CpuUsageBreakdown, SUBSYSTEM_CONNTRACK, SUBSYSTEM_CUSTOM,
+    SUBSYSTEM_PER_CORE, SubsystemStatus,
+};
+
+const SCHEMA: &str = include_str!("schemas/stats.schema.json");
+
+fn validator() -> jsonschema::Validator {
+    let schema: Value = serde_json::from_str(SCHEMA).expect("schema 不是合法的 JSON");
+    jsonschema::validator_for(&schema).expect("schema 无效")
+}
+
+fn assert_valid(stats: &SystemStats) {
+    let instance = serde_json::to_value(stats).unwrap();
+    let errors: Vec<String> = validator()
+        .iter_errors(&instance)
+        .map(|e| format!("{}: {e}", e.instance_path()))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "不符合 schema：{errors:#?}\n{instance:#}"
+    );
+}
+
+/// 所有可选字段都有值的统计数据
+fn populated_stats() -> SystemStats {
+    let core = CpuUsageBreakdown {
+        user_percent: 30.0,
+        nice_percent: 5.0,
+        system_percent: 10.0,
+        total_percent: 45.0,
+    };
+    let mut stats = SystemStats {
+        hostname: "schema-host".to_string(),
+        cpu_usage: 0.45,
+        memory_total: 2048 * 1024 * 1024,
+        memory_used: 1024 * 1024 * 1024,
+        memory_available: 1024 * 1024 * 1024,
+        memory_cached: 256 * 1024 * 1024,
+        memory_free: 512 * 1024 * 1024,
+        conntrack: Some(ConntrackStats {
+            count: 300,
+            max: 1000,
+            usage_percent: 30.0,
+        }),
+        custom: BTreeMap::from([("queue_length".to_string(), 42.0)]),
+        subsystems: BTreeMap::from([
+            (SUBSYSTEM_PER_CORE, SubsystemStatus::Ok),
+            (SUBSYSTEM_CONNTRACK, SubsystemStatus::Failed),
+            (SUBSYSTEM_CUSTOM, SubsystemStatus::Disabled),
+        ]),
+        anomalies: BTreeMap::from([(
+            METRIC_CPU_USAGE,
+            Anomaly {
+                value: 45.0,
+                mean: 10.0,
+                stddev: 2.5,
+            },
+        )]),
+        clock_synced: true,
+        clock_estimated_error: Some(Duration::from_micros(1500)),
+        collection_interval: Some(Duration::from_secs(10)),
+        sequence: 42,
+        collected_at_unix_ms: 1_700_000_000_000,
+        ..SystemStats::default()
+    };
+    stats.cpu_stats.overall = core.clone();
+    stats.cpu_stats.per_core = vec![core.clone(), core];
+    stats.cpu_stats.core_count = 4;
+    stats.cpu_stats.core_ids = Some(vec![1, 3]);
+    #[cfg(feature = "interrupts")]
+    {
+        use swb_sys_monitor::interrupts::{InterruptSourceRate, InterruptStats};
+        stats.interrupts_by_cpu = Some(InterruptStats {
+            cpus: vec![0, 1],
+            per_cpu_per_second: vec![5000.0, 100.0],
+            top_sources: vec![InterruptSourceRate {
+                irq: "24".to_string(),
+                description: "PCI-MSI 524288-edge eth0".to_string(),
+                per_cpu_per_second: vec![4900.0, 0.0],
+                total_per_second: 4900.0,
+            }],
+            hotspot_cpus: vec![0],
+        });
+    }
+    stats
+}
+
+#[test]
+fn test_schema_accepts_default_stats() {
+    assert_valid(&SystemStats::default());
+}
+
+#[test]
+fn test_schema_accepts_populated_stats() {
+    assert_valid(&populated_stats());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_schema_accepts_collected_stats() {
+    let proc_root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc");
+    let collector = swb_sys_monitor::stats::Collector::new(proc_root);
+    assert_valid(&collector.collect().await.unwrap());
+    assert_valid(&collector.collect().await.unwrap());
+}
+
+#[test]
+fn test_schema_rejects_contract_changes() {
+    let validator = validator();
+    let valid = serde_json::to_value(populated_stats()).unwrap();
+    assert!(validator.is_valid(&valid));
+
+    // 字段改名：缺少必填项且出现未声明的字段
+    let mut renamed = valid.clone();
+    let object = renamed.as_object_mut().unwrap();
+    let value = object.remove("memory_used_bytes").unwrap();
+    object.insert("memory_used".to_string(), value);
+    assert!(!validator.is_valid(&renamed));
+
+    // 类型变化
+    let mut retyped = valid.clone();
+    retyped["cpu_stats"]["core_count"] = Value::from("2");
+    assert!(!validator.is_valid(&retyped));
+
+    // 嵌套对象变为裸数字
+    let mut reshaped = valid;
+    reshaped["conntrack"] = Value::from(300);
+    assert!(!validator.is_valid(&reshaped));
+}
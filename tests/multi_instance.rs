@@ -0,0 +1,123 @@
+//! 同进程多实例隔离测试
+//!
+//! 在一个进程中启动两套完整的监控实例（各自的 proc 根目录、采集器、缓存与 HTTP 服务器），
+//! 分别绑定不同端口。一个实例的 CPU 采样基准、采集序号与请求指标都不应影响另一个实例。
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::ProcDir;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use swb_sys_monitor::SystemStatsCache;
+use swb_sys_monitor::cache::CacheRef;
+use swb_sys_monitor::stats::Collector;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// 一个完整的监控实例
+struct Instance {
+    proc_dir: ProcDir,
+    cache: CacheRef,
+    addr: SocketAddr,
+}
+
+impl Instance {
+    /// 以独立的 proc 目录（主机名为 `hostname`）创建实例并启动服务器，TTL 足够长，请求期间不会触发采集
+    fn spawn(hostname: &str) -> Self {
+        let proc_dir = ProcDir::new(hostname);
+        std::fs::write(
+            proc_dir.0.join("sys/kernel/hostname"),
+            format!("{hostname}\n"),
+        )
+        .unwrap();
+        let cache = Arc::new(
+            SystemStatsCache::new(Duration::from_secs(3600))
+                .with_collector(Collector::new(&proc_dir.0)),
+        );
+
+        let addr = common::spawn_server(cache.clone(), 3600);
+        Self {
+            proc_dir,
+            cache,
+            addr,
+        }
+    }
+
+    /// 发送一个 GET 请求并返回响应正文
+    async fn get(&self, path: &str) -> String {
+        let mut stream = TcpStream::connect(self.addr).await.unwrap();
+        stream
+            .write_all(
+                format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut raw = String::new();
+        tokio::time::timeout(Duration::from_secs(10), stream.read_to_string(&mut raw))
+            .await
+            .expect("读取响应超时")
+            .unwrap();
+        let (head, body) = raw.split_once("\r\n\r\n").expect("响应头不完整");
+        assert!(head.starts_with("HTTP/1.1 200"), "{path}: {head}");
+        body.to_string()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_instances_are_isolated() {
+    let a = Instance::spawn("instance-a");
+    let b = Instance::spawn("instance-b");
+
+    // 两个实例同时建立各自的 CPU 基准
+    let (first_a, first_b) = tokio::join!(a.cache.refresh(), b.cache.refresh());
+    assert_eq!(first_a.unwrap().hostname, "instance-a");
+    assert_eq!(first_b.unwrap().hostname, "instance-b");
+
+    // 只推进 A 的 /proc/stat：A 按自身基准得到 50%，B 的基准与读数均未变化
+    a.proc_dir.advance_stat();
+    let stats_a = a.cache.refresh().await.unwrap();
+    let stats_b = b.cache.refresh().await.unwrap();
+    assert!(
+        (stats_a.cpu_usage - 0.5).abs() < 1e-6,
+        "{}",
+        stats_a.cpu_usage
+    );
+    assert_eq!(stats_b.cpu_usage, 0.0);
+
+    // 再推进 B：B 的增量只与它自己的上一次采样有关
+    b.proc_dir.advance_stat();
+    let stats_b = b.cache.refresh().await.unwrap();
+    let stats_a = a.cache.refresh().await.unwrap();
+    assert!(
+        (stats_b.cpu_usage - 0.5).abs() < 1e-6,
+        "{}",
+        stats_b.cpu_usage
+    );
+    assert_eq!(stats_a.cpu_usage, 0.0);
+    assert_eq!(stats_a.sequence, 3);
+    assert_eq!(stats_b.sequence, 3);
+
+    // 各端口只返回本实例缓存中的数据
+    for _ in 0..3 {
+        let body = a.get("/api/stats").await;
+        assert!(body.contains("\"hostname\":\"instance-a\""), "{body}");
+    }
+    let body = b.get("/api/stats").await;
+    assert!(body.contains("\"hostname\":\"instance-b\""), "{body}");
+
+    // 请求指标按实例分别计数
+    let metrics_a = a.get("/metrics").await;
+    let metrics_b = b.get("/metrics").await;
+    assert!(
+        metrics_a.contains("swb_http_requests_total{path=\"/api/stats\"} 3\n"),
+        "{metrics_a}"
+    );
+    assert!(
+        metrics_b.contains("swb_http_requests_total{path=\"/api/stats\"} 1\n"),
+        "{metrics_b}"
+    );
+}